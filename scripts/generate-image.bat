@@ -1,2 +1,2 @@
-::C:\Users\jorge\Documents\raytracing-rust\target\debug\raytracing-rust.exe > image.ppm
-C:\Users\jorge\Documents\raytracing-rust\target\release\raytracing-rust.exe > image.ppm
\ No newline at end of file
+::C:\Users\jorge\Documents\raytracing-rust\target\debug\raytracing-rust.exe render --output image.ppm
+C:\Users\jorge\Documents\raytracing-rust\target\release\raytracing-rust.exe render --output image.ppm
\ No newline at end of file
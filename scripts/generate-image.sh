@@ -1,3 +1,3 @@
 #!/bin/sh
 cd ..
-./target/release/raytracing-rust > image.ppm
\ No newline at end of file
+./target/release/raytracing-rust render --output image.ppm
\ No newline at end of file
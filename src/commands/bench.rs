@@ -0,0 +1,51 @@
+use std::any;
+
+use raytracing_rust::math::Float;
+use raytracing_rust::rendering::{self, Film, RenderSettings};
+use raytracing_rust::scene::presets::Preset;
+use raytracing_rust::Result;
+
+use crate::input::BenchArgs;
+
+/// Renders every requested scene with a fixed seed and prints a table of the rendering speed and
+/// the memory of the scenes. Build with and without the `f32` feature to compare precisions, and
+/// run with and without `--packets` to compare the traversals.
+pub fn bench(args: BenchArgs) -> Result<()> {
+    let scenes = match args.scenes.is_empty() {
+        true => Preset::ALL.to_vec(),
+        false => args.scenes,
+    };
+    let settings = RenderSettings {
+        image_width: args.width,
+        image_height: args.height,
+        samples_per_pixel: args.samples,
+        packets: args.packets,
+        ..RenderSettings::default()
+    };
+
+    let mut results = Vec::with_capacity(scenes.len());
+    for preset in scenes {
+        let scene = preset.build()?;
+        let memory = scene.stats().memory;
+        let mut film = Film::new(settings.image_width, settings.image_height);
+        let stats = rendering::render_progressive(&settings, &scene, &mut film, |_| {}, |_| {})?;
+        results.push((preset, stats, memory));
+    }
+
+    println!("precision: {}", any::type_name::<Float>());
+    println!(
+        "{:<16}{:>12}{:>14}{:>12}{:>12}",
+        "scene", "time", "rays", "Mrays/s", "KiB"
+    );
+    for (preset, stats, memory) in results {
+        println!(
+            "{:<16}{:>12}{:>14}{:>12.2}{:>12.1}",
+            preset.name(),
+            format!("{:.2?}", stats.elapsed),
+            stats.rays,
+            stats.rays_per_second() / 1_000_000.0,
+            memory as f64 / 1024.0
+        );
+    }
+    Ok(())
+}
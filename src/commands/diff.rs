@@ -0,0 +1,34 @@
+use raytracing_rust::compare::{compare, error_map};
+use raytracing_rust::output::image::{read_image, ImageFormat, OutputOptions};
+use raytracing_rust::Result;
+
+use super::save_image;
+use crate::input::DiffArgs;
+
+/// Prints the error metrics of an image against its reference, and writes the error map if asked.
+pub fn diff(args: DiffArgs) -> Result<()> {
+    let error_format = args
+        .error_output
+        .as_ref()
+        .map(|path| ImageFormat::for_output(path, None))
+        .transpose()?;
+    let reference = read_image(&args.reference)?;
+    let image = read_image(&args.image)?;
+
+    let metrics = compare(&reference, &image)?;
+    println!("MSE:    {:.6e}", metrics.mse);
+    println!("relMSE: {:.6e}", metrics.rel_mse);
+    println!("PSNR:   {:.2} dB", metrics.psnr);
+    println!("SSIM:   {:.4}", metrics.ssim);
+    println!("FLIP:   {:.4}", metrics.flip);
+
+    if let (Some(path), Some(format)) = (&args.error_output, error_format) {
+        let map = error_map(&reference, &image, args.error_map)?;
+        let options = OutputOptions {
+            format,
+            ..OutputOptions::default()
+        };
+        save_image(path, &map, &options)?;
+    }
+    Ok(())
+}
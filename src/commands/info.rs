@@ -0,0 +1,17 @@
+use raytracing_rust::Result;
+
+use crate::input::InfoArgs;
+
+/// Prints what the scene is made of.
+pub fn info(args: InfoArgs) -> Result<()> {
+    let stats = args.scene.build()?.stats();
+    println!("Scene:      {}", args.scene.name());
+    println!("Primitives: {}", stats.primitive_count());
+    for (kind, count) in &stats.primitives {
+        println!("  {:<10}{}", kind, count);
+    }
+    println!("Lights:     {}", stats.lights);
+    println!("BVH depth:  {}", stats.bvh_depth);
+    println!("Memory:     {:.1} KiB", stats.memory as f64 / 1024.0);
+    Ok(())
+}
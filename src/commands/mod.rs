@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use raytracing_rust::output::image::{write_image, OutputOptions};
+use raytracing_rust::rendering::Film;
+use raytracing_rust::Result;
+
+pub use bench::bench;
+pub use diff::diff;
+pub use info::info;
+pub use render::render;
+
+mod bench;
+mod diff;
+mod info;
+mod render;
+
+/// Writes the film with `write_image` and tells where it went, unless that was stdout.
+fn save_image(path: &Path, film: &Film, options: &OutputOptions) -> Result<()> {
+    write_image(path, film, options)?;
+    if path.as_os_str() != "-" {
+        eprintln!("created file {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use std::time::{Duration, Instant};
+
+use raytracing_rust::math::random_seed;
+use raytracing_rust::output::aov::Aov;
+use raytracing_rust::output::checkpoint::{read_checkpoint, write_checkpoint};
+use raytracing_rust::output::display::DisplayTransform;
+use raytracing_rust::output::image::{ImageFormat, OutputOptions};
+use raytracing_rust::rendering::{
+    self, denoise, DenoiseSettings, Denoiser, Film, RenderSettings, TileStats,
+};
+use raytracing_rust::{Error, Result};
+
+use super::save_image;
+use crate::input::RenderArgs;
+use crate::print_diagnostic;
+
+/// Renders a scene, picking up from a checkpoint if asked to, and writes the image.
+pub fn render(args: RenderArgs) -> Result<()> {
+    // Image
+    // let aspect_ratio = 16.0 / 9.0;
+    let image_width: usize = args.width;
+    // let image_height = (image_width as f64 / aspect_ratio) as usize;
+    let image_height = args.height;
+    let samples_per_pixel = args.samples;
+    let max_depth = args.diffuse_bounces;
+
+    let scene = args.scene.build()?;
+
+    // Output, checked before rendering so a typo doesn't throw away the render
+    let format = ImageFormat::for_output(&args.output, args.format)?;
+    if args.noisy_output.is_some() && args.denoiser == Denoiser::None {
+        return Err(Error::InvalidSettings(
+            "--noisy-output needs a denoiser, without one it is the same image as --output"
+                .to_string(),
+        ));
+    }
+    let noisy_format = args
+        .noisy_output
+        .as_ref()
+        .map(|path| ImageFormat::for_output(path, None))
+        .transpose()?;
+    let aovs = match args.all_aovs {
+        true => Aov::ALL.to_vec(),
+        false => args.aovs.clone(),
+    };
+    if !aovs.is_empty() && format != ImageFormat::Exr {
+        eprintln!("Render passes are only written to EXR output, ignoring --aov");
+    }
+    let writes_aovs = format == ImageFormat::Exr && aovs.iter().any(Aov::needs_aov_buffers);
+    let needs_aov_buffers = args.denoiser != Denoiser::None || writes_aovs;
+    let output_options = OutputOptions {
+        format,
+        display: DisplayTransform {
+            exposure: args.exposure,
+            tone_mapping: args.tone_map,
+        },
+        bit_depth: args.bit_depth,
+        ppm_encoding: args.ppm_encoding,
+        aovs,
+    };
+
+    // Film, restored from the checkpoint when resuming
+    let (mut film, seed, max_depth) = match (&args.checkpoint, args.resume) {
+        (Some(path), true) => {
+            let checkpoint = read_checkpoint(path)?;
+            checkpoint.check_resumable(
+                args.scene.name(),
+                image_width,
+                image_height,
+                args.spectral,
+            )?;
+            if args.seed.is_some_and(|seed| seed != checkpoint.seed) {
+                eprintln!(
+                    "Ignoring --seed, resuming with the checkpoint seed {}",
+                    checkpoint.seed
+                );
+            }
+            if max_depth != checkpoint.max_depth {
+                eprintln!(
+                    "Ignoring --diffuse-bounces, resuming with the checkpoint value {}",
+                    checkpoint.max_depth
+                );
+            }
+            if writes_aovs && checkpoint.film.aovs().is_none() {
+                return Err(Error::InvalidSettings(
+                    "the checkpoint was rendered without render passes, resume it without --aov"
+                        .to_string(),
+                ));
+            }
+            if args.denoiser != Denoiser::None && checkpoint.film.aovs().is_none() {
+                eprintln!(
+                    "The checkpoint was rendered without render passes, the image won't be denoised"
+                );
+            }
+            eprintln!(
+                "Resuming from {} at {} samples",
+                path.display(),
+                checkpoint.film.min_samples()
+            );
+            (checkpoint.film, checkpoint.seed, checkpoint.max_depth)
+        }
+        _ if needs_aov_buffers => (
+            Film::with_aovs(image_width, image_height),
+            args.seed.unwrap_or_else(random_seed),
+            max_depth,
+        ),
+        _ => (
+            Film::new(image_width, image_height),
+            args.seed.unwrap_or_else(random_seed),
+            max_depth,
+        ),
+    };
+
+    let samples_per_pass = args.pass_samples.unwrap_or(match args.checkpoint {
+        Some(_) => 4,
+        None => 0,
+    });
+    let settings = RenderSettings {
+        image_width,
+        image_height,
+        samples_per_pixel,
+        samples_per_pass,
+        max_depth,
+        seed,
+        tile_size: args.tile_size,
+        tile_order: args.tile_order,
+        spectral: args.spectral,
+        ..RenderSettings::default()
+    };
+    settings.validate()?;
+
+    let checkpoint_interval = Duration::from_secs(args.checkpoint_interval);
+    let mut last_checkpoint = Instant::now();
+    let report_tile = |stats: &TileStats| {
+        if args.tile_stats {
+            eprintln!(
+                "Tile ({}, {}) {}x{}: {} rays in {:.2?} ({:.2} Mrays/s)",
+                stats.tile.x,
+                stats.tile.y,
+                stats.tile.width,
+                stats.tile.height,
+                stats.rays,
+                stats.elapsed,
+                stats.rays_per_second() / 1_000_000.0
+            );
+        }
+    };
+    let stats = rendering::render_progressive(&settings, &scene, &mut film, report_tile, |film| {
+        eprintln!(
+            "Pass done: {}/{} samples",
+            film.min_samples(),
+            samples_per_pixel
+        );
+        let Some(path) = &args.checkpoint else {
+            return;
+        };
+        let finished = film.min_samples() >= samples_per_pixel as u32;
+        if finished || last_checkpoint.elapsed() >= checkpoint_interval {
+            // Keep rendering if a checkpoint can't be written, the next one may succeed
+            match write_checkpoint(
+                path,
+                film,
+                args.scene.name(),
+                seed,
+                max_depth,
+                args.spectral,
+            ) {
+                Ok(()) => eprintln!("Saved checkpoint to {}", path.display()),
+                Err(error) => print_diagnostic("Warning", &error),
+            }
+            last_checkpoint = Instant::now();
+        }
+    })?;
+    eprintln!(
+        "Rendered {} tiles, {} rays in {:.2?} ({:.2} Mrays/s)",
+        stats.tiles,
+        stats.rays,
+        stats.elapsed,
+        stats.rays_per_second() / 1_000_000.0
+    );
+
+    let denoised = match args.denoiser {
+        Denoiser::None => None,
+        Denoiser::Atrous => {
+            let settings = DenoiseSettings {
+                iterations: args.denoise_iterations,
+                ..DenoiseSettings::default()
+            };
+            denoise(&film, &settings)
+        }
+    };
+    if let (Some(path), Some(format)) = (&args.noisy_output, noisy_format) {
+        let noisy_options = OutputOptions {
+            format,
+            ..output_options.clone()
+        };
+        save_image(path, &film, &noisy_options)?;
+    }
+    save_image(
+        &args.output,
+        denoised.as_ref().unwrap_or(&film),
+        &output_options,
+    )?;
+    eprintln!("Done");
+    Ok(())
+}
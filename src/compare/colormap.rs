@@ -0,0 +1,37 @@
+use crate::clamp;
+use crate::math::{Color, Float};
+
+/// Magma color map, black through purple and orange to pale yellow, as an sRGB encoded color.
+/// Polynomial fit by Matt Zucker.
+pub fn magma(value: Float) -> Color {
+    const COEFFICIENTS: [[Float; 3]; 7] = [
+        [
+            -0.002136485053939582,
+            -0.000749655052795221,
+            -0.005386127855323933,
+        ],
+        [0.2516605407371642, 0.6775232436837668, 2.494026599312351],
+        [8.353717279216625, -3.577719514958484, 0.3144679030132573],
+        [-27.66873308576866, 14.26473078096533, -13.64921318813922],
+        [52.17613981234068, -27.94360607168351, 12.94416944238394],
+        [-50.76852536473588, 29.04658282127291, 4.23415299384598],
+        [18.65570506591883, -11.48977351997711, -5.601961508734096],
+    ];
+    let t = clamp(value, 0.0, 1.0);
+    // Horner's scheme, highest power first
+    let [red, green, blue] = COEFFICIENTS
+        .iter()
+        .rev()
+        .fold([0.0; 3], |sum, coefficient| {
+            [
+                sum[0] * t + coefficient[0],
+                sum[1] * t + coefficient[1],
+                sum[2] * t + coefficient[2],
+            ]
+        });
+    Color::new(
+        clamp(red, 0.0, 1.0),
+        clamp(green, 0.0, 1.0),
+        clamp(blue, 0.0, 1.0),
+    )
+}
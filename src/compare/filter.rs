@@ -0,0 +1,49 @@
+use rayon::prelude::*;
+
+use crate::math::Float;
+
+/// Convolves a single channel image with a square kernel of side `2 * radius + 1`, stored row by
+/// row. Pixels outside the image repeat the closest edge pixel.
+pub fn convolve(
+    values: &[Float],
+    width: usize,
+    height: usize,
+    kernel: &[Float],
+    radius: usize,
+) -> Vec<Float> {
+    let side = 2 * radius + 1;
+    debug_assert_eq!(kernel.len(), side * side);
+    (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let mut sum = 0.0;
+            for j in 0..side {
+                let qy = (y + j).saturating_sub(radius).min(height - 1);
+                for i in 0..side {
+                    let qx = (x + i).saturating_sub(radius).min(width - 1);
+                    sum += kernel[j * side + i] * values[qy * width + qx];
+                }
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Normalized 2D Gaussian with a radius of `radius` pixels.
+pub fn gaussian_kernel(sigma: Float, radius: usize) -> Vec<Float> {
+    let kernel = kernel_from(radius, |x, y| {
+        (-(x * x + y * y) / (2.0 * sigma * sigma)).exp()
+    });
+    let sum: Float = kernel.iter().sum();
+    kernel.into_iter().map(|weight| weight / sum).collect()
+}
+
+/// Evaluates `function` at the integer offsets from -radius to radius, row by row.
+pub fn kernel_from(radius: usize, function: impl Fn(Float, Float) -> Float) -> Vec<Float> {
+    let radius = radius as isize;
+    (-radius..=radius)
+        .flat_map(|y| (-radius..=radius).map(move |x| (x as Float, y as Float)))
+        .map(|(x, y)| function(x, y))
+        .collect()
+}
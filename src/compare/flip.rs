@@ -0,0 +1,210 @@
+use crate::clamp;
+use crate::compare::filter::{convolve, kernel_from};
+use crate::math::{Color, Float, Vec3, PI};
+
+/// Viewing the image from 0.7 m on a 0.7 m wide 4K monitor.
+const PIXELS_PER_DEGREE: Float = 67.0205;
+
+const COLOR_EXPONENT: Float = 0.7;
+const COLOR_CUTOFF: Float = 0.4;
+const COLOR_THRESHOLD: Float = 0.95;
+const FEATURE_EXPONENT: Float = 0.5;
+
+const RGB_TO_XYZ: [[Float; 3]; 3] = [
+    [0.41238656, 0.35759149, 0.18045049],
+    [0.21263682, 0.71518298, 0.0721802],
+    [0.01933062, 0.11919716, 0.95037259],
+];
+const XYZ_TO_RGB: [[Float; 3]; 3] = [
+    [3.24100326, -1.53739899, -0.49861587],
+    [-0.96922426, 1.87592999, 0.04155422],
+    [0.05563942, -0.2040112, 1.05714897],
+];
+
+/// Perceived difference of every pixel following LDR-FLIP (Andersson et al. 2020), between 0 and
+/// 1. Colors are linear and clamped to the displayable range.
+pub fn flip_map(reference: &[Color], image: &[Color], width: usize, height: usize) -> Vec<Float> {
+    let reference: Vec<Color> = reference.iter().map(|&color| to_ycxcz(color)).collect();
+    let image: Vec<Color> = image.iter().map(|&color| to_ycxcz(color)).collect();
+
+    // Color pipeline: blur away what the eye can't resolve, then compare in a Hunt adjusted L*a*b*
+    let (kernels, radius) = contrast_sensitivity_kernels();
+    let filter = |colors: &[Color]| -> Vec<Vec3> {
+        let channels: Vec<Vec<Float>> = (0..3)
+            .map(|axis| {
+                let values: Vec<Float> = colors.iter().map(|color| color[axis]).collect();
+                convolve(&values, width, height, &kernels[axis], radius)
+            })
+            .collect();
+        (0..width * height)
+            .map(|index| {
+                let ycxcz = Color::new(channels[0][index], channels[1][index], channels[2][index]);
+                hunt(to_lab(from_ycxcz(ycxcz)))
+            })
+            .collect()
+    };
+    let (filtered_reference, filtered_image) = (filter(&reference), filter(&image));
+    let max_error = hyab(
+        hunt(to_lab(Color::new(0.0, 1.0, 0.0))),
+        hunt(to_lab(Color::new(0.0, 0.0, 1.0))),
+    )
+    .powf(COLOR_EXPONENT);
+
+    // Feature pipeline: edges and points that appear or disappear are more visible than their color
+    let luminance = |colors: &[Color]| -> Vec<Float> {
+        colors
+            .iter()
+            .map(|color| (color.x + 16.0) / 116.0)
+            .collect()
+    };
+    let (reference_features, image_features) = (
+        Features::detect(&luminance(&reference), width, height),
+        Features::detect(&luminance(&image), width, height),
+    );
+
+    (0..width * height)
+        .map(|index| {
+            let color_error = redistribute(
+                hyab(filtered_reference[index], filtered_image[index]).powf(COLOR_EXPONENT),
+                max_error,
+            );
+            let edge_difference =
+                (reference_features.edges[index] - image_features.edges[index]).abs();
+            let point_difference =
+                (reference_features.points[index] - image_features.points[index]).abs();
+            let feature_error =
+                (edge_difference.max(point_difference) / Float::sqrt(2.0)).powf(FEATURE_EXPONENT);
+            color_error.powf(1.0 - feature_error)
+        })
+        .collect()
+}
+
+/// Spatial filters of the achromatic, red-green and blue-yellow channels, all with the same radius.
+fn contrast_sensitivity_kernels() -> ([Vec<Float>; 3], usize) {
+    // (a1, b1, a2, b2) of a sum of two Gaussians, b in degrees squared
+    const PARAMETERS: [(Float, Float, Float, Float); 3] = [
+        (1.0, 0.0047, 0.0, 1e-5),
+        (1.0, 0.0053, 0.0, 1e-5),
+        (34.1, 0.04, 13.5, 0.025),
+    ];
+    let widest = 0.04;
+    let radius = (3.0 * (widest / (2.0 * PI * PI)).sqrt() * PIXELS_PER_DEGREE).ceil() as usize;
+
+    let kernels = PARAMETERS.map(|(a1, b1, a2, b2)| {
+        let kernel = kernel_from(radius, |x, y| {
+            let squared_distance = (x * x + y * y) / (PIXELS_PER_DEGREE * PIXELS_PER_DEGREE);
+            a1 * (PI / b1).sqrt() * (-PI * PI * squared_distance / b1).exp()
+                + a2 * (PI / b2).sqrt() * (-PI * PI * squared_distance / b2).exp()
+        });
+        let sum: Float = kernel.iter().sum();
+        kernel.into_iter().map(|weight| weight / sum).collect()
+    });
+    (kernels, radius)
+}
+
+/// Magnitudes of the edge and point detector responses of every pixel.
+struct Features {
+    edges: Vec<Float>,
+    points: Vec<Float>,
+}
+
+impl Features {
+    fn detect(luminance: &[Float], width: usize, height: usize) -> Features {
+        let sigma = 0.5 * 0.082 * PIXELS_PER_DEGREE;
+        let radius = (3.0 * sigma).ceil() as usize;
+        let gaussian = |x: Float, y: Float| (-(x * x + y * y) / (2.0 * sigma * sigma)).exp();
+
+        // First and second derivatives of the Gaussian along x, and their transposes along y
+        let magnitude = |derivative: &dyn Fn(Float) -> Float| -> Vec<Float> {
+            let along_x =
+                normalize_lobes(kernel_from(radius, |x, y| derivative(x) * gaussian(x, y)));
+            let along_y =
+                normalize_lobes(kernel_from(radius, |x, y| derivative(y) * gaussian(x, y)));
+            let response_x = convolve(luminance, width, height, &along_x, radius);
+            let response_y = convolve(luminance, width, height, &along_y, radius);
+            response_x
+                .iter()
+                .zip(&response_y)
+                .map(|(x, y)| (x * x + y * y).sqrt())
+                .collect()
+        };
+        Features {
+            edges: magnitude(&|x| -x),
+            points: magnitude(&|x| x * x / (sigma * sigma) - 1.0),
+        }
+    }
+}
+
+/// Scales the positive weights to sum to 1 and the negative ones to sum to -1.
+fn normalize_lobes(kernel: Vec<Float>) -> Vec<Float> {
+    let positive: Float = kernel.iter().filter(|weight| **weight > 0.0).sum();
+    let negative: Float = -kernel.iter().filter(|weight| **weight < 0.0).sum::<Float>();
+    kernel
+        .into_iter()
+        .map(|weight| match weight > 0.0 {
+            true => weight / positive,
+            false => weight / negative,
+        })
+        .collect()
+}
+
+/// Compresses large color differences into the top of the 0 to 1 range.
+fn redistribute(error: Float, max_error: Float) -> Float {
+    let cutoff = COLOR_CUTOFF * max_error;
+    if error < cutoff {
+        COLOR_THRESHOLD * error / cutoff
+    } else {
+        COLOR_THRESHOLD + (error - cutoff) / (max_error - cutoff) * (1.0 - COLOR_THRESHOLD)
+    }
+}
+
+fn clamp_color(color: Color) -> Color {
+    Color::new(
+        clamp(color.x, 0.0, 1.0),
+        clamp(color.y, 0.0, 1.0),
+        clamp(color.z, 0.0, 1.0),
+    )
+}
+
+fn white_point() -> Color {
+    Color::ONE.transform(&RGB_TO_XYZ)
+}
+
+/// Linear RGB to the opponent YCxCz space, relative to the D65 white point.
+fn to_ycxcz(color: Color) -> Color {
+    let xyz = clamp_color(color).transform(&RGB_TO_XYZ) / white_point();
+    Color::new(
+        116.0 * xyz.y - 16.0,
+        500.0 * (xyz.x - xyz.y),
+        200.0 * (xyz.y - xyz.z),
+    )
+}
+
+/// YCxCz back to linear RGB, clamped to the displayable range.
+fn from_ycxcz(ycxcz: Color) -> Color {
+    let y = (ycxcz.x + 16.0) / 116.0;
+    let xyz = Color::new(ycxcz.y / 500.0 + y, y, y - ycxcz.z / 200.0) * white_point();
+    clamp_color(xyz.transform(&XYZ_TO_RGB))
+}
+
+/// Linear RGB to CIE L*a*b*.
+fn to_lab(color: Color) -> Vec3 {
+    const DELTA: Float = 6.0 / 29.0;
+    let f = |t: Float| match t > DELTA * DELTA * DELTA {
+        true => t.cbrt(),
+        false => t / (3.0 * DELTA * DELTA) + 4.0 / 29.0,
+    };
+    let xyz = color.transform(&RGB_TO_XYZ) / white_point();
+    let (fx, fy, fz) = (f(xyz.x), f(xyz.y), f(xyz.z));
+    Vec3::new(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Hunt effect, colors look less saturated at low luminance.
+fn hunt(lab: Vec3) -> Vec3 {
+    Vec3::new(lab.x, 0.01 * lab.x * lab.y, 0.01 * lab.x * lab.z)
+}
+
+/// Hybrid distance, city block on lightness and Euclidean on the chromatic axes.
+fn hyab(a: Vec3, b: Vec3) -> Float {
+    (a.x - b.x).abs() + ((a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
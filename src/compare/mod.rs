@@ -0,0 +1,162 @@
+//! Error metrics between a rendered image and a reference, and false-color maps of where they
+//! differ.
+
+use crate::clamp;
+use crate::error::{Error, Result};
+use crate::math::{Color, Float};
+use crate::output::display::{srgb_eotf, srgb_oetf};
+use crate::rendering::Film;
+
+mod colormap;
+mod filter;
+mod flip;
+mod ssim;
+
+/// Keeps the relative error finite where the reference is black.
+const RELATIVE_EPSILON: Float = 0.01;
+
+/// Differences between an image and its reference, averaged over all pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageMetrics {
+    /// Mean squared error
+    pub mse: Float,
+    /// Mean squared error relative to the squared reference, so dark regions count as much as
+    /// bright ones
+    pub rel_mse: Float,
+    /// Peak signal to noise ratio in dB, with a peak of 1. Infinite for identical images
+    pub psnr: Float,
+    /// Mean structural similarity of the display encoded luma, 1 for identical images
+    pub ssim: Float,
+    /// Mean perceived difference according to FLIP, 0 for identical images
+    pub flip: Float,
+}
+
+/// Per-pixel error shown by an error map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ErrorMap {
+    /// Mean absolute difference of the channels
+    Absolute,
+    /// Squared difference relative to the squared reference
+    Relative,
+    /// One minus the structural similarity
+    Ssim,
+    /// Perceived difference according to FLIP
+    #[default]
+    Flip,
+}
+
+/// Compares `image` to `reference`, both holding linear colors. SSIM and FLIP see the colors as a
+/// display would, clamped between 0 and 1.
+pub fn compare(reference: &Film, image: &Film) -> Result<ImageMetrics> {
+    check_sizes(reference, image)?;
+    let (reference_colors, image_colors) = (colors(reference), colors(image));
+    let count = reference_colors.len() as Float;
+
+    let mut squared_error = 0.0;
+    let mut relative_error = 0.0;
+    for (reference, image) in reference_colors.iter().zip(&image_colors) {
+        let error = *image - *reference;
+        squared_error += (error * error).average();
+        relative_error += relative_squared_error(*reference, *image);
+    }
+    let mse = squared_error / count;
+    let ssim = ssim_map(reference, image);
+    let flip = flip::flip_map(
+        &reference_colors,
+        &image_colors,
+        reference.width(),
+        reference.height(),
+    );
+    Ok(ImageMetrics {
+        mse,
+        rel_mse: relative_error / count,
+        psnr: -10.0 * mse.log10(),
+        ssim: ssim.iter().sum::<Float>() / count,
+        flip: flip.iter().sum::<Float>() / count,
+    })
+}
+
+/// False-color image of the per-pixel error, from black for no error to pale yellow for an error
+/// of 1 or more. The film holds linear colors like a render, so it can be written in any format.
+pub fn error_map(reference: &Film, image: &Film, map: ErrorMap) -> Result<Film> {
+    check_sizes(reference, image)?;
+    let (reference_colors, image_colors) = (colors(reference), colors(image));
+    let errors: Vec<Float> = match map {
+        ErrorMap::Absolute => reference_colors
+            .iter()
+            .zip(&image_colors)
+            .map(|(reference, image)| {
+                let difference = *image - *reference;
+                (difference.x.abs() + difference.y.abs() + difference.z.abs()) / 3.0
+            })
+            .collect(),
+        ErrorMap::Relative => reference_colors
+            .iter()
+            .zip(&image_colors)
+            .map(|(reference, image)| relative_squared_error(*reference, *image))
+            .collect(),
+        ErrorMap::Ssim => ssim_map(reference, image)
+            .into_iter()
+            .map(|ssim| 1.0 - ssim)
+            .collect(),
+        ErrorMap::Flip => flip::flip_map(
+            &reference_colors,
+            &image_colors,
+            reference.width(),
+            reference.height(),
+        ),
+    };
+    let colors = errors
+        .into_iter()
+        .map(|error| {
+            let color = colormap::magma(error);
+            Color::new(srgb_eotf(color.x), srgb_eotf(color.y), srgb_eotf(color.z))
+        })
+        .collect();
+    Ok(
+        Film::from_colors(reference.width(), reference.height(), colors)
+            .expect("one error per pixel"),
+    )
+}
+
+fn relative_squared_error(reference: Color, image: Color) -> Float {
+    let difference = image - reference;
+    let relative =
+        difference * difference / (reference * reference + Color::ONE * RELATIVE_EPSILON);
+    (relative.x + relative.y + relative.z) / 3.0
+}
+
+fn ssim_map(reference: &Film, image: &Film) -> Vec<Float> {
+    let luma = |film: &Film| -> Vec<Float> {
+        colors(film)
+            .into_iter()
+            .map(|color| {
+                let encode = |channel: Float| srgb_oetf(clamp(channel, 0.0, 1.0));
+                0.2126 * encode(color.x) + 0.7152 * encode(color.y) + 0.0722 * encode(color.z)
+            })
+            .collect()
+    };
+    ssim::ssim_map(
+        &luma(reference),
+        &luma(image),
+        reference.width(),
+        reference.height(),
+    )
+}
+
+fn colors(film: &Film) -> Vec<Color> {
+    (0..film.height())
+        .flat_map(|y| (0..film.width()).map(move |x| film.pixel(x, y)))
+        .collect()
+}
+
+fn check_sizes(reference: &Film, image: &Film) -> Result<()> {
+    if reference.width() != image.width() || reference.height() != image.height() {
+        return Err(Error::ImageSizeMismatch {
+            reference: (reference.width(), reference.height()),
+            image: (image.width(), image.height()),
+        });
+    }
+    Ok(())
+}
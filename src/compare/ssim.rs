@@ -0,0 +1,33 @@
+use crate::compare::filter::{convolve, gaussian_kernel};
+use crate::math::Float;
+
+const SIGMA: Float = 1.5;
+const RADIUS: usize = 5;
+const C1: Float = 0.01 * 0.01;
+const C2: Float = 0.03 * 0.03;
+
+/// Structural similarity of every pixel (Wang et al. 2004), computed on display encoded luma in a
+/// Gaussian window. 1 where the images match.
+pub fn ssim_map(reference: &[Float], image: &[Float], width: usize, height: usize) -> Vec<Float> {
+    let kernel = gaussian_kernel(SIGMA, RADIUS);
+    let blur = |values: &[Float]| convolve(values, width, height, &kernel, RADIUS);
+    let product =
+        |a: &[Float], b: &[Float]| -> Vec<Float> { a.iter().zip(b).map(|(a, b)| a * b).collect() };
+
+    let mean_reference = blur(reference);
+    let mean_image = blur(image);
+    let reference_squares = blur(&product(reference, reference));
+    let image_squares = blur(&product(image, image));
+    let cross = blur(&product(reference, image));
+
+    (0..width * height)
+        .map(|index| {
+            let (mx, my) = (mean_reference[index], mean_image[index]);
+            let variance_x = reference_squares[index] - mx * mx;
+            let variance_y = image_squares[index] - my * my;
+            let covariance = cross[index] - mx * my;
+            ((2.0 * mx * my + C1) * (2.0 * covariance + C2))
+                / ((mx * mx + my * my + C1) * (variance_x + variance_y + C2))
+        })
+        .collect()
+}
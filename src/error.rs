@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong between reading a scene and writing the image.
+#[derive(Debug)]
+pub enum Error {
+    /// A file referenced by the scene, like a texture, could not be read
+    AssetLoad {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The scene contains an object that can't be rendered
+    InvalidScene(String),
+    /// The render settings can't produce an image
+    InvalidSettings(String),
+    /// No image format is known for the extension of the path
+    UnknownImageFormat(PathBuf),
+    /// Two images that are compared have different sizes
+    ImageSizeMismatch {
+        reference: (usize, usize),
+        image: (usize, usize),
+    },
+    ReadImage {
+        path: PathBuf,
+        source: io::Error,
+    },
+    WriteImage {
+        path: PathBuf,
+        source: io::Error,
+    },
+    ReadCheckpoint {
+        path: PathBuf,
+        source: io::Error,
+    },
+    WriteCheckpoint {
+        path: PathBuf,
+        source: io::Error,
+    },
+    WriteGrid {
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AssetLoad { path, .. } => write!(f, "could not load {}", path.display()),
+            Error::InvalidScene(message) => write!(f, "invalid scene: {}", message),
+            Error::InvalidSettings(message) => write!(f, "invalid settings: {}", message),
+            Error::UnknownImageFormat(path) => write!(
+                f,
+                "unknown image format for {}, use a .png, .ppm, .exr or .hdr extension",
+                path.display()
+            ),
+            Error::ImageSizeMismatch { reference, image } => write!(
+                f,
+                "the image is {}x{}, but the reference is {}x{}",
+                image.0, image.1, reference.0, reference.1
+            ),
+            Error::ReadImage { path, .. } => write!(f, "could not read {}", path.display()),
+            Error::WriteImage { path, .. } => write!(f, "could not write {}", path.display()),
+            Error::ReadCheckpoint { path, .. } => {
+                write!(f, "could not read checkpoint {}", path.display())
+            }
+            Error::WriteCheckpoint { path, .. } => {
+                write!(f, "could not write checkpoint {}", path.display())
+            }
+            Error::WriteGrid { path, .. } => {
+                write!(f, "could not write density grid {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::AssetLoad { source, .. }
+            | Error::ReadImage { source, .. }
+            | Error::WriteImage { source, .. }
+            | Error::ReadCheckpoint { source, .. }
+            | Error::WriteCheckpoint { source, .. }
+            | Error::WriteGrid { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
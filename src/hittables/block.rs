@@ -0,0 +1,93 @@
+use crate::hittables::solid::{first_crossing, Crossing, Span};
+use crate::hittables::{HitRecord, Hittable, Solid};
+use crate::math::{Aabb, Float};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Solid axis-aligned box between two corners. Unlike the six quads of `cuboid`, it has an
+/// inside, so it can be used with `Csg`, but it is not sampled as a light. U and v go across each
+/// face along the next two axes.
+#[derive(Clone)]
+pub struct Block {
+    pub(crate) min: Point3,
+    pub(crate) max: Point3,
+    pub(crate) material: UberShader,
+}
+
+impl Block {
+    /// Corners may be given in any order.
+    pub fn new(a: Point3, b: Point3, material: UberShader) -> Block {
+        let bounds = Aabb::new(a, b);
+        Block {
+            min: bounds.min,
+            max: bounds.max,
+            material,
+        }
+    }
+
+    /// Where the ray crosses the face of the slab of `axis` at `t`.
+    fn crossing(&self, ray: &Ray, axis: usize, t: Float, outward: Float) -> Crossing {
+        let point = ray.at(t);
+        let extent = self.max - self.min;
+        let mut normal = Vec3::ZERO;
+        normal[axis] = outward;
+        let normal = Normal3::from(normal);
+        let (first, second) = ((axis + 1) % 3, (axis + 2) % 3);
+        Crossing {
+            t,
+            normal,
+            material: self.material,
+            u: ((point[first] - self.min[first]) / extent[first]).clamp(0.0, 1.0),
+            v: ((point[second] - self.min[second]) / extent[second]).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Hittable for Block {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut spans = Vec::with_capacity(1);
+        self.spans(ray, &mut spans);
+        first_crossing(&spans, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
+    }
+}
+
+impl Solid for Block {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        // Slab test over the whole line, remembering which slab each end comes from
+        let (mut entry, mut exit) = ((Float::NEG_INFINITY, 0, 0.0), (Float::INFINITY, 0, 0.0));
+        for axis in 0..3 {
+            let inverse = 1.0 / ray.direction[axis];
+            if !inverse.is_finite() {
+                // Parallel to the slab, inside it or not at all
+                match (self.min[axis]..=self.max[axis]).contains(&ray.origin[axis]) {
+                    true => continue,
+                    false => return,
+                }
+            }
+            let t0 = (self.min[axis] - ray.origin[axis]) * inverse;
+            let t1 = (self.max[axis] - ray.origin[axis]) * inverse;
+            let (near, far) = match inverse < 0.0 {
+                true => ((t1, axis, 1.0), (t0, axis, -1.0)),
+                false => ((t0, axis, -1.0), (t1, axis, 1.0)),
+            };
+            if near.0 > entry.0 {
+                entry = near;
+            }
+            if far.0 < exit.0 {
+                exit = far;
+            }
+        }
+        // A direction of zero never reaches the box
+        if exit.0 <= entry.0 || !entry.0.is_finite() || !exit.0.is_finite() {
+            return;
+        }
+        spans.push(Span {
+            entry: self.crossing(ray, entry.1, entry.0, entry.2),
+            exit: self.crossing(ray, exit.1, exit.0, exit.2),
+        });
+    }
+}
@@ -0,0 +1,423 @@
+use std::mem;
+
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::{Aabb, Float, RayPacket, WideAabb, LANES};
+use crate::{Ray, Vec3};
+
+/// Most objects in a leaf, more are split in two.
+const LEAF_SIZE: usize = 2;
+/// Median splits keep the binary tree under 32 levels for any number of objects that fits in a
+/// `u32`, and every wide node takes at least two of them. A ray pushes at most `LANES - 1` more
+/// children than it pops on every level.
+const STACK_SIZE: usize = 1 + 16 * (LANES - 1);
+
+/// Bounding volume hierarchy, finds the closest hit without testing every object. Object IDs are
+/// the 1-based indices of the objects in the order they were given, like in a `HittableList`.
+///
+/// The tree is built with two children per node and then collapsed to `LANES` children per
+/// node, whose boxes are tested against a ray at once.
+#[derive(Debug, Clone)]
+pub struct Bvh<T: Hittable> {
+    objects: Vec<T>,
+    /// Indices into `objects`, ordered so that every leaf covers a contiguous range
+    order: Vec<u32>,
+    /// The root is the first node
+    nodes: Vec<Node>,
+    /// Objects without finite bounds, like infinite planes, which every ray is tested against
+    unbounded: Vec<u32>,
+    bounds: Aabb,
+    depth: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// Boxes of the children, `Aabb::EMPTY` in the lanes without one
+    bounds: WideAabb,
+    /// In the order of the binary tree, so they go from low to high along `axis`
+    children: [Child; LANES],
+    /// Axis the objects were first split along
+    axis: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Child {
+    Empty,
+    Node(u32),
+    /// Range of `order`
+    Leaf {
+        start: u32,
+        count: u32,
+    },
+}
+
+/// Node of the binary tree the wide nodes are collapsed from.
+struct BinaryNode {
+    bounds: Aabb,
+    kind: BinaryKind,
+}
+
+enum BinaryKind {
+    /// Range of `order`
+    Leaf { start: u32, count: u32 },
+    /// The first child directly follows its parent, the children are split along `axis`
+    Interior { second_child: u32, axis: u8 },
+}
+
+impl<T: Hittable> Bvh<T> {
+    /// Splits the objects at the median of their centers along the longest axis, until the
+    /// leaves are small.
+    pub fn new(objects: Vec<T>) -> Bvh<T> {
+        let bounds: Vec<Aabb> = objects.iter().map(Hittable::bounding_box).collect();
+        let (mut order, unbounded): (Vec<u32>, Vec<u32>) =
+            (0..objects.len() as u32).partition(|index| bounds[*index as usize].is_bounded());
+        let mut bvh = Bvh {
+            objects,
+            order: Vec::new(),
+            nodes: Vec::new(),
+            unbounded,
+            bounds: bounds
+                .iter()
+                .fold(Aabb::EMPTY, |total, bounds| total.union(bounds)),
+            depth: 0,
+        };
+        if !order.is_empty() {
+            let count = order.len();
+            let mut binary = Vec::new();
+            build(&mut binary, &bounds, &mut order, 0, count);
+            bvh.depth = bvh.collapse(&binary, 0);
+        }
+        bvh.order = order;
+        bvh
+    }
+
+    /// Appends the wide node replacing the binary node at `index` and its descendants, returns
+    /// the depth of its subtree. A binary leaf at the root becomes the only child of a wide node.
+    fn collapse(&mut self, binary: &[BinaryNode], index: usize) -> usize {
+        // With the level of the binary tree below `index` they are on
+        let mut children = match binary[index].kind {
+            BinaryKind::Leaf { .. } => vec![(index, 0)],
+            BinaryKind::Interior { second_child, .. } => {
+                vec![(index + 1, 1), (second_child as usize, 1)]
+            }
+        };
+        // Take in the children of the highest interior nodes, the largest first, while there is
+        // room for them
+        while children.len() < LANES {
+            let expanded = children
+                .iter()
+                .enumerate()
+                .filter(|(_, (child, _))| {
+                    matches!(binary[*child].kind, BinaryKind::Interior { .. })
+                })
+                .min_by(|(_, (a, a_level)), (_, (b, b_level))| {
+                    let a_area = binary[*a].bounds.surface_area();
+                    let b_area = binary[*b].bounds.surface_area();
+                    a_level.cmp(b_level).then(b_area.total_cmp(&a_area))
+                })
+                .map(|(position, _)| position);
+            let Some(position) = expanded else {
+                break;
+            };
+            let (child, level) = children[position];
+            let BinaryKind::Interior { second_child, .. } = binary[child].kind else {
+                unreachable!("only interior nodes are expanded");
+            };
+            children[position] = (child + 1, level + 1);
+            children.insert(position + 1, (second_child as usize, level + 1));
+        }
+
+        let node_index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: WideAabb::EMPTY,
+            children: [Child::Empty; LANES],
+            axis: match binary[index].kind {
+                BinaryKind::Interior { axis, .. } => axis,
+                BinaryKind::Leaf { .. } => 0,
+            },
+        });
+        let mut depth = 1;
+        for (lane, (child, _)) in children.into_iter().enumerate() {
+            self.nodes[node_index]
+                .bounds
+                .set(lane, &binary[child].bounds);
+            self.nodes[node_index].children[lane] = match binary[child].kind {
+                BinaryKind::Leaf { start, count } => Child::Leaf { start, count },
+                BinaryKind::Interior { .. } => {
+                    let child_index = self.nodes.len() as u32;
+                    depth = depth.max(1 + self.collapse(binary, child));
+                    Child::Node(child_index)
+                }
+            };
+        }
+        depth
+    }
+
+    pub fn objects(&self) -> &[T] {
+        &self.objects
+    }
+
+    /// Bytes used by the objects and the nodes, not counting what the objects point to.
+    pub fn memory(&self) -> usize {
+        mem::size_of::<Bvh<T>>()
+            + self.objects.capacity() * mem::size_of::<T>()
+            + (self.order.capacity() + self.unbounded.capacity()) * mem::size_of::<u32>()
+            + self.nodes.capacity() * mem::size_of::<Node>()
+    }
+
+    /// Number of levels of wide nodes, 0 if there are no bounded objects.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn hit_object(
+        &self,
+        index: u32,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        closest: &mut Option<HitRecord>,
+    ) {
+        if let Some(mut record) = self.objects[index as usize].hit(ray, t_min, t_max) {
+            record.object_id = index + 1;
+            *closest = Some(record);
+        }
+    }
+
+    /// Whether `direction` goes from low to high along the axis of `node`.
+    fn forward(&self, node: u32, direction: Vec3) -> bool {
+        direction[self.nodes[node as usize].axis as usize] >= 0.0
+    }
+
+    fn leaf(&self, start: u32, count: u32) -> &[u32] {
+        &self.order[start as usize..(start + count) as usize]
+    }
+}
+
+/// Appends the subtree of `order[start..start + count]` to `nodes`.
+fn build(
+    nodes: &mut Vec<BinaryNode>,
+    bounds: &[Aabb],
+    order: &mut [u32],
+    start: usize,
+    count: usize,
+) {
+    let range = &mut order[start..start + count];
+    let node_bounds = range.iter().fold(Aabb::EMPTY, |total, index| {
+        total.union(&bounds[*index as usize])
+    });
+    let index = nodes.len();
+    nodes.push(BinaryNode {
+        bounds: node_bounds,
+        kind: BinaryKind::Leaf {
+            start: start as u32,
+            count: count as u32,
+        },
+    });
+    if count <= LEAF_SIZE {
+        return;
+    }
+
+    let centroids = range.iter().fold(Aabb::EMPTY, |total, index| {
+        let centroid = bounds[*index as usize].centroid();
+        total.union(&Aabb::new(centroid, centroid))
+    });
+    let axis = centroids.longest_axis();
+    let half = count / 2;
+    range.select_nth_unstable_by(half, |a, b| {
+        let a = bounds[*a as usize].centroid()[axis];
+        let b = bounds[*b as usize].centroid()[axis];
+        a.total_cmp(&b)
+    });
+
+    build(nodes, bounds, order, start, half);
+    let second_child = nodes.len() as u32;
+    build(nodes, bounds, order, start + half, count - half);
+    nodes[index].kind = BinaryKind::Interior {
+        second_child,
+        axis: axis as u8,
+    };
+}
+
+/// Children waiting on the stack of a traversal, as `node * LANES + lane`.
+struct Stack {
+    slots: [u32; STACK_SIZE],
+    size: usize,
+}
+
+impl Stack {
+    fn new() -> Stack {
+        Stack {
+            slots: [0; STACK_SIZE],
+            size: 0,
+        }
+    }
+
+    /// Pushes the children of `node` with a finite entry distance. The ones closer to the ray
+    /// origin along the axis of the node go last, so they are visited first and the hits found in
+    /// them cull the others.
+    fn push_children(&mut self, node: u32, entries: &[Float; LANES], forward: bool) {
+        for step in 0..LANES {
+            let lane = match forward {
+                true => LANES - 1 - step,
+                false => step,
+            };
+            if entries[lane] < Float::INFINITY {
+                self.slots[self.size] = node * LANES as u32 + lane as u32;
+                self.size += 1;
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<(usize, usize)> {
+        self.size = self.size.checked_sub(1)?;
+        let slot = self.slots[self.size] as usize;
+        Some((slot / LANES, slot % LANES))
+    }
+}
+
+impl<T: Hittable> Hittable for Bvh<T> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut closest: Option<HitRecord> = None;
+        let mut t_max = t_max;
+        for &index in &self.unbounded {
+            self.hit_object(index, ray, t_min, t_max, &mut closest);
+            t_max = closest.as_ref().map_or(t_max, |record| record.t);
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let inverse_direction = Vec3::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let mut stack = Stack::new();
+        let entries = self.nodes[0]
+            .bounds
+            .hit(ray, inverse_direction, t_min, t_max);
+        stack.push_children(0, &entries, self.forward(0, ray.direction));
+        while let Some((node, lane)) = stack.pop() {
+            match self.nodes[node].children[lane] {
+                Child::Leaf { start, count } => {
+                    // Unless a hit found since it was pushed is closer
+                    let bounds = self.nodes[node].bounds.get(lane);
+                    if !bounds.hit(ray, inverse_direction, t_min, t_max) {
+                        continue;
+                    }
+                    for &index in self.leaf(start, count) {
+                        self.hit_object(index, ray, t_min, t_max, &mut closest);
+                        t_max = closest.as_ref().map_or(t_max, |record| record.t);
+                    }
+                }
+                Child::Node(index) => {
+                    let entries =
+                        self.nodes[index as usize]
+                            .bounds
+                            .hit(ray, inverse_direction, t_min, t_max);
+                    let forward = self.forward(index, ray.direction);
+                    stack.push_children(index, &entries, forward);
+                }
+                Child::Empty => {}
+            }
+        }
+        closest
+    }
+
+    /// Goes down the tree once for the whole packet, testing every box against all of the rays
+    /// at once. The leaves pass on the rays that reach them, so instances keep the packet
+    /// together in the BVH of their mesh.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        for &index in &self.unbounded {
+            self.hit_packet_object(index, packet, t_min, t_max, hits, &[t_min; LANES]);
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        // The rays of a packet go roughly the same way, the first one orders the children
+        let direction = packet.rays[0].direction;
+        let mut stack = Stack::new();
+        let entries = self.packet_entries(0, packet, t_min, t_max);
+        stack.push_children(0, &entries, self.forward(0, direction));
+        while let Some((node, lane)) = stack.pop() {
+            match self.nodes[node].children[lane] {
+                Child::Leaf { start, count } => {
+                    let bounds = self.nodes[node].bounds.get(lane);
+                    for &index in self.leaf(start, count) {
+                        let entries = packet.hit(&bounds, t_min, t_max);
+                        self.hit_packet_object(index, packet, t_min, t_max, hits, &entries);
+                    }
+                }
+                Child::Node(index) => {
+                    let entries = self.packet_entries(index, packet, t_min, t_max);
+                    stack.push_children(index, &entries, self.forward(index, direction));
+                }
+                Child::Empty => {}
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl<T: Hittable> Bvh<T> {
+    /// Where the closest ray of the packet enters each child of `node`.
+    fn packet_entries(
+        &self,
+        node: u32,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &[Float; LANES],
+    ) -> [Float; LANES] {
+        let node = &self.nodes[node as usize];
+        let mut nearest = [Float::INFINITY; LANES];
+        for (lane, child) in node.children.iter().enumerate() {
+            if let Child::Empty = child {
+                continue;
+            }
+            let bounds = node.bounds.get(lane);
+            let entries = packet.hit(&bounds, t_min, t_max);
+            nearest[lane] = entries.iter().fold(Float::INFINITY, |a, b| a.min(*b));
+        }
+        nearest
+    }
+
+    /// Tests the rays of the packet that reach the object, the ones with a finite entry.
+    fn hit_packet_object(
+        &self,
+        index: u32,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+        entries: &[Float; LANES],
+    ) {
+        if entries.iter().all(|entry| *entry == Float::INFINITY) {
+            return;
+        }
+        let mut reaching = *t_max;
+        for (lane, entry) in entries.iter().enumerate() {
+            if *entry == Float::INFINITY {
+                reaching[lane] = Float::NEG_INFINITY;
+            }
+        }
+        self.objects[index as usize].hit_packet(packet, t_min, &mut reaching, hits);
+        for lane in 0..LANES {
+            if entries[lane] < Float::INFINITY && reaching[lane] < t_max[lane] {
+                t_max[lane] = reaching[lane];
+                if let Some(record) = &mut hits[lane] {
+                    record.object_id = index + 1;
+                }
+            }
+        }
+    }
+}
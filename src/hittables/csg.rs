@@ -0,0 +1,146 @@
+use crate::hittables::solid::{first_crossing, Crossing, Span};
+use crate::hittables::{HitRecord, Hittable, Primitive, Solid};
+use crate::math::{Aabb, Float};
+use crate::{Ray, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// The first solid with the second cut out of it
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, first: bool, second: bool) -> bool {
+        match self {
+            CsgOperation::Union => first || second,
+            CsgOperation::Intersection => first && second,
+            CsgOperation::Difference => first && !second,
+        }
+    }
+}
+
+/// Solid made by combining two closed primitives, which can be `Csg` themselves. Every part of
+/// the surface keeps the material of the solid it comes from, so the walls of a hole cut by a
+/// difference have the material of the second solid.
+#[derive(Clone)]
+pub struct Csg {
+    pub(crate) operation: CsgOperation,
+    pub(crate) first: Box<Primitive>,
+    pub(crate) second: Box<Primitive>,
+    bounds: Aabb,
+}
+
+impl Csg {
+    pub fn new(
+        operation: CsgOperation,
+        first: impl Into<Primitive>,
+        second: impl Into<Primitive>,
+    ) -> Csg {
+        let (first, second) = (first.into(), second.into());
+        let bounds = match operation {
+            CsgOperation::Union => first.bounding_box().union(&second.bounding_box()),
+            CsgOperation::Intersection => first.bounding_box().intersection(&second.bounding_box()),
+            CsgOperation::Difference => first.bounding_box(),
+        };
+        Csg {
+            operation,
+            first: Box::new(first),
+            second: Box::new(second),
+            bounds,
+        }
+    }
+
+    pub fn union(first: impl Into<Primitive>, second: impl Into<Primitive>) -> Csg {
+        Csg::new(CsgOperation::Union, first, second)
+    }
+
+    pub fn intersection(first: impl Into<Primitive>, second: impl Into<Primitive>) -> Csg {
+        Csg::new(CsgOperation::Intersection, first, second)
+    }
+
+    pub fn difference(first: impl Into<Primitive>, second: impl Into<Primitive>) -> Csg {
+        Csg::new(CsgOperation::Difference, first, second)
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn first(&self) -> &Primitive {
+        &self.first
+    }
+
+    pub fn second(&self) -> &Primitive {
+        &self.second
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let inverse_direction = Vec3::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        if !self.bounds.hit(ray, inverse_direction, t_min, t_max) {
+            return None;
+        }
+        let mut spans = Vec::new();
+        self.spans(ray, &mut spans);
+        first_crossing(&spans, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl Solid for Csg {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        let mut operands = [Vec::new(), Vec::new()];
+        self.first.spans(ray, &mut operands[0]);
+        self.second.spans(ray, &mut operands[1]);
+
+        // Crossings of both solids along the ray: which solid, whether the ray enters it.
+        // Entries go first where surfaces touch, so solids sharing a face merge in a union.
+        let mut events: Vec<(Crossing, usize, bool)> = operands
+            .iter()
+            .enumerate()
+            .flat_map(|(operand, spans)| {
+                spans.iter().flat_map(move |span| {
+                    [(span.entry, operand, true), (span.exit, operand, false)]
+                })
+            })
+            .collect();
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t).then(b.2.cmp(&a.2)));
+
+        let mut depth = [0i32; 2];
+        let mut entry: Option<Crossing> = None;
+        for (mut crossing, operand, entering) in events {
+            let was_inside = self.operation.contains(depth[0] > 0, depth[1] > 0);
+            depth[operand] += if entering { 1 } else { -1 };
+            let inside = self.operation.contains(depth[0] > 0, depth[1] > 0);
+            if inside == was_inside {
+                continue;
+            }
+            // Leaving the second solid of a difference enters the result and the other way
+            // around, its normal is flipped to point out of the result
+            if inside != entering {
+                crossing.normal = -crossing.normal;
+            }
+            match inside {
+                true => entry = Some(crossing),
+                false => {
+                    if let Some(entry) = entry.take() {
+                        spans.push(Span {
+                            entry,
+                            exit: crossing,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
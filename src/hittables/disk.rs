@@ -0,0 +1,81 @@
+use crate::hittables::quad::pad;
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::{random_scalar, Aabb, Float, Onb, PI};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Flat circle, facing the side `normal` points to. U is the distance from the center divided by
+/// the radius, v the angle around the normal divided by 2π.
+#[derive(Clone)]
+pub struct Disk {
+    pub(crate) center: Point3,
+    pub(crate) normal: Normal3,
+    pub(crate) radius: Float,
+    pub(crate) material: UberShader,
+    frame: Onb,
+}
+
+impl Disk {
+    /// `normal` doesn't need to be normalized.
+    pub fn new(center: Point3, normal: Vec3, radius: Float, material: UberShader) -> Disk {
+        let frame = Onb::from_w(normal);
+        Disk {
+            center,
+            normal: Normal3::from(frame.w),
+            radius,
+            material,
+            frame,
+        }
+    }
+
+    pub fn area(&self) -> Float {
+        PI * self.radius * self.radius
+    }
+
+    /// Point on the disk, uniformly distributed over its area. Returns the point and its normal.
+    pub fn sample(&self) -> (Point3, Normal3) {
+        let radius = self.radius * random_scalar().sqrt();
+        let phi = 2.0 * PI * random_scalar();
+        let local = Vec3::new(radius * phi.cos(), radius * phi.sin(), 0.0);
+        (self.center + self.frame.to_world(local), self.normal)
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let local = self.frame.to_local(point - self.center);
+        let distance = (local.x * local.x + local.y * local.y).sqrt();
+        if distance > self.radius {
+            return None;
+        }
+
+        let front_face = HitRecord::set_front_face(ray, self.normal);
+        let normal = HitRecord::set_face_normal(front_face, self.normal);
+        let angle = local.y.atan2(local.x).rem_euclid(2.0 * PI);
+        Some(
+            HitRecord::new(point, normal, t, front_face, self.material)
+                .with_uv(distance / self.radius, angle / (2.0 * PI)),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // A circle extends along an axis by the radius times the sine of the angle between the
+        // axis and the normal
+        let extent = Vec3::new(
+            (1.0 - self.normal.x * self.normal.x).max(0.0).sqrt(),
+            (1.0 - self.normal.y * self.normal.y).max(0.0).sqrt(),
+            (1.0 - self.normal.z * self.normal.z).max(0.0).sqrt(),
+        ) * self.radius;
+        pad(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,113 @@
+use crate::hittables::sphere::sphere_uv;
+use crate::hittables::{HitRecord, Hittable, Sdf};
+use crate::math::{Aabb, Float};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Steps after which a ray that hasn't reached the surface counts as a miss.
+const MAX_STEPS: usize = 512;
+
+/// Shape given by a signed distance field, found by sphere tracing: rays step forward by the
+/// distance to the closest surface until they are within `precision` of it. UVs are the
+/// longitude and latitude of the normal, like on a sphere.
+#[derive(Clone)]
+pub struct DistanceField {
+    pub(crate) sdf: Sdf,
+    pub(crate) material: UberShader,
+    /// Distance to the surface that counts as a hit, also the step of the normal estimation
+    pub(crate) precision: Float,
+    bounds: Aabb,
+    lipschitz: Float,
+}
+
+impl DistanceField {
+    pub fn new(sdf: Sdf, material: UberShader) -> DistanceField {
+        DistanceField {
+            bounds: sdf.bounding_box(),
+            lipschitz: sdf.lipschitz(),
+            sdf,
+            material,
+            precision: 1e-4,
+        }
+    }
+
+    /// Smaller values show finer detail of fractals, at the cost of more steps.
+    pub fn with_precision(mut self, precision: Float) -> DistanceField {
+        self.precision = precision;
+        self
+    }
+
+    pub fn sdf(&self) -> &Sdf {
+        &self.sdf
+    }
+
+    /// Gradient of the field from four samples at the corners of a tetrahedron, which points
+    /// out of the shape.
+    fn normal(&self, point: Point3) -> Normal3 {
+        let h = self.precision;
+        let gradient = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .fold(Vec3::ZERO, |gradient, corner| {
+            gradient + corner * self.sdf.distance(point + corner * h)
+        });
+        Normal3::from(gradient.unit_vector())
+    }
+}
+
+impl Hittable for DistanceField {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let inverse_direction = Vec3::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let (start, end) = self.bounds.interval(ray, inverse_direction, t_min, t_max)?;
+        // Distances are in world units, t in lengths of the direction
+        let scale = 1.0 / (ray.direction.length() * self.lipschitz);
+
+        let mut t = start;
+        let mut distance = self.sdf.distance(ray.at(t));
+        // Rays that start on the surface, like the ones scattered from it, leave it towards the
+        // side they move to and can't hit it again until they are clear of it. Rays that enter
+        // the bounds on the way are outside the shape.
+        let mut leaving = start == t_min && distance.abs() < self.precision;
+        let side = match leaving {
+            true => self
+                .sdf
+                .distance(ray.at(t + 2.0 * self.precision * scale))
+                .signum(),
+            false if start > t_min => 1.0,
+            false => distance.signum(),
+        };
+        for _ in 0..MAX_STEPS {
+            let clearance = side * distance;
+            if clearance >= self.precision {
+                leaving = false;
+            } else if !leaving {
+                let point = ray.at(t);
+                let outward_normal = self.normal(point);
+                let front_face = HitRecord::set_front_face(ray, outward_normal);
+                let normal = HitRecord::set_face_normal(front_face, outward_normal);
+                let (u, v) = sphere_uv(Vec3::from(outward_normal));
+                return Some(
+                    HitRecord::new(point, normal, t, front_face, self.material).with_uv(u, v),
+                );
+            }
+            t += clearance.max(self.precision) * scale;
+            if t > end {
+                return None;
+            }
+            distance = self.sdf.distance(ray.at(t));
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
@@ -1,32 +1,65 @@
-use crate::math::{Point3, Ray, Vec3};
-use crate::rendering::UberShader;
+use crate::math::{self, Float, Normal3, Point3, Ray, Vec3};
+use crate::rendering::{DensityField, UberShader};
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub point: Point3,
-    pub normal: Vec3,
-    pub t: f64,
+    pub normal: Normal3,
+    pub t: Float,
+    /// Whether the ray hit the outside of the surface, `normal` always faces the ray
+    pub front_face: bool,
     pub material: UberShader,
+    /// Surface coordinates of the hit, between 0 and 1 on bounded shapes
+    pub u: Float,
+    pub v: Float,
+    /// 1-based index of the object in its `HittableList`, 0 if not set
+    pub object_id: u32,
+    /// Density of the medium behind the surface, for boundaries of volumes filled from a grid
+    pub density: Option<DensityField>,
 }
 
 impl HitRecord {
-    pub fn set_face_normal(front_face: bool, outward_normal: Vec3) -> Vec3 {
+    pub fn set_face_normal(front_face: bool, outward_normal: Normal3) -> Normal3 {
         if front_face {
             outward_normal
         } else {
             -outward_normal
         }
     }
-    pub fn set_front_face(ray: &Ray, outward_normal: Vec3) -> bool {
-        Vec3::dot_product(&ray.direction, &outward_normal) < 0.0
+    pub fn set_front_face(ray: &Ray, outward_normal: Normal3) -> bool {
+        outward_normal.dot(ray.direction) < 0.0
     }
     // #[inline]
-    pub fn new(p: Point3, normal: Vec3, t: f64, material: UberShader) -> HitRecord {
+    pub fn new(
+        p: Point3,
+        normal: Normal3,
+        t: Float,
+        front_face: bool,
+        material: UberShader,
+    ) -> HitRecord {
         HitRecord {
             point: p,
             normal,
             t,
+            front_face,
             material,
+            u: 0.0,
+            v: 0.0,
+            object_id: 0,
+            density: None,
         }
     }
+
+    pub fn with_uv(mut self, u: Float, v: Float) -> HitRecord {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
+    /// Where a ray leaving the surface in `direction` starts. The point is moved off the surface by
+    /// more than its rounding error, so the ray doesn't hit the surface again right away.
+    pub fn spawn_point(&self, direction: Vec3) -> Point3 {
+        let scale = (0..3).fold(1.0, |scale: Float, axis| scale.max(self.point[axis].abs()));
+        self.point + self.normal.face_forward(direction) * (math::EPSILON * scale)
+    }
 }
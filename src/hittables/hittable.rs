@@ -1,8 +1,68 @@
+use std::sync::Arc;
+
 use crate::hittables::HitRecord;
+use crate::math::{Aabb, Float, RayPacket, LANES};
 use crate::Ray;
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord>;
+
+    /// Closest hits of the rays of a packet, each between `t_min` and its own `t_max`. Rays that
+    /// hit the object get their `t_max` lowered to the hit and their record replaced, so the
+    /// packet can be passed on to the next object. A `t_max` below `t_min` leaves a ray out.
+    /// Tests the rays one by one unless the object can do better.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        hit_each(self, packet, t_min, t_max, hits);
+    }
+
+    /// Box containing the whole object, `Aabb::EVERYTHING` if it is infinite.
+    fn bounding_box(&self) -> Aabb;
+}
+
+/// Geometry behind an `Arc` is shared by every instance that places it in the scene.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        (**self).hit(ray, t_min, t_max)
+    }
+
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        (**self).hit_packet(packet, t_min, t_max, hits);
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (**self).bounding_box()
+    }
+}
+
+/// `Hittable::hit_packet` done with a `hit` for every ray.
+pub(crate) fn hit_each<H: Hittable + ?Sized>(
+    object: &H,
+    packet: &RayPacket,
+    t_min: Float,
+    t_max: &mut [Float; LANES],
+    hits: &mut [Option<HitRecord>; LANES],
+) {
+    for (lane, ray) in packet.rays.iter().enumerate() {
+        if t_max[lane] < t_min {
+            continue;
+        }
+        if let Some(record) = object.hit(ray, t_min, t_max[lane]) {
+            t_max[lane] = record.t;
+            hits[lane] = Some(record);
+        }
+    }
 }
 
 // pub struct HitResult<'a> {
@@ -1,4 +1,5 @@
 use crate::hittables::{HitRecord, Hittable};
+use crate::math::{Aabb, Float};
 use crate::Ray;
 
 #[derive(Debug, Clone)]
@@ -12,17 +13,24 @@ impl<Geometry: Hittable> HittableList<Geometry> {
     }
 }
 
-impl<T: Hittable> HittableList<T> {
-    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+impl<T: Hittable> Hittable for HittableList<T> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let mut closest_so_far: Option<HitRecord> = None;
         let mut t_max = t_max;
 
-        for object in &self.objects {
-            if let Some(record) = object.hit(&ray, t_min, t_max) {
+        for (index, object) in self.objects.iter().enumerate() {
+            if let Some(mut record) = object.hit(ray, t_min, t_max) {
                 t_max = record.t;
+                record.object_id = index as u32 + 1;
                 closest_so_far = Some(record);
             }
         }
         closest_so_far
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.objects.iter().fold(Aabb::EMPTY, |bounds, object| {
+            bounds.union(&object.bounding_box())
+        })
+    }
 }
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crate::hittables::{HitRecord, Hittable, Mesh, Transform};
+use crate::math::{Aabb, Float, Matrix4, RayPacket, LANES};
+use crate::rendering::UberShader;
+use crate::Ray;
+
+/// Mesh shared with other instances, placed in the scene with its own transform.
+#[derive(Clone)]
+pub struct Instance {
+    transform: Transform<Arc<Mesh>>,
+    /// Replaces the materials of everything in the mesh
+    material: Option<UberShader>,
+}
+
+impl Instance {
+    /// `None` if `to_world` can't be inverted.
+    pub fn new(mesh: Arc<Mesh>, to_world: Matrix4) -> Option<Instance> {
+        Some(Instance {
+            transform: Transform::new(mesh, to_world)?,
+            material: None,
+        })
+    }
+
+    pub fn with_material(mut self, material: UberShader) -> Instance {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn mesh(&self) -> &Arc<Mesh> {
+        self.transform.object()
+    }
+
+    pub fn to_world(&self) -> &Matrix4 {
+        self.transform.to_world()
+    }
+
+    pub fn material(&self) -> Option<&UberShader> {
+        self.material.as_ref()
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut record = self.transform.hit(ray, t_min, t_max)?;
+        // Boundaries of volumes stay invisible
+        match self.material {
+            Some(material) if !record.material.is_interface() => record.material = material,
+            _ => {}
+        }
+        Some(record)
+    }
+
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        let before = *t_max;
+        self.transform.hit_packet(packet, t_min, t_max, hits);
+        let Some(material) = self.material else {
+            return;
+        };
+        for lane in 0..LANES {
+            match &mut hits[lane] {
+                Some(record) if t_max[lane] < before[lane] && !record.material.is_interface() => {
+                    record.material = material
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.transform.bounding_box()
+    }
+}
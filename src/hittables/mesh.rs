@@ -0,0 +1,47 @@
+use crate::hittables::{Bvh, HitRecord, Hittable, Primitive};
+use crate::math::{Aabb, Float, RayPacket, LANES};
+use crate::Ray;
+
+/// Primitives that are stored once and placed in the scene by any number of instances. Put it in
+/// an `Arc` and pass it to `SceneBuilder::instance` or `Node::with_mesh`. Its BVH is the bottom
+/// level of the scene, under the BVH over the instances.
+#[derive(Clone)]
+pub struct Mesh {
+    primitives: Bvh<Primitive>,
+}
+
+impl Mesh {
+    pub fn new(primitives: Vec<Primitive>) -> Mesh {
+        Mesh {
+            primitives: Bvh::new(primitives),
+        }
+    }
+
+    pub fn primitives(&self) -> &[Primitive] {
+        self.primitives.objects()
+    }
+
+    pub fn bvh(&self) -> &Bvh<Primitive> {
+        &self.primitives
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        self.primitives.hit(ray, t_min, t_max)
+    }
+
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        self.primitives.hit_packet(packet, t_min, t_max, hits);
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.primitives.bounding_box()
+    }
+}
@@ -1,12 +1,43 @@
-#![allow(dead_code)]
-
+pub use block::Block;
+pub use bvh::Bvh;
+pub use csg::{Csg, CsgOperation};
+pub use disk::Disk;
+pub use distance_field::DistanceField;
 pub use hit_record::HitRecord;
 pub use hittable::Hittable;
+pub(crate) use hittable::hit_each;
 pub use hittable_list::HittableList;
+pub use instance::Instance;
+pub use mesh::Mesh;
+pub use plane::Plane;
+pub use primitive::Primitive;
+pub use quad::{cuboid, Quad};
+pub use quadric::Quadric;
+pub(crate) use quadric::QuadricKind;
+pub use sdf::Sdf;
+pub use solid::{Crossing, Solid, Span};
 pub use sphere::Sphere;
+pub use torus::Torus;
+pub use transform::Transform;
+pub use volume::Volume;
 
+mod block;
+mod bvh;
+mod csg;
+mod disk;
+mod distance_field;
 mod hit_record;
-mod sphere;
-mod hittable_list;
 mod hittable;
-
+mod hittable_list;
+mod instance;
+mod mesh;
+mod plane;
+mod primitive;
+mod quad;
+mod quadric;
+mod sdf;
+mod solid;
+mod sphere;
+mod torus;
+mod transform;
+mod volume;
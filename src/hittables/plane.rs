@@ -0,0 +1,56 @@
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::{Aabb, Float, Onb};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Infinite plane through `point`. UVs are distances along two directions in the plane, so they
+/// don't stay between 0 and 1.
+#[derive(Clone)]
+pub struct Plane {
+    pub(crate) point: Point3,
+    pub(crate) normal: Normal3,
+    pub(crate) material: UberShader,
+    frame: Onb,
+}
+
+impl Plane {
+    /// `normal` doesn't need to be normalized, it points to the front side of the plane.
+    pub fn new(point: Point3, normal: Vec3, material: UberShader) -> Plane {
+        let frame = Onb::from_w(normal);
+        Plane {
+            point,
+            normal: Normal3::from(frame.w),
+            material,
+            frame,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            // Parallel to the plane
+            return None;
+        }
+        let t = self.normal.dot(self.point - ray.origin) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let front_face = HitRecord::set_front_face(ray, self.normal);
+        let normal = HitRecord::set_face_normal(front_face, self.normal);
+        let offset = point - self.point;
+        Some(
+            HitRecord::new(point, normal, t, front_face, self.material).with_uv(
+                Vec3::dot_product(&offset, &self.frame.u),
+                Vec3::dot_product(&offset, &self.frame.v),
+            ),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::EVERYTHING
+    }
+}
@@ -0,0 +1,251 @@
+use crate::hittables::{
+    hit_each, Block, Csg, Disk, DistanceField, HitRecord, Hittable, Instance, Plane, Quad, Quadric,
+    Solid, Span, Sphere, Torus, Volume,
+};
+use crate::math::{Aabb, Float, RayPacket, LANES};
+use crate::rendering::UberShader;
+use crate::Ray;
+
+/// Anything that can be put in a scene.
+#[derive(Clone)]
+pub enum Primitive {
+    Sphere(Sphere),
+    Plane(Plane),
+    Disk(Disk),
+    Quad(Quad),
+    Quadric(Quadric),
+    Torus(Torus),
+    Block(Block),
+    /// Boxed because it contains primitives
+    Csg(Box<Csg>),
+    /// Boxed because it contains a primitive
+    Volume(Box<Volume>),
+    /// Boxed because of the expression tree and its bounds
+    DistanceField(Box<DistanceField>),
+    /// Boxed because the two transform matrices make it much larger than the shapes
+    Instance(Box<Instance>),
+}
+
+impl Primitive {
+    /// Plural name of the kind of primitive, for statistics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Primitive::Sphere(_) => "spheres",
+            Primitive::Plane(_) => "planes",
+            Primitive::Disk(_) => "disks",
+            Primitive::Quad(_) => "quads",
+            Primitive::Quadric(quadric) => quadric.kind_name(),
+            Primitive::Torus(_) => "tori",
+            Primitive::Block(_) => "blocks",
+            Primitive::Csg(_) => "csg solids",
+            Primitive::Volume(_) => "volumes",
+            Primitive::DistanceField(_) => "distance fields",
+            Primitive::Instance(_) => "instances",
+        }
+    }
+
+    /// Number of light sources the primitive puts in the scene, every instance of a mesh adds
+    /// the lights of the mesh again.
+    pub fn lights(&self) -> usize {
+        self.lights_with(None)
+    }
+
+    /// Whether the primitive encloses a volume, which the operands of `Csg` need.
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Primitive::Sphere(_) | Primitive::Block(_) => true,
+            Primitive::Quadric(quadric) => quadric.is_closed(),
+            Primitive::Torus(torus) => torus.is_closed(),
+            Primitive::Csg(csg) => csg.first.is_closed() && csg.second.is_closed(),
+            Primitive::Plane(_)
+            | Primitive::Disk(_)
+            | Primitive::Quad(_)
+            | Primitive::DistanceField(_)
+            | Primitive::Volume(_)
+            | Primitive::Instance(_) => false,
+        }
+    }
+
+    /// `material` replaces the materials of the primitive when an instance overrides them.
+    fn lights_with(&self, material: Option<&UberShader>) -> usize {
+        match self {
+            Primitive::Sphere(Sphere {
+                material: own_material,
+                ..
+            })
+            | Primitive::Plane(Plane {
+                material: own_material,
+                ..
+            })
+            | Primitive::Disk(Disk {
+                material: own_material,
+                ..
+            })
+            | Primitive::Quad(Quad {
+                material: own_material,
+                ..
+            })
+            | Primitive::Quadric(Quadric {
+                material: own_material,
+                ..
+            })
+            | Primitive::Torus(Torus {
+                material: own_material,
+                ..
+            })
+            | Primitive::Block(Block {
+                material: own_material,
+                ..
+            }) => material.unwrap_or(own_material).is_light() as usize,
+            Primitive::Csg(csg) => {
+                csg.first.lights_with(material) + csg.second.lights_with(material)
+            }
+            Primitive::Volume(_) => 0,
+            Primitive::DistanceField(field) => {
+                material.unwrap_or(&field.material).is_light() as usize
+            }
+            Primitive::Instance(instance) => {
+                let material = material.or(instance.material());
+                instance
+                    .mesh()
+                    .primitives()
+                    .iter()
+                    .map(|primitive| primitive.lights_with(material))
+                    .sum()
+            }
+        }
+    }
+}
+
+impl Hittable for Primitive {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        match self {
+            Primitive::Sphere(sphere) => sphere.hit(ray, t_min, t_max),
+            Primitive::Plane(plane) => plane.hit(ray, t_min, t_max),
+            Primitive::Disk(disk) => disk.hit(ray, t_min, t_max),
+            Primitive::Quad(quad) => quad.hit(ray, t_min, t_max),
+            Primitive::Quadric(quadric) => quadric.hit(ray, t_min, t_max),
+            Primitive::Torus(torus) => torus.hit(ray, t_min, t_max),
+            Primitive::Block(block) => block.hit(ray, t_min, t_max),
+            Primitive::Csg(csg) => csg.hit(ray, t_min, t_max),
+            Primitive::Volume(volume) => volume.hit(ray, t_min, t_max),
+            Primitive::DistanceField(field) => field.hit(ray, t_min, t_max),
+            Primitive::Instance(instance) => instance.hit(ray, t_min, t_max),
+        }
+    }
+
+    /// Instances pass the packet on to their mesh, the other primitives test the rays one by one.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        match self {
+            Primitive::Instance(instance) => instance.hit_packet(packet, t_min, t_max, hits),
+            _ => hit_each(self, packet, t_min, t_max, hits),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            Primitive::Sphere(sphere) => sphere.bounding_box(),
+            Primitive::Plane(plane) => plane.bounding_box(),
+            Primitive::Disk(disk) => disk.bounding_box(),
+            Primitive::Quad(quad) => quad.bounding_box(),
+            Primitive::Quadric(quadric) => quadric.bounding_box(),
+            Primitive::Torus(torus) => torus.bounding_box(),
+            Primitive::Block(block) => block.bounding_box(),
+            Primitive::Csg(csg) => csg.bounding_box(),
+            Primitive::Volume(volume) => volume.bounding_box(),
+            Primitive::DistanceField(field) => field.bounding_box(),
+            Primitive::Instance(instance) => instance.bounding_box(),
+        }
+    }
+}
+
+/// Primitives that aren't closed add no spans, see `is_closed`.
+impl Solid for Primitive {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        match self {
+            Primitive::Sphere(sphere) => sphere.spans(ray, spans),
+            Primitive::Quadric(quadric) => quadric.spans(ray, spans),
+            Primitive::Torus(torus) => torus.spans(ray, spans),
+            Primitive::Block(block) => block.spans(ray, spans),
+            Primitive::Csg(csg) => csg.spans(ray, spans),
+            Primitive::Plane(_)
+            | Primitive::Disk(_)
+            | Primitive::Quad(_)
+            | Primitive::DistanceField(_)
+            | Primitive::Volume(_)
+            | Primitive::Instance(_) => {}
+        }
+    }
+}
+
+impl From<Sphere> for Primitive {
+    fn from(sphere: Sphere) -> Self {
+        Primitive::Sphere(sphere)
+    }
+}
+
+impl From<Plane> for Primitive {
+    fn from(plane: Plane) -> Self {
+        Primitive::Plane(plane)
+    }
+}
+
+impl From<Disk> for Primitive {
+    fn from(disk: Disk) -> Self {
+        Primitive::Disk(disk)
+    }
+}
+
+impl From<Quad> for Primitive {
+    fn from(quad: Quad) -> Self {
+        Primitive::Quad(quad)
+    }
+}
+
+impl From<Quadric> for Primitive {
+    fn from(quadric: Quadric) -> Self {
+        Primitive::Quadric(quadric)
+    }
+}
+
+impl From<Torus> for Primitive {
+    fn from(torus: Torus) -> Self {
+        Primitive::Torus(torus)
+    }
+}
+
+impl From<DistanceField> for Primitive {
+    fn from(field: DistanceField) -> Self {
+        Primitive::DistanceField(Box::new(field))
+    }
+}
+
+impl From<Instance> for Primitive {
+    fn from(instance: Instance) -> Self {
+        Primitive::Instance(Box::new(instance))
+    }
+}
+
+impl From<Block> for Primitive {
+    fn from(block: Block) -> Self {
+        Primitive::Block(block)
+    }
+}
+
+impl From<Csg> for Primitive {
+    fn from(csg: Csg) -> Self {
+        Primitive::Csg(Box::new(csg))
+    }
+}
+
+impl From<Volume> for Primitive {
+    fn from(volume: Volume) -> Self {
+        Primitive::Volume(Box::new(volume))
+    }
+}
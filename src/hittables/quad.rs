@@ -0,0 +1,109 @@
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::{random_scalar, Aabb, Float};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Flat boxes get this thickness, so that rays in their plane still hit the bounding box.
+pub(crate) const MIN_THICKNESS: Float = 1e-4;
+
+/// Parallelogram spanned by the edges `u` and `v` from `corner`. The front side is the one the
+/// normal `u × v` points to, UVs go from 0 to 1 along the edges.
+#[derive(Clone)]
+pub struct Quad {
+    pub(crate) corner: Point3,
+    pub(crate) u: Vec3,
+    pub(crate) v: Vec3,
+    pub(crate) material: UberShader,
+    normal: Normal3,
+    /// `u × v` divided by its squared length, turns hit points into UVs
+    w: Vec3,
+    area: Float,
+}
+
+impl Quad {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, material: UberShader) -> Quad {
+        let n = Vec3::cross_product(&u, &v);
+        Quad {
+            corner,
+            u,
+            v,
+            material,
+            normal: Normal3::from(n.unit_vector()),
+            w: n / n.squared_length(),
+            area: n.length(),
+        }
+    }
+
+    pub fn area(&self) -> Float {
+        self.area
+    }
+
+    /// Point on the quad, uniformly distributed over its area. Returns the point and its normal.
+    pub fn sample(&self) -> (Point3, Normal3) {
+        (
+            self.corner + random_scalar() * self.u + random_scalar() * self.v,
+            self.normal,
+        )
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let t = self.normal.dot(self.corner - ray.origin) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.corner;
+        let alpha = Vec3::dot_product(&self.w, &Vec3::cross_product(&offset, &self.v));
+        let beta = Vec3::dot_product(&self.w, &Vec3::cross_product(&self.u, &offset));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let front_face = HitRecord::set_front_face(ray, self.normal);
+        let normal = HitRecord::set_face_normal(front_face, self.normal);
+        Some(HitRecord::new(point, normal, t, front_face, self.material).with_uv(alpha, beta))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounds = Aabb::new(self.corner, self.corner + self.u + self.v)
+            .union(&Aabb::new(self.corner + self.u, self.corner + self.v));
+        pad(bounds)
+    }
+}
+
+/// Grows the box to `MIN_THICKNESS` along the axes it is flat on.
+pub(crate) fn pad(mut bounds: Aabb) -> Aabb {
+    for axis in 0..3 {
+        let missing = MIN_THICKNESS - (bounds.max[axis] - bounds.min[axis]);
+        if missing > 0.0 {
+            bounds.min[axis] -= missing / 2.0;
+            bounds.max[axis] += missing / 2.0;
+        }
+    }
+    bounds
+}
+
+/// The six sides of the box between the opposite corners `a` and `b`, facing outwards.
+pub fn cuboid(a: Point3, b: Point3, material: UberShader) -> [Quad; 6] {
+    let bounds = Aabb::new(a, b);
+    let (min, max) = (bounds.min, bounds.max);
+    let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+    [
+        // Front, right, back, left, top and bottom
+        Quad::new(Point3::new(min.x, min.y, max.z), dx, dy, material),
+        Quad::new(Point3::new(max.x, min.y, max.z), -dz, dy, material),
+        Quad::new(Point3::new(max.x, min.y, min.z), -dx, dy, material),
+        Quad::new(Point3::new(min.x, min.y, min.z), dz, dy, material),
+        Quad::new(Point3::new(min.x, max.y, max.z), dx, -dz, material),
+        Quad::new(Point3::new(min.x, min.y, min.z), dx, dz, material),
+    ]
+}
@@ -0,0 +1,270 @@
+use crate::hittables::solid::{pair_crossings, Crossing, Span};
+use crate::hittables::{HitRecord, Hittable, Solid};
+use crate::math::{
+    degrees_to_radians, roots_between, Aabb, Float, Matrix4, Normal3, Onb, EPSILON, PI,
+};
+use crate::rendering::UberShader;
+use crate::{Point3, Ray, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QuadricKind {
+    Cylinder,
+    Cone,
+    Paraboloid,
+    Hyperboloid,
+}
+
+/// Surface of revolution around `axis`, from `base` to `base + axis`. In a frame where the axis is
+/// +z, the side is where x² + y² = a·z² + b·z + c, which covers cylinders, cones, paraboloids and
+/// hyperboloids of one sheet. U goes around the axis from 0 to 1 over the sweep, v along the axis
+/// on the side and out from the axis on the caps.
+#[derive(Clone)]
+pub struct Quadric {
+    pub(crate) kind: QuadricKind,
+    pub(crate) base: Point3,
+    pub(crate) height: Float,
+    /// Radius at the base for cones and hyperboloids, at the end for paraboloids
+    pub(crate) radius: Float,
+    /// Radius halfway up a hyperboloid
+    pub(crate) waist: Float,
+    /// Radians around the axis, starting at the `u` direction of the frame
+    pub(crate) sweep: Float,
+    pub(crate) capped: bool,
+    pub(crate) material: UberShader,
+    frame: Onb,
+    a: Float,
+    b: Float,
+    c: Float,
+}
+
+impl Quadric {
+    /// Open tube of `radius`.
+    pub fn cylinder(base: Point3, axis: Vec3, radius: Float, material: UberShader) -> Quadric {
+        Quadric::new(QuadricKind::Cylinder, base, axis, radius, radius, material)
+    }
+
+    /// Cone with `radius` at the base and the apex at `base + axis`.
+    pub fn cone(base: Point3, axis: Vec3, radius: Float, material: UberShader) -> Quadric {
+        Quadric::new(QuadricKind::Cone, base, axis, radius, 0.0, material)
+    }
+
+    /// Bowl with its vertex at the base, `radius` wide at `base + axis`.
+    pub fn paraboloid(base: Point3, axis: Vec3, radius: Float, material: UberShader) -> Quadric {
+        Quadric::new(QuadricKind::Paraboloid, base, axis, radius, 0.0, material)
+    }
+
+    /// Hyperboloid of one sheet with `radius` at both ends, narrowing to `waist` halfway.
+    pub fn hyperboloid(
+        base: Point3,
+        axis: Vec3,
+        radius: Float,
+        waist: Float,
+        material: UberShader,
+    ) -> Quadric {
+        Quadric::new(
+            QuadricKind::Hyperboloid,
+            base,
+            axis,
+            radius,
+            waist,
+            material,
+        )
+    }
+
+    fn new(
+        kind: QuadricKind,
+        base: Point3,
+        axis: Vec3,
+        radius: Float,
+        waist: Float,
+        material: UberShader,
+    ) -> Quadric {
+        let height = axis.length();
+        let squared = radius * radius;
+        let (a, b, c) = match kind {
+            QuadricKind::Cylinder => (0.0, 0.0, squared),
+            // Radius shrinking linearly to 0 at the apex, r = radius (1 - z / height)
+            QuadricKind::Cone => {
+                let slope = squared / (height * height);
+                (slope, -2.0 * slope * height, squared)
+            }
+            QuadricKind::Paraboloid => (0.0, squared / height, 0.0),
+            // r² = waist² + s (z - height / 2)², with s chosen to reach `radius` at the ends
+            QuadricKind::Hyperboloid => {
+                let middle = height / 2.0;
+                let s = (squared - waist * waist) / (middle * middle);
+                (s, -2.0 * s * middle, squared)
+            }
+        };
+        Quadric {
+            kind,
+            base,
+            height,
+            radius,
+            waist,
+            sweep: 2.0 * PI,
+            capped: false,
+            material,
+            frame: Onb::from_w(axis),
+            a,
+            b,
+            c,
+        }
+    }
+
+    /// Closes the ends that have a radius with disks.
+    pub fn with_caps(mut self) -> Quadric {
+        self.capped = true;
+        self
+    }
+
+    /// Keeps only the part from 0 to `degrees` around the axis, counterclockwise when looking
+    /// against it.
+    pub fn with_sweep(mut self, degrees: Float) -> Quadric {
+        self.sweep = degrees_to_radians(degrees);
+        self
+    }
+
+    /// Plural name of the shape, for statistics.
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self.kind {
+            QuadricKind::Cylinder => "cylinders",
+            QuadricKind::Cone => "cones",
+            QuadricKind::Paraboloid => "paraboloids",
+            QuadricKind::Hyperboloid => "hyperboloids",
+        }
+    }
+
+    /// Whether the shape encloses a volume: capped and all the way around.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.capped && self.sweep >= 2.0 * PI * (1.0 - EPSILON)
+    }
+
+    /// Squared radius of the side at height `z`.
+    fn squared_radius(&self, z: Float) -> Float {
+        (self.a * z + self.b) * z + self.c
+    }
+
+    /// Calls `visit` for every crossing of the side and caps between `t_min` and `t_max`, in no
+    /// particular order.
+    fn crossings(&self, ray: &Ray, t_min: Float, t_max: Float, mut visit: impl FnMut(Crossing)) {
+        let o = self.frame.to_local(ray.origin - self.base);
+        let d = self.frame.to_local(ray.direction);
+        let mut crossing = |t: Float, local_normal: Vec3, angle: Float, v: Float| {
+            visit(Crossing {
+                t,
+                normal: Normal3::from(self.frame.to_world(local_normal)).unit_vector(),
+                material: self.material,
+                u: angle / self.sweep,
+                v,
+            })
+        };
+
+        let coefficients = [
+            d.x * d.x + d.y * d.y - self.a * d.z * d.z,
+            2.0 * (o.x * d.x + o.y * d.y - self.a * o.z * d.z) - self.b * d.z,
+            o.x * o.x + o.y * o.y - self.squared_radius(o.z),
+        ];
+        for &t in roots_between(&coefficients, t_min, t_max).as_slice() {
+            let point = o + t * d;
+            if !(0.0..=self.height).contains(&point.z) {
+                continue;
+            }
+            if let Some(angle) = sweep_angle(point, self.sweep) {
+                let normal = Vec3::new(point.x, point.y, -(2.0 * self.a * point.z + self.b) / 2.0);
+                crossing(t, normal, angle, point.z / self.height);
+            }
+        }
+
+        if self.capped && d.z != 0.0 {
+            for (z, normal) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (z - o.z) / d.z;
+                if t < t_min || t_max < t {
+                    continue;
+                }
+                let point = o + t * d;
+                let squared_distance = point.x * point.x + point.y * point.y;
+                let squared_radius = self.squared_radius(z);
+                if squared_distance > squared_radius {
+                    continue;
+                }
+                if let Some(angle) = sweep_angle(point, self.sweep) {
+                    let v = (squared_distance / squared_radius).sqrt();
+                    crossing(t, Vec3::new(0.0, 0.0, normal), angle, v);
+                }
+            }
+        }
+    }
+}
+
+impl Hittable for Quadric {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut closest: Option<Crossing> = None;
+        self.crossings(ray, t_min, t_max, |crossing| {
+            if closest.is_none_or(|closest| crossing.t < closest.t) {
+                closest = Some(crossing);
+            }
+        });
+        let crossing = closest?;
+        let front_face = HitRecord::set_front_face(ray, crossing.normal);
+        let normal = HitRecord::set_face_normal(front_face, crossing.normal);
+        Some(
+            HitRecord::new(
+                ray.at(crossing.t),
+                normal,
+                crossing.t,
+                front_face,
+                self.material,
+            )
+            .with_uv(crossing.u, crossing.v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let widest = self
+            .squared_radius(0.0)
+            .max(self.squared_radius(self.height))
+            .sqrt();
+        let local = Aabb::new(
+            Point3::new(-widest, -widest, 0.0),
+            Point3::new(widest, widest, self.height),
+        );
+        local.transform(&frame_matrix(self.base, &self.frame))
+    }
+}
+
+/// Only closed quadrics have an inside, open ones add no spans.
+impl Solid for Quadric {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        if !self.is_closed() {
+            return;
+        }
+        let mut crossings = Vec::with_capacity(4);
+        self.crossings(ray, Float::NEG_INFINITY, Float::INFINITY, |crossing| {
+            crossings.push(crossing)
+        });
+        crossings.sort_by(|a, b| a.t.total_cmp(&b.t));
+        pair_crossings(ray, &crossings, spans);
+    }
+}
+
+/// Angle around the z axis of the point at `offset` from the origin of a frame, `None` if it is
+/// outside the sweep.
+pub(crate) fn sweep_angle(offset: Vec3, sweep: Float) -> Option<Float> {
+    let angle = offset.y.atan2(offset.x).rem_euclid(2.0 * PI);
+    match angle <= sweep {
+        true => Some(angle),
+        false => None,
+    }
+}
+
+/// Transform from the frame of a shape at `origin` to the world.
+pub(crate) fn frame_matrix(origin: Point3, frame: &Onb) -> Matrix4 {
+    let mut matrix = Matrix4::translation(Vec3::from(origin));
+    for axis in 0..3 {
+        matrix.rows[axis][0] = frame.u[axis];
+        matrix.rows[axis][1] = frame.v[axis];
+        matrix.rows[axis][2] = frame.w[axis];
+    }
+    matrix
+}
@@ -0,0 +1,444 @@
+use crate::math::{Aabb, Float, Matrix4};
+use crate::{Point3, Vec3};
+
+/// Signed distance field: negative inside the shape, positive outside, and never more than the
+/// distance to the surface, so a ray can safely step that far. Shapes are centered on the origin
+/// and combined into a tree.
+#[derive(Debug, Clone)]
+pub enum Sdf {
+    Sphere {
+        radius: Float,
+    },
+    Cuboid {
+        half_extent: Vec3,
+    },
+    /// Box whose edges are rounded by `radius`, within the same extent
+    RoundedCuboid {
+        half_extent: Vec3,
+        radius: Float,
+    },
+    /// Ring around the y axis
+    Torus {
+        major_radius: Float,
+        minor_radius: Float,
+    },
+    /// Points within `radius` of the segment from `a` to `b`
+    Capsule {
+        a: Point3,
+        b: Point3,
+        radius: Float,
+    },
+    /// Power 8 gives the classic bulb, which fits in a sphere of radius 1.2
+    Mandelbulb {
+        power: Float,
+        iterations: u32,
+    },
+    /// Fills the cube from -1 to 1
+    MengerSponge {
+        iterations: u32,
+    },
+    /// Both shapes, blended over a distance of `smoothness` where they meet. A smoothness of 0
+    /// is a plain union, and the same for the other operations.
+    Union {
+        first: Box<Sdf>,
+        second: Box<Sdf>,
+        smoothness: Float,
+    },
+    /// `first` with `second` cut out of it
+    Subtraction {
+        first: Box<Sdf>,
+        second: Box<Sdf>,
+        smoothness: Float,
+    },
+    Intersection {
+        first: Box<Sdf>,
+        second: Box<Sdf>,
+        smoothness: Float,
+    },
+    Translation {
+        shape: Box<Sdf>,
+        offset: Vec3,
+    },
+    Rotation {
+        shape: Box<Sdf>,
+        /// Rotation from the world to the shape, the inverse of the one it was given
+        to_shape: Matrix4,
+    },
+    Scaling {
+        shape: Box<Sdf>,
+        factor: Float,
+    },
+    /// Copies at multiples of `spacing`, `2 limit + 1` of them along every axis. Distances are
+    /// only bounds when every copy stays inside its cell.
+    Repetition {
+        shape: Box<Sdf>,
+        spacing: Vec3,
+        limit: [u32; 3],
+    },
+    /// Turns the shape around the y axis by `rate` radians per unit of height
+    Twist {
+        shape: Box<Sdf>,
+        rate: Float,
+    },
+}
+
+impl Sdf {
+    pub fn sphere(radius: Float) -> Sdf {
+        Sdf::Sphere { radius }
+    }
+
+    pub fn cuboid(half_extent: Vec3) -> Sdf {
+        Sdf::Cuboid { half_extent }
+    }
+
+    pub fn rounded_cuboid(half_extent: Vec3, radius: Float) -> Sdf {
+        Sdf::RoundedCuboid {
+            half_extent,
+            radius,
+        }
+    }
+
+    pub fn torus(major_radius: Float, minor_radius: Float) -> Sdf {
+        Sdf::Torus {
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    pub fn capsule(a: Point3, b: Point3, radius: Float) -> Sdf {
+        Sdf::Capsule { a, b, radius }
+    }
+
+    pub fn mandelbulb(power: Float, iterations: u32) -> Sdf {
+        Sdf::Mandelbulb { power, iterations }
+    }
+
+    pub fn menger_sponge(iterations: u32) -> Sdf {
+        Sdf::MengerSponge { iterations }
+    }
+
+    pub fn union(self, other: Sdf, smoothness: Float) -> Sdf {
+        Sdf::Union {
+            first: Box::new(self),
+            second: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn subtraction(self, other: Sdf, smoothness: Float) -> Sdf {
+        Sdf::Subtraction {
+            first: Box::new(self),
+            second: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn intersection(self, other: Sdf, smoothness: Float) -> Sdf {
+        Sdf::Intersection {
+            first: Box::new(self),
+            second: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn translate(self, offset: Vec3) -> Sdf {
+        Sdf::Translation {
+            shape: Box::new(self),
+            offset,
+        }
+    }
+
+    /// Counterclockwise around `axis` when looking against it, like `Matrix4::rotation`.
+    pub fn rotate(self, axis: Vec3, degrees: Float) -> Sdf {
+        Sdf::Rotation {
+            shape: Box::new(self),
+            to_shape: Matrix4::rotation(axis, -degrees),
+        }
+    }
+
+    /// Scales uniformly, other scales would distort the distances.
+    pub fn scale(self, factor: Float) -> Sdf {
+        Sdf::Scaling {
+            shape: Box::new(self),
+            factor,
+        }
+    }
+
+    pub fn repeat(self, spacing: Vec3, limit: [u32; 3]) -> Sdf {
+        Sdf::Repetition {
+            shape: Box::new(self),
+            spacing,
+            limit,
+        }
+    }
+
+    pub fn twist(self, rate: Float) -> Sdf {
+        Sdf::Twist {
+            shape: Box::new(self),
+            rate,
+        }
+    }
+
+    pub fn distance(&self, point: Point3) -> Float {
+        match self {
+            Sdf::Sphere { radius } => Vec3::from(point).length() - radius,
+            Sdf::Cuboid { half_extent } => cuboid_distance(point, *half_extent),
+            Sdf::RoundedCuboid {
+                half_extent,
+                radius,
+            } => cuboid_distance(point, *half_extent - Vec3::from_float(*radius)) - radius,
+            Sdf::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let ring = point.x.hypot(point.z) - major_radius;
+                ring.hypot(point.y) - minor_radius
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let (offset, axis) = (point - *a, *b - *a);
+                let along = match axis.squared_length() > 0.0 {
+                    true => Vec3::dot_product(&offset, &axis) / axis.squared_length(),
+                    false => 0.0,
+                };
+                (offset - along.clamp(0.0, 1.0) * axis).length() - radius
+            }
+            Sdf::Mandelbulb { power, iterations } => {
+                mandelbulb_distance(point, *power, *iterations)
+            }
+            Sdf::MengerSponge { iterations } => menger_distance(point, *iterations),
+            Sdf::Union {
+                first,
+                second,
+                smoothness,
+            } => smooth_min(first.distance(point), second.distance(point), *smoothness),
+            Sdf::Subtraction {
+                first,
+                second,
+                smoothness,
+            } => -smooth_min(-first.distance(point), second.distance(point), *smoothness),
+            Sdf::Intersection {
+                first,
+                second,
+                smoothness,
+            } => -smooth_min(-first.distance(point), -second.distance(point), *smoothness),
+            Sdf::Translation { shape, offset } => shape.distance(point - *offset),
+            Sdf::Rotation { shape, to_shape } => shape.distance(to_shape.transform_point(point)),
+            Sdf::Scaling { shape, factor } => {
+                shape.distance(Point3::from(Vec3::from(point) / *factor)) * factor
+            }
+            Sdf::Repetition {
+                shape,
+                spacing,
+                limit,
+            } => {
+                // The copy in the cell of the point and the ones next to it on the side it is
+                // closer to, one of them is the closest copy
+                let mut cells = [[0.0; 2]; 3];
+                for axis in 0..3 {
+                    if limit[axis] > 0 {
+                        let limit = limit[axis] as Float;
+                        let position = point[axis] / spacing[axis];
+                        let cell = position.round();
+                        let neighbor = cell + (position - cell).signum();
+                        cells[axis] = [cell.clamp(-limit, limit), neighbor.clamp(-limit, limit)];
+                    }
+                }
+                (0..8)
+                    // Axes without repetition have a single cell
+                    .filter(|corner| {
+                        (0..3).all(|axis| limit[axis] > 0 || corner & (1 << axis) == 0)
+                    })
+                    .map(|corner| {
+                        let mut local = point;
+                        for axis in 0..3 {
+                            local[axis] -= cells[axis][(corner >> axis) & 1] * spacing[axis];
+                        }
+                        shape.distance(local)
+                    })
+                    .fold(Float::INFINITY, Float::min)
+            }
+            Sdf::Twist { shape, rate } => {
+                let (sin, cos) = (-rate * point.y).sin_cos();
+                shape.distance(Point3::new(
+                    cos * point.x - sin * point.z,
+                    point.y,
+                    sin * point.x + cos * point.z,
+                ))
+            }
+        }
+    }
+
+    /// Box that contains the shape. Smooth unions can bulge out by a quarter of their smoothness,
+    /// which is included.
+    pub fn bounding_box(&self) -> Aabb {
+        match self {
+            Sdf::Sphere { radius } => symmetric(Vec3::from_float(*radius)),
+            Sdf::Cuboid { half_extent } | Sdf::RoundedCuboid { half_extent, .. } => {
+                symmetric(*half_extent)
+            }
+            Sdf::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                symmetric(Vec3::new(outer, *minor_radius, outer))
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let radius = Vec3::from_float(*radius);
+                Aabb::new(*a - radius, *a + radius).union(&Aabb::new(*b - radius, *b + radius))
+            }
+            Sdf::Mandelbulb { .. } => symmetric(Vec3::from_float(1.25)),
+            Sdf::MengerSponge { .. } => symmetric(Vec3::ONE),
+            Sdf::Union {
+                first,
+                second,
+                smoothness,
+            } => {
+                let bounds = first.bounding_box().union(&second.bounding_box());
+                let padding = Vec3::from_float(smoothness / 4.0);
+                Aabb::new(bounds.min - padding, bounds.max + padding)
+            }
+            // Both only remove parts of the first shape
+            Sdf::Subtraction { first, .. } => first.bounding_box(),
+            Sdf::Intersection { first, second, .. } => {
+                first.bounding_box().intersection(&second.bounding_box())
+            }
+            Sdf::Translation { shape, offset } => {
+                let bounds = shape.bounding_box();
+                Aabb::new(bounds.min + *offset, bounds.max + *offset)
+            }
+            Sdf::Rotation { shape, to_shape } => {
+                shape.bounding_box().transform(&to_shape.transpose())
+            }
+            Sdf::Scaling { shape, factor } => {
+                let bounds = shape.bounding_box();
+                Aabb::new(
+                    Point3::from(Vec3::from(bounds.min) * *factor),
+                    Point3::from(Vec3::from(bounds.max) * *factor),
+                )
+            }
+            Sdf::Repetition {
+                shape,
+                spacing,
+                limit,
+            } => {
+                let bounds = shape.bounding_box();
+                let reach = Vec3::new(
+                    (spacing.x * limit[0] as Float).abs(),
+                    (spacing.y * limit[1] as Float).abs(),
+                    (spacing.z * limit[2] as Float).abs(),
+                );
+                Aabb::new(bounds.min - reach, bounds.max + reach)
+            }
+            Sdf::Twist { shape, .. } => {
+                let bounds = shape.bounding_box();
+                let radius = twist_radius(&bounds);
+                Aabb::new(
+                    Point3::new(-radius, bounds.min.y, -radius),
+                    Point3::new(radius, bounds.max.y, radius),
+                )
+            }
+        }
+    }
+
+    /// How much faster than the distance to the surface the field can change. Rays step by
+    /// the distance divided by this, so that they don't overshoot fields that twist space.
+    pub fn lipschitz(&self) -> Float {
+        match self {
+            Sdf::Union { first, second, .. }
+            | Sdf::Subtraction { first, second, .. }
+            | Sdf::Intersection { first, second, .. } => first.lipschitz().max(second.lipschitz()),
+            Sdf::Translation { shape, .. }
+            | Sdf::Rotation { shape, .. }
+            | Sdf::Scaling { shape, .. }
+            | Sdf::Repetition { shape, .. } => shape.lipschitz(),
+            // Points further from the axis move faster as the height changes
+            Sdf::Twist { shape, rate } => {
+                let stretch = rate * twist_radius(&shape.bounding_box());
+                shape.lipschitz() * (1.0 + stretch * stretch).sqrt()
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+fn symmetric(half_extent: Vec3) -> Aabb {
+    Aabb::new(Point3::ORIGIN - half_extent, Point3::ORIGIN + half_extent)
+}
+
+/// Furthest any point of the box is from the y axis.
+fn twist_radius(bounds: &Aabb) -> Float {
+    let x = bounds.min.x.abs().max(bounds.max.x.abs());
+    let z = bounds.min.z.abs().max(bounds.max.z.abs());
+    x.hypot(z)
+}
+
+fn cuboid_distance(point: Point3, half_extent: Vec3) -> Float {
+    let q = Vec3::new(
+        point.x.abs() - half_extent.x,
+        point.y.abs() - half_extent.y,
+        point.z.abs() - half_extent.z,
+    );
+    let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside
+}
+
+/// Polynomial smooth minimum, at most `smoothness / 4` below the plain minimum.
+fn smooth_min(a: Float, b: Float, smoothness: Float) -> Float {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let h = (smoothness - (a - b).abs()).max(0.0) / smoothness;
+    a.min(b) - h * h * smoothness / 4.0
+}
+
+/// Distance estimate from the derivative of the iteration z → z^power + c in spherical
+/// coordinates.
+fn mandelbulb_distance(point: Point3, power: Float, iterations: u32) -> Float {
+    let point = Vec3::from(point);
+    let mut z = point;
+    let mut derivative = 1.0;
+    let mut radius = z.length();
+    for _ in 0..iterations {
+        if radius > 2.0 || radius == 0.0 {
+            break;
+        }
+        let theta = (z.y / radius).clamp(-1.0, 1.0).acos() * power;
+        let phi = z.z.atan2(z.x) * power;
+        derivative = power * radius.powf(power - 1.0) * derivative + 1.0;
+        let scaled = radius.powf(power);
+        z = scaled
+            * Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            )
+            + point;
+        radius = z.length();
+    }
+    match radius > 0.0 {
+        true => 0.5 * radius.ln() * radius / derivative,
+        false => 0.0,
+    }
+}
+
+/// Cube with crosses cut out at every level, each a third of the size of the one before.
+fn menger_distance(point: Point3, iterations: u32) -> Float {
+    let mut distance = cuboid_distance(point, Vec3::ONE);
+    let mut scale = 1.0;
+    for _ in 0..iterations {
+        // Position inside the cell of this level, from -1 to 1
+        let level = scale;
+        let cell = |value: Float| (value * level).rem_euclid(2.0) - 1.0;
+        scale *= 3.0;
+        let r = Vec3::new(
+            (1.0 - 3.0 * cell(point.x).abs()).abs(),
+            (1.0 - 3.0 * cell(point.y).abs()).abs(),
+            (1.0 - 3.0 * cell(point.z).abs()).abs(),
+        );
+        let cross = r.x.max(r.y).min(r.y.max(r.z)).min(r.z.max(r.x));
+        distance = distance.max((cross - 1.0) / scale);
+    }
+    distance
+}
@@ -0,0 +1,75 @@
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::Float;
+use crate::rendering::UberShader;
+use crate::{Normal3, Ray};
+
+/// Point where a ray crosses the surface of a solid.
+#[derive(Clone, Copy)]
+pub struct Crossing {
+    pub t: Float,
+    /// Unit normal pointing out of the solid
+    pub normal: Normal3,
+    pub material: UberShader,
+    pub u: Float,
+    pub v: Float,
+}
+
+/// Part of a ray inside a solid, from where it enters to where it leaves.
+#[derive(Clone, Copy)]
+pub struct Span {
+    pub entry: Crossing,
+    pub exit: Crossing,
+}
+
+/// Closed shape that can tell every part of a ray inside it, not just the closest hit, which is
+/// what combining shapes with `Csg` needs.
+pub trait Solid: Hittable {
+    /// Adds the spans of the whole line through the ray to `spans`, behind its origin too, in
+    /// order along the ray.
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>);
+}
+
+/// Pairs crossings sorted along the ray into spans. A ray entering a solid moves against the
+/// outward normal, crossings that don't fit, like a ray grazing the surface, are skipped.
+pub(crate) fn pair_crossings(ray: &Ray, crossings: &[Crossing], spans: &mut Vec<Span>) {
+    let mut entry: Option<Crossing> = None;
+    for crossing in crossings {
+        let entering = crossing.normal.dot(ray.direction) < 0.0;
+        match (entering, entry) {
+            (true, _) => entry = Some(*crossing),
+            (false, Some(start)) => {
+                spans.push(Span {
+                    entry: start,
+                    exit: *crossing,
+                });
+                entry = None;
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+/// Closest crossing of the spans between `t_min` and `t_max`, as a hit.
+pub(crate) fn first_crossing(
+    spans: &[Span],
+    ray: &Ray,
+    t_min: Float,
+    t_max: Float,
+) -> Option<HitRecord> {
+    let crossing = spans
+        .iter()
+        .flat_map(|span| [span.entry, span.exit])
+        .find(|crossing| t_min <= crossing.t && crossing.t <= t_max)?;
+    let front_face = HitRecord::set_front_face(ray, crossing.normal);
+    let normal = HitRecord::set_face_normal(front_face, crossing.normal);
+    Some(
+        HitRecord::new(
+            ray.at(crossing.t),
+            normal,
+            crossing.t,
+            front_face,
+            crossing.material,
+        )
+        .with_uv(crossing.u, crossing.v),
+    )
+}
@@ -1,17 +1,19 @@
 use crate::hittables::hit_record::HitRecord;
-use crate::hittables::Hittable;
+use crate::hittables::solid::{Crossing, Span};
+use crate::hittables::{Hittable, Solid};
+use crate::math::{Aabb, Float, PI};
 use crate::rendering::UberShader;
-use crate::{Point3, Ray, Vec3};
+use crate::{Normal3, Point3, Ray, Vec3};
 
 #[derive(Clone)]
 pub struct Sphere {
     pub(crate) center: Point3,
-    pub(crate) radius: f64,
+    pub(crate) radius: Float,
     pub(crate) material: UberShader,
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let origin_to_center: Vec3 = ray.origin - self.center;
         let a = ray.direction.squared_length();
         let half_b = Vec3::dot_product(&origin_to_center, &ray.direction);
@@ -40,17 +42,59 @@ impl Hittable for Sphere {
         }
 
         let point = ray.at(root);
-        let outward_normal = (point - self.center) / self.radius;
-        let front_face = HitRecord::set_front_face(ray, outward_normal);
-        let normal = HitRecord::set_face_normal(front_face, outward_normal);
-        let hit_record = HitRecord::new(point, normal, root, self.material.clone());
+        let outward = (point - self.center) / self.radius;
+        let front_face = HitRecord::set_front_face(ray, Normal3::from(outward));
+        let normal = HitRecord::set_face_normal(front_face, Normal3::from(outward));
+        let (u, v) = sphere_uv(outward);
+        let hit_record =
+            HitRecord::new(point, normal, root, front_face, self.material).with_uv(u, v);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = Vec3::from_float(self.radius);
+        Aabb::new(self.center - radius, self.center + radius)
+    }
+}
+
+impl Solid for Sphere {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        let origin_to_center: Vec3 = ray.origin - self.center;
+        let a = ray.direction.squared_length();
+        let half_b = Vec3::dot_product(&origin_to_center, &ray.direction);
+        let c = origin_to_center.squared_length() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return;
+        }
+        let sqrt_discriminant = discriminant.sqrt();
+        let [entry, exit] = [-sqrt_discriminant, sqrt_discriminant].map(|root| {
+            let t = (-half_b + root) / a;
+            let outward = (ray.at(t) - self.center) / self.radius;
+            let (u, v) = sphere_uv(outward);
+            Crossing {
+                t,
+                normal: Normal3::from(outward),
+                material: self.material,
+                u,
+                v,
+            }
+        });
+        spans.push(Span { entry, exit });
+    }
+}
+
+/// Longitude and latitude of a unit direction from the center of a sphere, u starts at -x and
+/// goes around +y, v goes from the bottom to the top.
+pub(crate) fn sphere_uv(direction: Vec3) -> (Float, Float) {
+    let theta = (-direction.y).clamp(-1.0, 1.0).acos();
+    let phi = (-direction.z).atan2(direction.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, material: UberShader) -> Self {
+    pub fn new(center: Point3, radius: Float, material: UberShader) -> Self {
         Sphere {
             center,
             radius,
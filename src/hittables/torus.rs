@@ -0,0 +1,151 @@
+use crate::hittables::quadric::{frame_matrix, sweep_angle};
+use crate::hittables::solid::{pair_crossings, Crossing, Span};
+use crate::hittables::{HitRecord, Hittable, Solid};
+use crate::math::{degrees_to_radians, roots_between, Aabb, Float, Onb, EPSILON, PI};
+use crate::rendering::UberShader;
+use crate::{Normal3, Point3, Ray, Vec3};
+
+/// Ring around `axis` through `center`, a tube of `minor_radius` along a circle of
+/// `major_radius`. U goes around the axis from 0 to 1 over the sweep, v around the tube starting
+/// at the outer edge.
+#[derive(Clone)]
+pub struct Torus {
+    pub(crate) center: Point3,
+    pub(crate) axis: Vec3,
+    pub(crate) major_radius: Float,
+    pub(crate) minor_radius: Float,
+    /// Radians around the axis, starting at the `u` direction of the frame
+    pub(crate) sweep: Float,
+    pub(crate) material: UberShader,
+    frame: Onb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: Float,
+        minor_radius: Float,
+        material: UberShader,
+    ) -> Torus {
+        let frame = Onb::from_w(axis);
+        Torus {
+            center,
+            axis: frame.w,
+            major_radius,
+            minor_radius,
+            sweep: 2.0 * PI,
+            material,
+            frame,
+        }
+    }
+
+    /// Keeps only the part from 0 to `degrees` around the axis, leaving the ends of the tube
+    /// open.
+    pub fn with_sweep(mut self, degrees: Float) -> Torus {
+        self.sweep = degrees_to_radians(degrees);
+        self
+    }
+
+    /// Whether the tube goes all the way around, so it encloses a volume.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.sweep >= 2.0 * PI * (1.0 - EPSILON)
+    }
+
+    /// Calls `visit` for every crossing of the surface between `t_min` and `t_max`, in order
+    /// along the ray.
+    fn crossings(&self, ray: &Ray, t_min: Float, t_max: Float, mut visit: impl FnMut(Crossing)) {
+        let length = ray.direction.length();
+        let d = self.frame.to_local(ray.direction) / length;
+        // Starting from the point of the ray closest to the center keeps the coefficients small,
+        // the quartic loses precision far away. Hits are at most the outer radius from there.
+        let origin = self.frame.to_local(ray.origin - self.center);
+        let offset = -Vec3::dot_product(&origin, &d);
+        let o = origin + offset * d;
+        let outer = self.major_radius + self.minor_radius;
+        if o.squared_length() > outer * outer {
+            return;
+        }
+        let min = (t_min * length - offset).max(-outer);
+        let max = (t_max * length - offset).min(outer);
+        if max < min {
+            return;
+        }
+
+        // (|p|² + R² - r²)² = 4 R² (x² + y²) along p = o + s d, with |d| = 1
+        let major = self.major_radius * self.major_radius;
+        let m = Vec3::dot_product(&o, &d);
+        let k = o.squared_length() + major - self.minor_radius * self.minor_radius;
+        let coefficients = [
+            1.0,
+            4.0 * m,
+            4.0 * m * m + 2.0 * k - 4.0 * major * (d.x * d.x + d.y * d.y),
+            4.0 * m * k - 8.0 * major * (o.x * d.x + o.y * d.y),
+            k * k - 4.0 * major * (o.x * o.x + o.y * o.y),
+        ];
+        for &s in roots_between(&coefficients, min, max).as_slice() {
+            let point = o + s * d;
+            let Some(angle) = sweep_angle(point, self.sweep) else {
+                continue;
+            };
+            // The normal points away from the closest point on the circle inside the tube
+            let distance = (point.x * point.x + point.y * point.y).sqrt();
+            let scale = self.major_radius / distance;
+            let local_normal = point - Vec3::new(point.x * scale, point.y * scale, 0.0);
+            let tube_angle = point.z.atan2(distance - self.major_radius);
+            visit(Crossing {
+                t: (s + offset) / length,
+                normal: Normal3::from(self.frame.to_world(local_normal)).unit_vector(),
+                material: self.material,
+                u: angle / self.sweep,
+                v: tube_angle.rem_euclid(2.0 * PI) / (2.0 * PI),
+            });
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        // Crossings come in order along the ray
+        let mut closest: Option<Crossing> = None;
+        self.crossings(ray, t_min, t_max, |crossing| {
+            closest = closest.or(Some(crossing));
+        });
+        let crossing = closest?;
+        let front_face = HitRecord::set_front_face(ray, crossing.normal);
+        let normal = HitRecord::set_face_normal(front_face, crossing.normal);
+        Some(
+            HitRecord::new(
+                ray.at(crossing.t),
+                normal,
+                crossing.t,
+                front_face,
+                self.material,
+            )
+            .with_uv(crossing.u, crossing.v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        let local = Aabb::new(
+            Point3::new(-outer, -outer, -self.minor_radius),
+            Point3::new(outer, outer, self.minor_radius),
+        );
+        local.transform(&frame_matrix(self.center, &self.frame))
+    }
+}
+
+/// Only a torus that goes all the way around has an inside, a partial one adds no spans.
+impl Solid for Torus {
+    fn spans(&self, ray: &Ray, spans: &mut Vec<Span>) {
+        if !self.is_closed() {
+            return;
+        }
+        let mut crossings = Vec::with_capacity(4);
+        self.crossings(ray, Float::NEG_INFINITY, Float::INFINITY, |crossing| {
+            crossings.push(crossing)
+        });
+        pair_crossings(ray, &crossings, spans);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::hittables::{HitRecord, Hittable};
+use crate::math::{Aabb, Float, Matrix4, RayPacket, LANES};
+use crate::Ray;
+
+/// Places `object` in the world with an affine transform. Rays are moved into the space of the
+/// object instead of moving the object, so any hittable can be translated, rotated and scaled.
+#[derive(Debug, Clone)]
+pub struct Transform<T: Hittable> {
+    pub(crate) object: T,
+    to_world: Matrix4,
+    to_object: Matrix4,
+}
+
+impl<T: Hittable> Transform<T> {
+    /// `None` if `to_world` can't be inverted, like a scale of 0 along an axis.
+    pub fn new(object: T, to_world: Matrix4) -> Option<Transform<T>> {
+        Some(Transform {
+            object,
+            to_world,
+            to_object: to_world.inverse()?,
+        })
+    }
+
+    pub fn object(&self) -> &T {
+        &self.object
+    }
+
+    pub fn to_world(&self) -> &Matrix4 {
+        &self.to_world
+    }
+}
+
+impl<T: Hittable> Transform<T> {
+    /// The direction keeps its scale, so t is the same in both spaces.
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.to_object.transform_point(ray.origin),
+            self.to_object.transform_vector(ray.direction),
+        )
+    }
+
+    fn world_record(&self, mut record: HitRecord) -> HitRecord {
+        record.point = self.to_world.transform_point(record.point);
+        record.normal = self.to_object.transform_normal(record.normal).unit_vector();
+        record.density = record
+            .density
+            .map(|density| density.transformed(&self.to_object));
+        record
+    }
+}
+
+impl<T: Hittable> Hittable for Transform<T> {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        // The direction keeps its scale, so t is the same in both spaces
+        let record = self.object.hit(&self.object_ray(ray), t_min, t_max)?;
+        Some(self.world_record(record))
+    }
+
+    /// Moves the whole packet into the space of the object, whose rays stay as close together
+    /// as they were.
+    fn hit_packet(
+        &self,
+        packet: &RayPacket,
+        t_min: Float,
+        t_max: &mut [Float; LANES],
+        hits: &mut [Option<HitRecord>; LANES],
+    ) {
+        let before = *t_max;
+        let object_packet = RayPacket::new(packet.rays.map(|ray| self.object_ray(&ray)));
+        self.object.hit_packet(&object_packet, t_min, t_max, hits);
+        for lane in 0..LANES {
+            if t_max[lane] < before[lane] {
+                hits[lane] = hits[lane].take().map(|record| self.world_record(record));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box().transform(&self.to_world)
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::hittables::{Block, HitRecord, Hittable, Primitive};
+use crate::math::{Aabb, Float};
+use crate::rendering::{DensityField, DensityGrid, Medium, UberShader};
+use crate::{Point3, Ray};
+
+/// Medium filling a closed primitive, whose surface is invisible and only marks where the
+/// medium starts. Its density is constant, or varies with a density field that scales the
+/// coefficients. Volumes don't nest and the camera needs to be outside of them, use `Fog` for a
+/// medium around the camera.
+#[derive(Clone)]
+pub struct Volume {
+    pub(crate) boundary: Box<Primitive>,
+    pub(crate) medium: Medium,
+    pub(crate) density: Option<DensityField>,
+    material: UberShader,
+}
+
+impl Volume {
+    pub fn new(boundary: impl Into<Primitive>, medium: Medium) -> Volume {
+        Volume {
+            boundary: Box::new(boundary.into()),
+            medium,
+            density: None,
+            material: UberShader::interface(medium),
+        }
+    }
+
+    /// Volume of `grid` stretched over the box between two corners, with the coefficients of
+    /// `medium` for a density of 1.
+    pub fn from_grid(grid: Arc<DensityGrid>, a: Point3, b: Point3, medium: Medium) -> Volume {
+        Volume::new(Block::new(a, b, UberShader::interface(medium)), medium)
+            .with_density(DensityField::new(grid, a, b))
+    }
+
+    /// Scales the coefficients of the medium by `density` at every point.
+    pub fn with_density(mut self, density: DensityField) -> Volume {
+        self.density = Some(density);
+        self
+    }
+
+    pub fn boundary(&self) -> &Primitive {
+        &self.boundary
+    }
+
+    pub fn medium(&self) -> &Medium {
+        &self.medium
+    }
+
+    pub fn density(&self) -> Option<&DensityField> {
+        self.density.as_ref()
+    }
+}
+
+impl Hittable for Volume {
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut record = self.boundary.hit(ray, t_min, t_max)?;
+        record.material = self.material;
+        record.density = self.density.clone();
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
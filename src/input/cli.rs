@@ -1,8 +1,40 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use raytracing_rust::compare::ErrorMap;
+use raytracing_rust::math::Float;
+use raytracing_rust::output::aov::Aov;
+use raytracing_rust::output::display::ToneMapping;
+use raytracing_rust::output::image::{BitDepth, ImageFormat, PpmEncoding};
+use raytracing_rust::rendering::{Denoiser, TileOrder};
+use raytracing_rust::scene::presets::Preset;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
-pub struct Args {
+pub struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Render a scene to an image
+    Render(RenderArgs),
+    /// Print statistics about a scene
+    Info(InfoArgs),
+    /// Render the built-in scenes and report the rendering speed
+    Bench(BenchArgs),
+    /// Compare an image to a reference with error metrics
+    Diff(DiffArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct RenderArgs {
+    /// Scene to render
+    #[arg(long, value_enum, default_value_t = Preset::FourSpheres)]
+    pub(crate) scene: Preset,
+
     /// Number of direct samples per pixel
     #[arg(short, long, default_value_t = 50)]
     pub(crate) samples: i32,
@@ -18,4 +50,131 @@ pub struct Args {
     // Image width in pixels
     #[arg(short, long, default_value_t = 1920)]
     pub(crate) width: usize,
+
+    /// Tile edge length in pixels
+    #[arg(long, default_value_t = 32)]
+    pub(crate) tile_size: usize,
+
+    /// Order in which tiles are rendered
+    #[arg(long, value_enum, default_value_t = TileOrder::Spiral)]
+    pub(crate) tile_order: TileOrder,
+
+    /// Print rays/second for every tile
+    #[arg(long)]
+    pub(crate) tile_stats: bool,
+
+    /// Trace wavelengths instead of RGB, for dispersion in glass with an Abbe number
+    #[arg(long)]
+    pub(crate) spectral: bool,
+
+    /// Seed for the random number generators, random if not given
+    #[arg(long)]
+    pub(crate) seed: Option<u64>,
+
+    /// Samples added to every pixel between checkpoints [default: 4 with --checkpoint, otherwise all]
+    #[arg(long)]
+    pub(crate) pass_samples: Option<i32>,
+
+    /// File the accumulated film is periodically saved to
+    #[arg(long)]
+    pub(crate) checkpoint: Option<PathBuf>,
+
+    /// Minimum number of seconds between two checkpoints
+    #[arg(long, default_value_t = 300)]
+    pub(crate) checkpoint_interval: u64,
+
+    /// Continue the render stored in the checkpoint file up to the requested sample count
+    #[arg(long, requires = "checkpoint")]
+    pub(crate) resume: bool,
+
+    /// Output image, "-" writes to stdout
+    #[arg(short, long, default_value = "image.exr")]
+    pub(crate) output: PathBuf,
+
+    /// Output format, inferred from the output extension if not given
+    #[arg(short, long, value_enum)]
+    pub(crate) format: Option<ImageFormat>,
+
+    /// Bits per channel of PNG output
+    #[arg(long, value_enum, default_value_t = BitDepth::Eight)]
+    pub(crate) bit_depth: BitDepth,
+
+    /// ASCII (P3) or binary (P6) PPM output
+    #[arg(long, value_enum, default_value_t = PpmEncoding::Binary)]
+    pub(crate) ppm_encoding: PpmEncoding,
+
+    /// Exposure adjustment in stops for PNG and PPM output
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub(crate) exposure: Float,
+
+    /// Tone mapping operator for PNG and PPM output
+    #[arg(long, value_enum, default_value_t = ToneMapping::None)]
+    pub(crate) tone_map: ToneMapping,
+
+    /// Render passes written as extra layers of EXR output, comma separated
+    #[arg(long = "aov", value_enum, value_delimiter = ',')]
+    pub(crate) aovs: Vec<Aov>,
+
+    /// Write every render pass, same as listing all of them with --aov
+    #[arg(long, conflicts_with = "aovs")]
+    pub(crate) all_aovs: bool,
+
+    /// Denoiser applied to the output image
+    #[arg(long, value_enum, default_value_t = Denoiser::None)]
+    pub(crate) denoiser: Denoiser,
+
+    /// Number of passes of the à-trous denoiser
+    #[arg(long, default_value_t = 5)]
+    pub(crate) denoise_iterations: u32,
+
+    /// Also write the image before denoising, format inferred from the extension
+    #[arg(long, requires = "denoiser")]
+    pub(crate) noisy_output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct InfoArgs {
+    /// Scene to describe
+    #[arg(long, value_enum, default_value_t = Preset::FourSpheres)]
+    pub(crate) scene: Preset,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Scenes to render, all of them if not given
+    #[arg(long = "scene", value_enum, value_delimiter = ',')]
+    pub(crate) scenes: Vec<Preset>,
+
+    /// Number of direct samples per pixel
+    #[arg(short, long, default_value_t = 16)]
+    pub(crate) samples: i32,
+
+    // Image height in pixels
+    #[arg(short = 'e', long, default_value_t = 360)]
+    pub(crate) height: usize,
+
+    // Image width in pixels
+    #[arg(short, long, default_value_t = 640)]
+    pub(crate) width: usize,
+
+    /// Trace the camera rays of a pixel in packets, to measure the packet traversal
+    #[arg(long)]
+    pub(crate) packets: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Ground truth image
+    pub(crate) reference: PathBuf,
+
+    /// Image compared to the reference
+    pub(crate) image: PathBuf,
+
+    /// Write a false-color image of the per-pixel error, format inferred from the extension
+    #[arg(short, long)]
+    pub(crate) error_output: Option<PathBuf>,
+
+    /// Error shown by the false-color image
+    #[arg(long, value_enum, default_value_t = ErrorMap::Flip, requires = "error_output")]
+    pub(crate) error_map: ErrorMap,
 }
@@ -1,3 +1,3 @@
 mod cli;
 
-pub use cli::Args;
+pub use cli::{BenchArgs, Cli, Command, DiffArgs, InfoArgs, RenderArgs};
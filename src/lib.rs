@@ -0,0 +1,49 @@
+//! Peter Shirley's "Raytracing in one Weekend" implemented in Rust
+//!
+//! Build a [`Scene`], describe the image with [`RenderSettings`] and render it into a [`Film`],
+//! which the [`output`] module writes to disk:
+//!
+//! ```no_run
+//! # fn main() -> raytracing_rust::Result<()> {
+//! use raytracing_rust::output::image::{write_image, ImageFormat, OutputOptions};
+//! use raytracing_rust::scene::presets;
+//! use raytracing_rust::RenderSettings;
+//!
+//! let scene = presets::four_spheres()?;
+//! let settings = RenderSettings {
+//!     image_width: 640,
+//!     image_height: 360,
+//!     samples_per_pixel: 16,
+//!     ..RenderSettings::default()
+//! };
+//! let film = scene.render(&settings)?;
+//! let options = OutputOptions {
+//!     format: ImageFormat::Png,
+//!     ..OutputOptions::default()
+//! };
+//! write_image("image.png".as_ref(), &film, &options)?;
+//! # Ok(())
+//! # }
+//! ```
+
+// Constants are written to f64 precision and conversions to f32 for output are needed in the
+// default build
+#![cfg_attr(
+    feature = "f32",
+    allow(clippy::excessive_precision, clippy::unnecessary_cast)
+)]
+
+pub use error::{Error, Result};
+pub use hittables::{Hittable, HittableList};
+pub use math::{clamp, random_double, Color, Float, Normal3, Point3, Ray, Vec3};
+pub use rendering::{render, Film, RenderSettings};
+pub use scene::{Scene, SceneBuilder};
+
+pub mod compare;
+pub mod error;
+pub mod hittables;
+pub mod materials;
+pub mod math;
+pub mod output;
+pub mod rendering;
+pub mod scene;
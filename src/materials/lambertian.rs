@@ -9,10 +9,10 @@ pub struct LambertianMaterial {
 
 impl Material for LambertianMaterial {
     fn scatter(&self, _ray_in: &Ray, record: &HitRecord) -> Option<ScatteringResult> {
-        let mut scatter_direction: Vec3 = record.normal + Vec3::random_point_in_unit_vector();
+        let mut scatter_direction: Vec3 = Vec3::from(record.normal) + Vec3::random_point_in_unit_vector();
 
         if scatter_direction.near_zero() {
-            scatter_direction = record.normal;
+            scatter_direction = Vec3::from(record.normal);
         }
 
         Some(ScatteringResult{ ray: Ray::new(record.point, scatter_direction), attenuation: self.albedo })
@@ -1,19 +1,19 @@
-use crate::{Color, Ray, Vec3};
+use crate::{Color, Float, Ray, Vec3};
 use crate::hittables::HitRecord;
 use crate::rendering::{Material, ScatteringResult};
 
 #[derive(Clone, Copy)]
 pub struct MetallicMaterial {
     pub albedo: Color,
-    pub fuzz: f64
+    pub fuzz: Float
 }
 
 impl Material for MetallicMaterial {
     fn scatter(&self, ray_in: &Ray, record: &HitRecord) -> Option<ScatteringResult>{
-        let reflected: Vec3 = Vec3::reflect(ray_in.direction.unit_vector(), record.normal);
+        let reflected: Vec3 = Vec3::reflect(ray_in.direction.unit_vector(), Vec3::from(record.normal));
         let scattered = Ray::new(record.point, reflected + self.fuzz * Vec3::random_point_in_unit_sphere());
         let attenuation = self.albedo;
-        if Vec3::dot_product(&scattered.direction, &record.normal) > 0.0_f64 {
+        if record.normal.dot(scattered.direction) > 0.0 {
             Some(ScatteringResult{ray: scattered, attenuation})
         } else {
             None
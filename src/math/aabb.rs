@@ -0,0 +1,136 @@
+use crate::math::{Float, Matrix4, Point3, Ray, Vec3};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    /// Contains nothing, the starting point of a union.
+    pub const EMPTY: Aabb = Aabb {
+        min: Point3::POSITIVE_INFINITY,
+        max: Point3::NEGATIVE_INFINITY,
+    };
+    /// Bounds of objects that extend to infinity, like planes.
+    pub const EVERYTHING: Aabb = Aabb {
+        min: Point3::NEGATIVE_INFINITY,
+        max: Point3::POSITIVE_INFINITY,
+    };
+
+    /// Smallest box containing both corners, in any order.
+    pub fn new(a: Point3, b: Point3) -> Aabb {
+        Aabb {
+            min: Point3::min(a, b),
+            max: Point3::max(a, b),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Point3::min(self.min, other.min),
+            max: Point3::max(self.max, other.max),
+        }
+    }
+
+    /// Box of the space inside both, `EMPTY` if they don't overlap.
+    pub fn intersection(&self, other: &Aabb) -> Aabb {
+        let bounds = Aabb {
+            min: Point3::max(self.min, other.min),
+            max: Point3::min(self.max, other.max),
+        };
+        match bounds.is_empty() {
+            true => Aabb::EMPTY,
+            false => bounds,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    /// Whether the box is finite, so it can be put in a BVH.
+    pub fn is_bounded(&self) -> bool {
+        (0..3).all(|axis| self.min[axis].is_finite() && self.max[axis].is_finite())
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::midpoint(self.min, self.max)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.extent();
+        match (
+            extent.x > extent.y,
+            extent.x > extent.z,
+            extent.y > extent.z,
+        ) {
+            (true, true, _) => 0,
+            (false, _, true) => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn surface_area(&self) -> Float {
+        let extent = self.extent();
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    /// Bounds of the box after transforming it, which contain the transformed object.
+    pub fn transform(&self, matrix: &Matrix4) -> Aabb {
+        if !self.is_bounded() {
+            return match self.is_empty() {
+                true => Aabb::EMPTY,
+                false => Aabb::EVERYTHING,
+            };
+        }
+        (0..8)
+            .map(|corner| {
+                let pick = |axis: usize| match corner & (1 << axis) {
+                    0 => self.min[axis],
+                    _ => self.max[axis],
+                };
+                matrix.transform_point(Point3::new(pick(0), pick(1), pick(2)))
+            })
+            .fold(Aabb::EMPTY, |bounds, point| {
+                bounds.union(&Aabb::new(point, point))
+            })
+    }
+
+    /// Slab test, `inverse_direction` is 1 divided by every component of the direction of the ray.
+    pub fn hit(&self, ray: &Ray, inverse_direction: Vec3, t_min: Float, t_max: Float) -> bool {
+        self.interval(ray, inverse_direction, t_min, t_max)
+            .is_some()
+    }
+
+    /// Part of `t_min` to `t_max` where the ray is inside the box, see `hit`.
+    pub fn interval(
+        &self,
+        ray: &Ray,
+        inverse_direction: Vec3,
+        t_min: Float,
+        t_max: Float,
+    ) -> Option<(Float, Float)> {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let (near, far) = match inverse_direction[axis] < 0.0 {
+                true => (t1, t0),
+                false => (t0, t1),
+            };
+            // NaN from a ray in the plane of a slab leaves the interval unchanged
+            t_min = near.max(t_min);
+            t_max = far.min(t_max);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
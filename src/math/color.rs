@@ -0,0 +1,198 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Sub};
+
+use crate::math::{random_scalar, random_scalar_in_range, Float, Scalar};
+
+/// Linear RGB color, or in spectral mode the values at the three wavelengths of a path. Colors
+/// are added, scaled and multiplied channel by channel, but have no direction or length.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Color<T = Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Color<T> {
+    pub const ZERO: Self = Color {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ZERO,
+    };
+    pub const ONE: Self = Color {
+        x: T::ONE,
+        y: T::ONE,
+        z: T::ONE,
+    };
+
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+    pub fn from_float(val: T) -> Self {
+        Color::new(val, val, val)
+    }
+    pub fn max_component(&self) -> T {
+        self.x.max(self.y).max(self.z)
+    }
+    /// Average of the channels.
+    pub fn average(&self) -> T {
+        (self.x + self.y + self.z) / T::from_f64(3.0)
+    }
+    pub fn map(&self, function: impl Fn(T) -> T) -> Self {
+        Color::new(function(self.x), function(self.y), function(self.z))
+    }
+    /// Product of a 3x3 matrix given by its rows and the color, e.g. to change color spaces.
+    pub fn transform(&self, rows: &[[T; 3]; 3]) -> Self {
+        let row = |row: &[T; 3]| row[0] * self.x + row[1] * self.y + row[2] * self.z;
+        Color::new(row(&rows[0]), row(&rows[1]), row(&rows[2]))
+    }
+}
+
+impl Color {
+    pub fn random() -> Self {
+        Color::new(random_scalar(), random_scalar(), random_scalar())
+    }
+    pub fn random_in_range(min: Float, max: Float) -> Self {
+        Color::new(
+            random_scalar_in_range(min, max),
+            random_scalar_in_range(min, max),
+            random_scalar_in_range(min, max),
+        )
+    }
+}
+
+impl<T: Scalar> Add for Color<T> {
+    type Output = Color<T>;
+
+    fn add(self, rhs: Color<T>) -> Self::Output {
+        Color {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Sub for Color<T> {
+    type Output = Color<T>;
+
+    fn sub(self, rhs: Color<T>) -> Self::Output {
+        Color {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Mul for Color<T> {
+    type Output = Color<T>;
+
+    fn mul(self, rhs: Color<T>) -> Self::Output {
+        Color {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+            z: self.z * rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Mul<T> for Color<T> {
+    type Output = Color<T>;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        Color {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl Mul<Color> for Float {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl<T: Scalar> Div for Color<T> {
+    type Output = Color<T>;
+
+    fn div(self, rhs: Color<T>) -> Self::Output {
+        Color {
+            x: self.x / rhs.x,
+            y: self.y / rhs.y,
+            z: self.z / rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Div<T> for Color<T> {
+    type Output = Color<T>;
+
+    fn div(self, rhs: T) -> Self::Output {
+        Color {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+        }
+    }
+}
+
+impl<T: Scalar> AddAssign for Color<T> {
+    fn add_assign(&mut self, rhs: Color<T>) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl<T: Scalar> MulAssign for Color<T> {
+    fn mul_assign(&mut self, rhs: Color<T>) {
+        self.x *= rhs.x;
+        self.y *= rhs.y;
+        self.z *= rhs.z;
+    }
+}
+
+impl<T: Scalar> MulAssign<T> for Color<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+    }
+}
+
+impl<T: Scalar> DivAssign<T> for Color<T> {
+    fn div_assign(&mut self, rhs: T) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+    }
+}
+
+impl<T> Index<usize> for Color<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if index == 0 {
+            &self.x
+        } else if index == 1 {
+            &self.y
+        } else {
+            &self.z
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Color<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        if index == 0 {
+            &mut self.x
+        } else if index == 1 {
+            &mut self.y
+        } else {
+            &mut self.z
+        }
+    }
+}
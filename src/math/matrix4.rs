@@ -0,0 +1,153 @@
+use std::ops::Mul;
+
+use crate::math::{degrees_to_radians, Float, Normal3, Point3, Vec3};
+
+/// Row-major 4x4 matrix of an affine transform, applied to column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    pub rows: [[Float; 4]; 4],
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Matrix4::IDENTITY
+    }
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        rows: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(offset: Vec3) -> Matrix4 {
+        let mut matrix = Matrix4::IDENTITY;
+        for axis in 0..3 {
+            matrix.rows[axis][3] = offset[axis];
+        }
+        matrix
+    }
+
+    pub fn scaling(factors: Vec3) -> Matrix4 {
+        let mut matrix = Matrix4::IDENTITY;
+        for axis in 0..3 {
+            matrix.rows[axis][axis] = factors[axis];
+        }
+        matrix
+    }
+
+    /// Counterclockwise rotation around `axis` when looking against it, which doesn't need to be
+    /// normalized.
+    pub fn rotation(axis: Vec3, degrees: Float) -> Matrix4 {
+        let axis = axis.unit_vector();
+        let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let c = 1.0 - cos;
+        Matrix4 {
+            rows: [
+                [
+                    cos + x * x * c,
+                    x * y * c - z * sin,
+                    x * z * c + y * sin,
+                    0.0,
+                ],
+                [
+                    y * x * c + z * sin,
+                    cos + y * y * c,
+                    y * z * c - x * sin,
+                    0.0,
+                ],
+                [
+                    z * x * c - y * sin,
+                    z * y * c + x * sin,
+                    cos + z * z * c,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut transposed = Matrix4::IDENTITY;
+        for row in 0..4 {
+            for column in 0..4 {
+                transposed.rows[row][column] = self.rows[column][row];
+            }
+        }
+        transposed
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting, `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut matrix = self.rows;
+        let mut inverse = Matrix4::IDENTITY.rows;
+        for column in 0..4 {
+            let pivot = (column..4).max_by(|a, b| {
+                matrix[*a][column]
+                    .abs()
+                    .total_cmp(&matrix[*b][column].abs())
+            })?;
+            if matrix[pivot][column].abs() < 1e-12 || !matrix[pivot][column].is_finite() {
+                return None;
+            }
+            matrix.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = 1.0 / matrix[column][column];
+            for index in 0..4 {
+                matrix[column][index] *= scale;
+                inverse[column][index] *= scale;
+            }
+            for row in (0..4).filter(|row| *row != column) {
+                let factor = matrix[row][column];
+                for index in 0..4 {
+                    matrix[row][index] -= factor * matrix[column][index];
+                    inverse[row][index] -= factor * inverse[column][index];
+                }
+            }
+        }
+        Some(Matrix4 { rows: inverse })
+    }
+
+    pub fn transform_point(&self, point: Point3) -> Point3 {
+        let offset = Vec3::new(self.rows[0][3], self.rows[1][3], self.rows[2][3]);
+        Point3::from(self.transform_vector(Vec3::from(point))) + offset
+    }
+
+    /// Applies the matrix without the translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let row = |index: usize| {
+            let row = self.rows[index];
+            row[0] * vector.x + row[1] * vector.y + row[2] * vector.z
+        };
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    /// Normals stay perpendicular to the surface when transformed by the inverse transpose, so
+    /// `self` is the inverse of the matrix that transforms the points. Not normalized.
+    pub fn transform_normal(&self, normal: Normal3) -> Normal3 {
+        Normal3::from(self.transpose().transform_vector(Vec3::from(normal)))
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    /// Transform that applies `rhs` first and then `self`.
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut product = [[0.0; 4]; 4];
+        for (row, product_row) in product.iter_mut().enumerate() {
+            for (column, value) in product_row.iter_mut().enumerate() {
+                *value = (0..4)
+                    .map(|index| self.rows[row][index] * rhs.rows[index][column])
+                    .sum();
+            }
+        }
+        Matrix4 { rows: product }
+    }
+}
@@ -1,23 +1,41 @@
-#![allow(dead_code)]
-
 pub use {
-    random::{random_double, random_double_in_range, random_float, random_float_in_range},
+    aabb::Aabb,
+    color::Color,
+    matrix4::Matrix4,
+    normal3::Normal3,
+    onb::Onb,
+    point3::Point3,
+    polynomial::{roots_between, Roots},
+    random::{
+        random_double, random_double_in_range, random_float, random_float_in_range, random_seed,
+        restore_rng, sample_seed, save_rng, seed_rng, RngState,
+    },
     ray::Ray,
+    scalar::{random_scalar, random_scalar_in_range, Float, Scalar},
     util::clamp,
-    vec3::Color,
-    vec3::Point3,
     vec3::Vec3,
+    wide::{RayPacket, WideAabb, LANES},
 };
 
+mod aabb;
+mod color;
+mod matrix4;
+mod normal3;
+mod onb;
+mod point3;
+mod polynomial;
 mod random;
 mod ray;
+mod scalar;
 mod util;
 mod vec3;
-mod vec4;
+mod wide;
 
-pub const INFINITY: f64 = f64::MAX;
-pub const PI: f64 = std::f64::consts::PI;
+pub const INFINITY: Float = Float::MAX;
+/// Relative difference that is still rounding error rather than a real difference.
+pub const EPSILON: Float = if cfg!(feature = "f32") { 1e-4 } else { 1e-9 };
+pub const PI: Float = <Float as Scalar>::PI;
 
-pub fn degrees_to_radians(degrees: f64) -> f64 {
-    degrees * PI / 180.0_f64
+pub fn degrees_to_radians(degrees: Float) -> Float {
+    degrees * PI / 180.0
 }
@@ -0,0 +1,97 @@
+use std::ops::{Index, Mul, Neg};
+
+use crate::math::{Float, Scalar, Vec3};
+
+/// Direction perpendicular to a surface. Unlike vectors, normals are transformed by the inverse
+/// transpose of a transform, see `Matrix4::transform_normal`, so they stay perpendicular to
+/// surfaces that are scaled unevenly.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Normal3<T = Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Normal3<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+    pub fn length(&self) -> T {
+        Vec3::from(*self).length()
+    }
+    pub fn unit_vector(&self) -> Self {
+        let length = self.length();
+        Normal3::new(self.x / length, self.y / length, self.z / length)
+    }
+    pub fn dot(&self, vector: Vec3<T>) -> T {
+        self.x * vector.x + self.y * vector.y + self.z * vector.z
+    }
+    /// The normal flipped to the side of `vector`.
+    pub fn face_forward(&self, vector: Vec3<T>) -> Self {
+        match self.dot(vector) < T::ZERO {
+            true => -*self,
+            false => *self,
+        }
+    }
+}
+
+impl<T> From<Vec3<T>> for Normal3<T> {
+    /// Normal along `vector`, which is kept as long as it is.
+    fn from(vector: Vec3<T>) -> Self {
+        Normal3 {
+            x: vector.x,
+            y: vector.y,
+            z: vector.z,
+        }
+    }
+}
+
+impl<T> From<Normal3<T>> for Vec3<T> {
+    fn from(normal: Normal3<T>) -> Self {
+        Vec3 {
+            x: normal.x,
+            y: normal.y,
+            z: normal.z,
+        }
+    }
+}
+
+impl<T: Scalar> Mul<T> for Normal3<T> {
+    type Output = Vec3<T>;
+
+    /// Offset along the normal.
+    fn mul(self, rhs: T) -> Self::Output {
+        Vec3::from(self) * rhs
+    }
+}
+
+impl Mul<Normal3> for Float {
+    type Output = Vec3;
+
+    /// Offset along the normal.
+    fn mul(self, rhs: Normal3) -> Self::Output {
+        self * Vec3::from(rhs)
+    }
+}
+
+impl<T: Scalar> Neg for Normal3<T> {
+    type Output = Normal3<T>;
+
+    fn neg(self) -> Self::Output {
+        Normal3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T> Index<usize> for Normal3<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if index == 0 {
+            &self.x
+        } else if index == 1 {
+            &self.y
+        } else {
+            &self.z
+        }
+    }
+}
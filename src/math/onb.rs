@@ -0,0 +1,36 @@
+use crate::math::Vec3;
+
+/// Orthonormal basis, used to move directions in and out of a local frame where `w` is +z.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Basis around the direction `w`, which doesn't need to be normalized.
+    pub fn from_w(w: Vec3) -> Onb {
+        let w = w.unit_vector();
+        // Any vector that isn't parallel to w
+        let a = match w.x.abs() > 0.9 {
+            true => Vec3::new(0.0, 1.0, 0.0),
+            false => Vec3::new(1.0, 0.0, 0.0),
+        };
+        let v = Vec3::cross_product(&w, &a).unit_vector();
+        let u = Vec3::cross_product(&v, &w);
+        Onb { u, v, w }
+    }
+
+    pub fn to_world(&self, local: Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+
+    pub fn to_local(&self, world: Vec3) -> Vec3 {
+        Vec3::new(
+            Vec3::dot_product(&world, &self.u),
+            Vec3::dot_product(&world, &self.v),
+            Vec3::dot_product(&world, &self.w),
+        )
+    }
+}
@@ -0,0 +1,156 @@
+use std::ops::{Add, AddAssign, Index, IndexMut, Sub, SubAssign};
+
+use crate::math::{Float, Scalar, Vec3};
+
+/// Position in space. Points can be moved by vectors and subtracted from each other, but not
+/// added, scaled or normalized; `Vec3::from` gives the vector from the origin when that is meant.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point3<T = Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Point3<T> {
+    pub const ORIGIN: Self = Point3 {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ZERO,
+    };
+    pub const POSITIVE_INFINITY: Self = Point3 {
+        x: T::INFINITY,
+        y: T::INFINITY,
+        z: T::INFINITY,
+    };
+    pub const NEGATIVE_INFINITY: Self = Point3 {
+        x: T::NEG_INFINITY,
+        y: T::NEG_INFINITY,
+        z: T::NEG_INFINITY,
+    };
+
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+    pub fn distance(a: Point3<T>, b: Point3<T>) -> T {
+        (a - b).length()
+    }
+    pub fn squared_distance(a: Point3<T>, b: Point3<T>) -> T {
+        (a - b).squared_length()
+    }
+    /// Point a fraction `t` of the way from `a` to `b`.
+    pub fn lerp(a: Point3<T>, b: Point3<T>, t: T) -> Self {
+        a + (b - a) * t
+    }
+    pub fn midpoint(a: Point3<T>, b: Point3<T>) -> Self {
+        Point3::lerp(a, b, T::from_f64(0.5))
+    }
+    /// Smallest coordinates of both points along every axis.
+    pub fn min(a: Point3<T>, b: Point3<T>) -> Self {
+        Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+    }
+    /// Largest coordinates of both points along every axis.
+    pub fn max(a: Point3<T>, b: Point3<T>) -> Self {
+        Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+    }
+}
+
+impl<T> From<Vec3<T>> for Point3<T> {
+    /// Point at the end of `vector` from the origin.
+    fn from(vector: Vec3<T>) -> Self {
+        Point3 {
+            x: vector.x,
+            y: vector.y,
+            z: vector.z,
+        }
+    }
+}
+
+impl<T> From<Point3<T>> for Vec3<T> {
+    /// Vector from the origin to `point`.
+    fn from(point: Point3<T>) -> Self {
+        Vec3 {
+            x: point.x,
+            y: point.y,
+            z: point.z,
+        }
+    }
+}
+
+impl<T: Scalar> Add<Vec3<T>> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn add(self, rhs: Vec3<T>) -> Self::Output {
+        Point3 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Sub<Vec3<T>> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn sub(self, rhs: Vec3<T>) -> Self::Output {
+        Point3 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> Sub for Point3<T> {
+    type Output = Vec3<T>;
+
+    /// Vector from `rhs` to `self`.
+    fn sub(self, rhs: Point3<T>) -> Self::Output {
+        Vec3 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl<T: Scalar> AddAssign<Vec3<T>> for Point3<T> {
+    fn add_assign(&mut self, rhs: Vec3<T>) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl<T: Scalar> SubAssign<Vec3<T>> for Point3<T> {
+    fn sub_assign(&mut self, rhs: Vec3<T>) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+impl<T> Index<usize> for Point3<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        if index == 0 {
+            &self.x
+        } else if index == 1 {
+            &self.y
+        } else {
+            &self.z
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Point3<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        if index == 0 {
+            &mut self.x
+        } else if index == 1 {
+            &mut self.y
+        } else {
+            &mut self.z
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::math::Float;
+
+/// Highest degree `roots_between` solves.
+const MAX_DEGREE: usize = 4;
+
+/// Real roots of a polynomial, in increasing order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Roots {
+    values: [Float; MAX_DEGREE],
+    count: usize,
+}
+
+impl Roots {
+    pub fn as_slice(&self) -> &[Float] {
+        &self.values[..self.count]
+    }
+
+    fn push(&mut self, root: Float) {
+        // A root on the boundary of two intervals is found in both
+        if self.count > 0 && self.values[self.count - 1] == root {
+            return;
+        }
+        self.values[self.count] = root;
+        self.count += 1;
+    }
+}
+
+/// Value of the polynomial with `coefficients`, highest degree first.
+fn evaluate(coefficients: &[Float], x: Float) -> Float {
+    coefficients
+        .iter()
+        .fold(0.0, |value, coefficient| value * x + coefficient)
+}
+
+/// Real roots between `min` and `max` of the polynomial with `coefficients`, highest degree
+/// first. Up to quadratics the bounds may be infinite. Higher degrees split the range at the
+/// roots of the derivative, into pieces where the polynomial is monotonic, and search each piece
+/// for a sign change, so the bounds need to be finite.
+///
+/// Panics above `MAX_DEGREE`.
+pub fn roots_between(coefficients: &[Float], min: Float, max: Float) -> Roots {
+    let start = coefficients
+        .iter()
+        .position(|coefficient| *coefficient != 0.0)
+        .unwrap_or(coefficients.len());
+    let coefficients = &coefficients[start..];
+    assert!(
+        coefficients.len() <= MAX_DEGREE + 1,
+        "degree above {}",
+        MAX_DEGREE
+    );
+
+    let mut roots = Roots::default();
+    match *coefficients {
+        [] | [_] => {}
+        [a, b] => {
+            let root = -b / a;
+            if min <= root && root <= max {
+                roots.push(root);
+            }
+        }
+        [a, b, c] => {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return roots;
+            }
+            // Avoids subtracting nearly equal numbers, the second root comes from the product
+            // of the roots being c / a
+            let q = -0.5 * (b + discriminant.sqrt().copysign(b));
+            let (first, second) = (q / a, c / q);
+            for root in [first.min(second), first.max(second)] {
+                if min <= root && root <= max {
+                    roots.push(root);
+                }
+            }
+        }
+        _ => {
+            let degree = coefficients.len() - 1;
+            let mut derivative = [0.0; MAX_DEGREE];
+            for (index, coefficient) in coefficients[..degree].iter().enumerate() {
+                derivative[index] = coefficient * (degree - index) as Float;
+            }
+            let derivative = &derivative[..degree];
+            let extrema = roots_between(derivative, min, max);
+
+            let mut low = min;
+            for high in extrema.as_slice().iter().copied().chain([max]) {
+                if let Some(root) = bisect(coefficients, derivative, low, high) {
+                    roots.push(root);
+                }
+                low = high;
+            }
+        }
+    }
+    roots
+}
+
+/// Root of a polynomial that is monotonic between `low` and `high`, if it changes sign there.
+/// Newton steps speed up the bisection while they stay inside the bracket.
+fn bisect(
+    coefficients: &[Float],
+    derivative: &[Float],
+    mut low: Float,
+    mut high: Float,
+) -> Option<Float> {
+    let low_value = evaluate(coefficients, low);
+    let high_value = evaluate(coefficients, high);
+    if low_value == 0.0 {
+        return Some(low);
+    }
+    if high_value == 0.0 {
+        return Some(high);
+    }
+    if low_value.signum() == high_value.signum() {
+        return None;
+    }
+
+    let mut x = 0.5 * (low + high);
+    for _ in 0..100 {
+        let value = evaluate(coefficients, x);
+        if value == 0.0 {
+            break;
+        }
+        match value.signum() == low_value.signum() {
+            true => low = x,
+            false => high = x,
+        }
+        if high - low <= Float::EPSILON * x.abs().max(1.0) {
+            break;
+        }
+        let newton = x - value / evaluate(derivative, x);
+        if (newton - x).abs() <= Float::EPSILON * x.abs().max(1.0) {
+            x = newton;
+            break;
+        }
+        x = match low < newton && newton < high {
+            true => newton,
+            false => 0.5 * (low + high),
+        };
+    }
+    Some(x)
+}
@@ -1,19 +1,61 @@
 extern crate rand;
 
-use rand::Rng;
+use std::cell::RefCell;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_os_rng());
+}
+
+/// Reseeds the random number generator of the current thread.
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Seed for one sample of one pixel, so every sample draws the same random numbers no matter which
+/// thread renders it or whether the render was resumed from a checkpoint.
+pub fn sample_seed(seed: u64, pixel: u64, sample: u64) -> u64 {
+    // SplitMix64 finalizer over the combined inputs
+    let mut z = seed
+        ^ pixel.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ sample.wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Where the random number generator of a thread is in its sequence, to come back to it later.
+#[derive(Debug, Clone)]
+pub struct RngState(SmallRng);
+
+/// State of the random number generator of the current thread, see `restore_rng`.
+pub fn save_rng() -> RngState {
+    RNG.with(|rng| RngState(rng.borrow().clone()))
+}
+
+/// Continues the sequence of the random number generator of the current thread from `state`.
+pub fn restore_rng(state: RngState) {
+    RNG.with(|rng| *rng.borrow_mut() = state.0);
+}
+
+pub fn random_seed() -> u64 {
+    rand::random()
+}
 
 pub fn random_double() -> f64 {
-    rand::thread_rng().gen_range(0.0..1.0)
+    RNG.with(|rng| rng.borrow_mut().random_range(0.0..1.0))
 }
 
 pub fn random_float() -> f32 {
-    rand::thread_rng().gen_range(0.0f32..1.0f32)
+    RNG.with(|rng| rng.borrow_mut().random_range(0.0f32..1.0f32))
 }
 
 pub fn random_double_in_range(min: f64, max: f64) -> f64 {
-    rand::thread_rng().gen_range(min..max)
+    RNG.with(|rng| rng.borrow_mut().random_range(min..max))
 }
 
 pub fn random_float_in_range(min: f32, max: f32) -> f32 {
-    rand::thread_rng().gen_range(min..max)
+    RNG.with(|rng| rng.borrow_mut().random_range(min..max))
 }
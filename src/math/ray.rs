@@ -1,4 +1,4 @@
-use crate::math::{Point3, Vec3};
+use crate::math::{Float, Point3, Vec3};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
@@ -14,7 +14,7 @@ impl Ray {
     #Arguments
     * `distance` - 't' in the book. Distance in the ray where it is sampled.
     */
-    pub fn at(&self, distance: f64) -> Point3 {
+    pub fn at(&self, distance: Float) -> Point3 {
         self.origin + distance * self.direction
     }
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::math::{random_double, random_double_in_range, random_float, random_float_in_range};
+
+/// Floating point type the renderer computes in. `f64` unless built with the `f32` feature, which
+/// halves the size of every value, but loses detail in scenes that are very large or very small
+/// compared to their closest features.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+/// Floating point type the vector types are generic over, implemented for `f32` and `f64`.
+pub trait Scalar:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const NEG_ONE: Self;
+    const PI: Self;
+    const INFINITY: Self;
+    const NEG_INFINITY: Self;
+
+    /// Nearest value to `value`.
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    /// Random number in [0, 1).
+    fn random() -> Self;
+    /// Random number in [min, max).
+    fn random_in_range(min: Self, max: Self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($type:ident, $random:ident, $random_in_range:ident) => {
+        impl Scalar for $type {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const NEG_ONE: Self = -1.0;
+            const PI: Self = std::$type::consts::PI;
+            const INFINITY: Self = $type::INFINITY;
+            const NEG_INFINITY: Self = $type::NEG_INFINITY;
+
+            fn from_f64(value: f64) -> Self {
+                value as $type
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn sqrt(self) -> Self {
+                $type::sqrt(self)
+            }
+            fn abs(self) -> Self {
+                $type::abs(self)
+            }
+            fn sin(self) -> Self {
+                $type::sin(self)
+            }
+            fn cos(self) -> Self {
+                $type::cos(self)
+            }
+            fn min(self, other: Self) -> Self {
+                $type::min(self, other)
+            }
+            fn max(self, other: Self) -> Self {
+                $type::max(self, other)
+            }
+            fn random() -> Self {
+                $random()
+            }
+            fn random_in_range(min: Self, max: Self) -> Self {
+                $random_in_range(min, max)
+            }
+        }
+    };
+}
+
+impl_scalar!(f32, random_float, random_float_in_range);
+impl_scalar!(f64, random_double, random_double_in_range);
+
+/// Random number in [0, 1) at the precision of the renderer.
+pub fn random_scalar() -> Float {
+    Float::random()
+}
+
+/// Random number in [min, max) at the precision of the renderer.
+pub fn random_scalar_in_range(min: Float, max: Float) -> Float {
+    Float::random_in_range(min, max)
+}
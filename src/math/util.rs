@@ -1,4 +1,6 @@
-pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
+use crate::math::Float;
+
+pub fn clamp(x: Float, min: Float, max: Float) -> Float {
     if x < min {
         min
     } else if x > max {
@@ -6,4 +8,4 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     } else {
         x
     }
-}
\ No newline at end of file
+}
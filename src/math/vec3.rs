@@ -2,105 +2,124 @@ use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
 };
 
-use crate::math::random_double_in_range;
-use crate::random_double;
+use crate::math::{random_scalar, random_scalar_in_range, Float, Scalar, PI};
 
-// TODO: add generics
-#[derive(Debug, Clone, Copy, Default)]
-pub struct Vec3 {
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+/// Direction or offset in space. Positions are `Point3`, surface normals `Normal3` and colors
+/// `Color`, which only have the operations that make sense for them. Generic over the scalar, which
+/// is the `Float` of the renderer unless given.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vec3<T = Float> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
-impl Vec3 {
-    pub const ZERO: Vec3 = Vec3 {
-        x: 0.0,
-        y: 0.0,
-        z: 0.0,
-    };
-    pub const ONE: Vec3 = Vec3 {
-        x: 1.0,
-        y: 1.0,
-        z: 1.0,
+impl<T: Scalar> Vec3<T> {
+    pub const ZERO: Self = Vec3 {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ZERO,
     };
-    pub const FORWARD: Vec3 = Vec3 {
-        x: 0.0,
-        y: 0.0,
-        z: 1.0,
+    pub const ONE: Self = Vec3 {
+        x: T::ONE,
+        y: T::ONE,
+        z: T::ONE,
     };
-    pub const BACK: Vec3 = Vec3 {
-        x: 0.0,
-        y: 0.0,
-        z: -1.0,
+    pub const FORWARD: Self = Vec3 {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::ONE,
     };
-    pub const UP: Vec3 = Vec3 {
-        x: 0.0,
-        y: 1.0,
-        z: 0.0,
+    pub const BACK: Self = Vec3 {
+        x: T::ZERO,
+        y: T::ZERO,
+        z: T::NEG_ONE,
     };
-    pub const DOWN: Vec3 = Vec3 {
-        x: 0.0,
-        y: -1.0,
-        z: 0.0,
+    pub const UP: Self = Vec3 {
+        x: T::ZERO,
+        y: T::ONE,
+        z: T::ZERO,
     };
-    pub const LEFT: Vec3 = Vec3 {
-        x: -1.0,
-        y: 0.0,
-        z: 0.0,
+    pub const DOWN: Self = Vec3 {
+        x: T::ZERO,
+        y: T::NEG_ONE,
+        z: T::ZERO,
     };
-    pub const RIGHT: Vec3 = Vec3 {
-        x: 1.0,
-        y: 0.0,
-        z: 0.0,
+    pub const LEFT: Self = Vec3 {
+        x: T::NEG_ONE,
+        y: T::ZERO,
+        z: T::ZERO,
     };
-    pub const POSITIVE_INFINITY: Vec3 = Vec3 {
-        x: f64::INFINITY,
-        y: f64::INFINITY,
-        z: f64::INFINITY,
-    };
-    pub const NEGATIVE_INFINITY: Vec3 = Vec3 {
-        x: f64::NEG_INFINITY,
-        y: f64::NEG_INFINITY,
-        z: f64::NEG_INFINITY,
+    pub const RIGHT: Self = Vec3 {
+        x: T::ONE,
+        y: T::ZERO,
+        z: T::ZERO,
     };
 
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: T, y: T, z: T) -> Self {
         Self { x, y, z }
     }
-    pub fn new_away_from_camera(x: f64, y: f64, z: f64) -> Self {
+    pub fn new_away_from_camera(x: T, y: T, z: T) -> Self {
         Self { x: -x, y, z }
     }
-    pub fn from_float(val: f64) -> Self {
+    pub fn from_float(val: T) -> Self {
         Vec3::new(val, val, val)
     }
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> T {
         self.squared_length().sqrt()
     }
-    pub fn squared_length(&self) -> f64 {
+    pub fn squared_length(&self) -> T {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
     pub fn unit_vector(&self) -> Self {
-        self / self.length()
+        *self / self.length()
     }
-    pub fn dot_product(a: &Vec3, b: &Vec3) -> f64 {
+    pub fn dot_product(a: &Vec3<T>, b: &Vec3<T>) -> T {
         a.x * b.x + a.y * b.y + a.z * b.z
     }
-    pub fn cross_product(a: &Vec3, b: &Vec3) -> Self {
+    pub fn cross_product(a: &Vec3<T>, b: &Vec3<T>) -> Self {
         Self {
             x: a.y * b.z - a.z * b.y,
             y: a.z * b.x - a.x * b.z,
             z: a.x * b.y - a.y * b.x,
         }
     }
+    /// The vector in another precision.
+    pub fn cast<U: Scalar>(&self) -> Vec3<U> {
+        Vec3::new(
+            U::from_f64(self.x.to_f64()),
+            U::from_f64(self.y.to_f64()),
+            U::from_f64(self.z.to_f64()),
+        )
+    }
+    pub fn reflect(vector: Vec3<T>, normal: Vec3<T>) -> Self {
+        vector - normal * (T::from_f64(2.0) * Vec3::dot_product(&vector, &normal))
+    }
+    pub fn refract(uv: Vec3<T>, normal: Vec3<T>, etai_over_etat: T) -> Self {
+        let cos_theta = Vec3::dot_product(&-uv, &normal).min(T::ONE);
+        let ray_out_perpendicular = (uv + normal * cos_theta) * etai_over_etat;
+        let parallel_length = (T::ONE - ray_out_perpendicular.squared_length())
+            .abs()
+            .sqrt();
+        let ray_out_parallel = normal * -parallel_length;
+        ray_out_perpendicular + ray_out_parallel
+    }
+    pub fn near_zero(&self) -> bool {
+        let s = T::from_f64(1e-8);
+        (self.x.abs() < s) && (self.y.abs() < s) && (self.z.abs() < s)
+    }
+}
+
+/// Random vectors, drawn at the precision of the renderer.
+impl Vec3 {
     pub fn random() -> Self {
-        Vec3::new(random_double(), random_double(), random_double())
+        Vec3::new(random_scalar(), random_scalar(), random_scalar())
     }
-    pub fn random_in_range(min: f64, max: f64) -> Self {
+    pub fn random_in_range(min: Float, max: Float) -> Self {
         Vec3::new_away_from_camera(
-            random_double_in_range(min, max),
-            random_double_in_range(min, max),
-            random_double_in_range(min, max),
+            random_scalar_in_range(min, max),
+            random_scalar_in_range(min, max),
+            random_scalar_in_range(min, max),
         )
     }
     pub fn random_point_in_unit_sphere() -> Self {
@@ -123,24 +142,23 @@ impl Vec3 {
             -in_unit_sphere
         }
     }
-    pub fn reflect(vector: Vec3, normal: Vec3) -> Self {
-        vector - 2.0 * Vec3::dot_product(&vector, &normal) * normal
-    }
-    pub fn refract(uv: Vec3, normal: Vec3, etai_over_etat: f64) -> Self {
-        let cos_theta = Vec3::dot_product(&-uv, &normal).min(1.0_f64);
-        let ray_out_perpendicular = etai_over_etat * (uv + cos_theta * normal);
-        let ray_out_parallel = (1.0 - ray_out_perpendicular.squared_length()).abs().sqrt() * normal;
-        ray_out_perpendicular + ray_out_parallel
-    }
-    pub fn near_zero(&self) -> bool {
-        const S: f64 = 1e-8;
-        (self.x.abs() < S) && (self.y.abs() < S) && (self.z.abs() < S)
+    /// Direction in the hemisphere around +z, distributed proportionally to the cosine of its
+    /// angle to +z. The density is `z / PI`.
+    pub fn random_cosine_direction() -> Self {
+        let r1 = random_scalar();
+        let r2 = random_scalar();
+        let phi = 2.0 * PI * r1;
+        Vec3::new(
+            phi.cos() * r2.sqrt(),
+            phi.sin() * r2.sqrt(),
+            (1.0 - r2).sqrt(),
+        )
     }
 }
 
-impl Add for Vec3 {
-    type Output = Vec3;
-    fn add(self, rhs: Vec3) -> Self {
+impl<T: Scalar> Add for Vec3<T> {
+    type Output = Vec3<T>;
+    fn add(self, rhs: Vec3<T>) -> Self {
         Self {
             x: self.x + rhs.x,
             y: self.y + rhs.y,
@@ -149,8 +167,8 @@ impl Add for Vec3 {
     }
 }
 
-impl Sub for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Sub for Vec3<T> {
+    type Output = Vec3<T>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         Self {
@@ -161,22 +179,10 @@ impl Sub for Vec3 {
     }
 }
 
-impl Mul for Vec3 {
-    type Output = Vec3;
-
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x * rhs.x,
-            y: self.y * rhs.y,
-            z: self.z * rhs.z,
-        }
-    }
-}
-
-impl Mul<f64> for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Mul<T> for Vec3<T> {
+    type Output = Vec3<T>;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: T) -> Self::Output {
         Self {
             x: self.x * rhs,
             y: self.y * rhs,
@@ -185,46 +191,19 @@ impl Mul<f64> for Vec3 {
     }
 }
 
-impl Mul<Vec3> for f64 {
+/// Only at the precision of the renderer, so the type of the scalar in `2.0 * vector` is inferred.
+impl Mul<Vec3> for Float {
     type Output = Vec3;
 
     fn mul(self, rhs: Vec3) -> Self::Output {
-        Vec3 {
-            x: self * rhs.x,
-            y: self * rhs.y,
-            z: self * rhs.z,
-        }
+        rhs * self
     }
 }
 
-impl Div for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Div<T> for Vec3<T> {
+    type Output = Vec3<T>;
 
-    fn div(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x / rhs.x,
-            y: self.y / rhs.y,
-            z: self.z / rhs.z,
-        }
-    }
-}
-
-impl Div for &Vec3 {
-    type Output = Vec3;
-
-    fn div(self, rhs: Self) -> Self::Output {
-        Vec3 {
-            x: self.x / rhs.x,
-            y: self.y / rhs.y,
-            z: self.z / rhs.z,
-        }
-    }
-}
-
-impl Div<f64> for Vec3 {
-    type Output = Vec3;
-
-    fn div(self, rhs: f64) -> Self::Output {
+    fn div(self, rhs: T) -> Self::Output {
         Self {
             x: self.x / rhs,
             y: self.y / rhs,
@@ -233,19 +212,15 @@ impl Div<f64> for Vec3 {
     }
 }
 
-impl Div<f64> for &Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Div<T> for &Vec3<T> {
+    type Output = Vec3<T>;
 
-    fn div(self, rhs: f64) -> Self::Output {
-        Vec3 {
-            x: self.x / rhs,
-            y: self.y / rhs,
-            z: self.z / rhs,
-        }
+    fn div(self, rhs: T) -> Self::Output {
+        *self / rhs
     }
 }
 
-impl AddAssign for Vec3 {
+impl<T: Scalar> AddAssign for Vec3<T> {
     fn add_assign(&mut self, rhs: Self) {
         self.x += rhs.x;
         self.y += rhs.y;
@@ -253,32 +228,32 @@ impl AddAssign for Vec3 {
     }
 }
 
-impl SubAssign for Vec3 {
-    fn sub_assign(&mut self, rhs: Self) {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-        self.z -= rhs.z;
+impl<T: Scalar> MulAssign<T> for Vec3<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
     }
 }
 
-impl MulAssign for Vec3 {
-    fn mul_assign(&mut self, rhs: Self) {
-        self.x *= rhs.x;
-        self.y *= rhs.y;
-        self.z *= rhs.z;
+impl<T: Scalar> DivAssign<T> for Vec3<T> {
+    fn div_assign(&mut self, rhs: T) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
     }
 }
 
-impl DivAssign for Vec3 {
-    fn div_assign(&mut self, rhs: Self) {
-        self.x /= rhs.x;
-        self.y /= rhs.y;
-        self.z /= rhs.z;
+impl<T: Scalar> SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
     }
 }
 
-impl Index<usize> for Vec3 {
-    type Output = f64;
+impl<T> Index<usize> for Vec3<T> {
+    type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
         if index == 0 {
@@ -291,7 +266,7 @@ impl Index<usize> for Vec3 {
     }
 }
 
-impl IndexMut<usize> for Vec3 {
+impl<T> IndexMut<usize> for Vec3<T> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         if index == 0 {
             &mut self.x
@@ -303,8 +278,8 @@ impl IndexMut<usize> for Vec3 {
     }
 }
 
-impl Neg for Vec3 {
-    type Output = Vec3;
+impl<T: Scalar> Neg for Vec3<T> {
+    type Output = Vec3<T>;
 
     fn neg(self) -> Self::Output {
         Self {
@@ -314,6 +289,3 @@ impl Neg for Vec3 {
         }
     }
 }
-
-pub type Color = Vec3;
-pub type Point3 = Vec3;
@@ -1 +0,0 @@
-pub struct Vec4{}
\ No newline at end of file
@@ -0,0 +1,138 @@
+use std::mem;
+
+use crate::math::{Aabb, Float, Point3, Ray, Vec3};
+
+/// Number of boxes or rays tested at once, as many `Float`s as fit in a 256-bit AVX2 register: 4
+/// in f64 builds and 8 with the `f32` feature. The wide tests are plain loops over arrays of this
+/// length, which the compiler turns into SIMD instructions for the target CPU.
+pub const LANES: usize = 32 / mem::size_of::<Float>();
+
+/// Unsigned integer with as many bits as a `Float`.
+#[cfg(not(feature = "f32"))]
+type Bits = u64;
+#[cfg(feature = "f32")]
+type Bits = u32;
+
+/// `negative` if the sign bit of `value` is set, `positive` otherwise. Picks with bit masks instead
+/// of a comparison, which the compiler turns into SIMD blends of the lanes instead of branches.
+fn select_by_sign(value: Float, negative: Float, positive: Float) -> Float {
+    let mask = Bits::wrapping_sub(0, value.to_bits() >> (Bits::BITS - 1));
+    Float::from_bits((negative.to_bits() & mask) | (positive.to_bits() & !mask))
+}
+
+/// `LANES` boxes stored axis by axis, so that a ray is tested against all of them at once.
+#[derive(Debug, Clone, Copy)]
+pub struct WideAabb {
+    min: [[Float; LANES]; 3],
+    max: [[Float; LANES]; 3],
+}
+
+impl WideAabb {
+    /// Every lane is `Aabb::EMPTY`, which no ray hits.
+    pub const EMPTY: WideAabb = WideAabb {
+        min: [[Float::INFINITY; LANES]; 3],
+        max: [[Float::NEG_INFINITY; LANES]; 3],
+    };
+
+    pub fn set(&mut self, lane: usize, bounds: &Aabb) {
+        for axis in 0..3 {
+            self.min[axis][lane] = bounds.min[axis];
+            self.max[axis][lane] = bounds.max[axis];
+        }
+    }
+
+    pub fn get(&self, lane: usize) -> Aabb {
+        Aabb {
+            min: Point3::new(self.min[0][lane], self.min[1][lane], self.min[2][lane]),
+            max: Point3::new(self.max[0][lane], self.max[1][lane], self.max[2][lane]),
+        }
+    }
+
+    /// Where `ray` enters each of the boxes, `Float::INFINITY` for the boxes it doesn't reach
+    /// between `t_min` and `t_max`. The same slab test as `Aabb::hit`, with the same result for
+    /// every lane.
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        inverse_direction: Vec3,
+        t_min: Float,
+        t_max: Float,
+    ) -> [Float; LANES] {
+        let mut entry = [t_min; LANES];
+        let mut exit = [t_max; LANES];
+        for axis in 0..3 {
+            let origin = ray.origin[axis];
+            let inverse = inverse_direction[axis];
+            let (near, far) = match inverse < 0.0 {
+                true => (&self.max[axis], &self.min[axis]),
+                false => (&self.min[axis], &self.max[axis]),
+            };
+            for lane in 0..LANES {
+                // NaN from a ray in the plane of a slab leaves the interval unchanged
+                entry[lane] = ((near[lane] - origin) * inverse).max(entry[lane]);
+                exit[lane] = ((far[lane] - origin) * inverse).min(exit[lane]);
+            }
+        }
+        let mut hits = [Float::INFINITY; LANES];
+        for lane in 0..LANES {
+            if entry[lane] <= exit[lane] {
+                hits[lane] = entry[lane];
+            }
+        }
+        hits
+    }
+}
+
+/// `LANES` rays traced together, stored axis by axis so that a box is tested against all of them
+/// at once. Worth it for rays that start close together and go the same way, like the samples of
+/// a pixel, which mostly visit the same nodes of a BVH.
+#[derive(Debug, Clone, Copy)]
+pub struct RayPacket {
+    pub rays: [Ray; LANES],
+    origin: [[Float; LANES]; 3],
+    inverse_direction: [[Float; LANES]; 3],
+}
+
+impl RayPacket {
+    pub fn new(rays: [Ray; LANES]) -> RayPacket {
+        let mut origin = [[0.0; LANES]; 3];
+        let mut inverse_direction = [[0.0; LANES]; 3];
+        for (lane, ray) in rays.iter().enumerate() {
+            for axis in 0..3 {
+                origin[axis][lane] = ray.origin[axis];
+                inverse_direction[axis][lane] = 1.0 / ray.direction[axis];
+            }
+        }
+        RayPacket {
+            rays,
+            origin,
+            inverse_direction,
+        }
+    }
+
+    /// Where each ray enters `bounds`, `Float::INFINITY` for the rays that don't reach it between
+    /// `t_min` and their own `t_max`. The same slab test as `Aabb::interval`.
+    pub fn hit(&self, bounds: &Aabb, t_min: Float, t_max: &[Float; LANES]) -> [Float; LANES] {
+        let mut entry = [t_min; LANES];
+        let mut exit = *t_max;
+        for axis in 0..3 {
+            let (min, max) = (bounds.min[axis], bounds.max[axis]);
+            let origin = &self.origin[axis];
+            let inverse_direction = &self.inverse_direction[axis];
+            for lane in 0..LANES {
+                let inverse = inverse_direction[lane];
+                let t0 = (min - origin[lane]) * inverse;
+                let t1 = (max - origin[lane]) * inverse;
+                entry[lane] = select_by_sign(inverse, t1, t0).max(entry[lane]);
+                exit[lane] = select_by_sign(inverse, t0, t1).min(exit[lane]);
+            }
+        }
+        let mut hits = [Float::INFINITY; LANES];
+        for lane in 0..LANES {
+            if entry[lane] <= exit[lane] {
+                hits[lane] = entry[lane];
+            }
+        }
+        hits
+    }
+}
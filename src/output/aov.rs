@@ -0,0 +1,176 @@
+use std::ops::Index;
+
+use exr::prelude::*;
+
+use crate::math::{Color, Float};
+use crate::rendering::{AovPixel, Film};
+
+/// Arbitrary output variables, written as extra layers of the EXR output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Aov {
+    /// Albedo of the first surface, background color for escaped rays
+    Albedo,
+    /// World space shading normal of the first surface
+    Normal,
+    /// Distance from the camera to the first surface
+    Depth,
+    /// World space position of the first surface
+    Position,
+    /// 1-based index of the first object in the scene
+    ObjectId,
+    MaterialId,
+    DiffuseDirect,
+    DiffuseIndirect,
+    SpecularDirect,
+    SpecularIndirect,
+    /// Light sources and sky seen directly by the camera
+    Emission,
+    SampleCount,
+    /// Variance of the pixel estimate
+    Variance,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 13] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::Position,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::DiffuseDirect,
+        Aov::DiffuseIndirect,
+        Aov::SpecularDirect,
+        Aov::SpecularIndirect,
+        Aov::Emission,
+        Aov::SampleCount,
+        Aov::Variance,
+    ];
+
+    /// Name of the EXR layer, channels are called "<layer>.<channel>".
+    pub fn layer_name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::DiffuseDirect => "diffuse_direct",
+            Aov::DiffuseIndirect => "diffuse_indirect",
+            Aov::SpecularDirect => "specular_direct",
+            Aov::SpecularIndirect => "specular_indirect",
+            Aov::Emission => "emission",
+            Aov::SampleCount => "sample_count",
+            Aov::Variance => "variance",
+        }
+    }
+
+    /// Whether the film has to accumulate AOV buffers to produce this output.
+    pub fn needs_aov_buffers(&self) -> bool {
+        *self != Aov::SampleCount
+    }
+
+    /// EXR channels holding this AOV for the whole film. Returns `None` if the film has not
+    /// accumulated the data it needs.
+    pub fn channels(&self, film: &Film) -> Option<Vec<AnyChannel<FlatSamples>>> {
+        let layer = self.layer_name();
+        if *self == Aov::SampleCount {
+            let counts = film.pixels().iter().map(|pixel| pixel.samples).collect();
+            return Some(vec![channel(
+                format!("{}.count", layer),
+                FlatSamples::U32(counts),
+            )]);
+        }
+
+        let aovs = film.aovs()?;
+        let channels = match self {
+            Aov::Albedo => color_channels(layer, film, |pixel, weight| pixel.albedo / weight),
+            Aov::Normal => vector_channels(layer, aovs, AovPixel::normal),
+            Aov::Position => vector_channels(layer, aovs, AovPixel::position),
+            Aov::Depth => vec![channel(
+                format!("{}.Z", layer),
+                FlatSamples::F32(aovs.iter().map(|aov| aov.depth() as f32).collect()),
+            )],
+            Aov::ObjectId => vec![channel(
+                format!("{}.id", layer),
+                FlatSamples::U32(aovs.iter().map(|aov| aov.object_id).collect()),
+            )],
+            Aov::MaterialId => vec![channel(
+                format!("{}.id", layer),
+                FlatSamples::U32(aovs.iter().map(|aov| aov.material_id).collect()),
+            )],
+            Aov::DiffuseDirect => {
+                color_channels(layer, film, |pixel, weight| pixel.diffuse_direct / weight)
+            }
+            Aov::DiffuseIndirect => {
+                color_channels(layer, film, |pixel, weight| pixel.diffuse_indirect / weight)
+            }
+            Aov::SpecularDirect => {
+                color_channels(layer, film, |pixel, weight| pixel.specular_direct / weight)
+            }
+            Aov::SpecularIndirect => color_channels(layer, film, |pixel, weight| {
+                pixel.specular_indirect / weight
+            }),
+            Aov::Emission => color_channels(layer, film, |pixel, weight| pixel.emission / weight),
+            Aov::Variance => {
+                let variances: Vec<Color> = (0..film.height)
+                    .flat_map(|y| (0..film.width).map(move |x| (x, y)))
+                    .map(|(x, y)| film.variance(x, y))
+                    .collect();
+                split_channels(layer, ["R", "G", "B"], &variances)
+            }
+            Aov::SampleCount => unreachable!(),
+        };
+        Some(channels)
+    }
+}
+
+/// Channels of a color summed over all samples, divided by the pixel weight.
+fn color_channels(
+    layer: &str,
+    film: &Film,
+    average: impl Fn(&AovPixel, Float) -> Color,
+) -> Vec<AnyChannel<FlatSamples>> {
+    let aovs = film.aovs().unwrap_or_default();
+    let colors: Vec<Color> = film
+        .pixels()
+        .iter()
+        .zip(aovs)
+        .map(|(pixel, aov)| match pixel.weight > 0.0 {
+            true => average(aov, pixel.weight),
+            false => Color::ZERO,
+        })
+        .collect();
+    split_channels(layer, ["R", "G", "B"], &colors)
+}
+
+fn vector_channels<T: Index<usize, Output = Float>>(
+    layer: &str,
+    aovs: &[AovPixel],
+    average: impl Fn(&AovPixel) -> T,
+) -> Vec<AnyChannel<FlatSamples>> {
+    let vectors: Vec<T> = aovs.iter().map(average).collect();
+    split_channels(layer, ["X", "Y", "Z"], &vectors)
+}
+
+fn split_channels<T: Index<usize, Output = Float>>(
+    layer: &str,
+    names: [&str; 3],
+    values: &[T],
+) -> Vec<AnyChannel<FlatSamples>> {
+    (0..3)
+        .map(|axis| {
+            let samples = values.iter().map(|value| value[axis] as f32).collect();
+            channel(
+                format!("{}.{}", layer, names[axis]),
+                FlatSamples::F32(samples),
+            )
+        })
+        .collect()
+}
+
+fn channel(name: String, samples: FlatSamples) -> AnyChannel<FlatSamples> {
+    AnyChannel::new(name.as_str(), samples)
+}
@@ -0,0 +1,29 @@
+//! Helpers shared by the readers of the image, checkpoint and density grid files.
+
+use std::io::{self, Read};
+
+/// Largest image a file is read for, 16384x16384 pixels.
+const MAX_PIXELS: usize = 1 << 28;
+
+/// Number of pixels of a `width` x `height` image from a file header. Checked before allocating
+/// anything, so a corrupt header gives an error instead of aborting.
+pub(crate) fn pixel_count(width: usize, height: usize) -> io::Result<usize> {
+    width
+        .checked_mul(height)
+        .filter(|&count| count > 0 && count <= MAX_PIXELS)
+        .ok_or_else(|| invalid_data("unsupported image size"))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(reader)?))
+}
@@ -0,0 +1,264 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::math::{Color, Float, Scalar, Vec3};
+use crate::output::binary::{invalid_data, pixel_count, read_bytes, read_u64};
+use crate::rendering::{AovPixel, Film, FilmPixel};
+
+const MAGIC: &[u8; 8] = b"RTCKPT03";
+
+/// Bytes stored for each pixel of the film: the sum, the weight and the sample count.
+const PIXEL_BYTES: u64 = 4 * 8 + 4;
+
+/// Everything needed to continue an interrupted render.
+pub struct Checkpoint {
+    /// Name of the scene that was rendered
+    pub scene: String,
+    pub seed: u64,
+    pub max_depth: i32,
+    pub spectral: bool,
+    pub film: Film,
+}
+
+impl Checkpoint {
+    /// Fails unless the checkpoint can be continued by a render of `scene` at `width` x `height`,
+    /// in the same spectral mode, which would otherwise mix samples of different images.
+    pub fn check_resumable(
+        &self,
+        scene: &str,
+        width: usize,
+        height: usize,
+        spectral: bool,
+    ) -> Result<()> {
+        if self.film.width() != width || self.film.height() != height {
+            return Err(Error::InvalidSettings(format!(
+                "the checkpoint is {}x{}, but a {}x{} image was requested",
+                self.film.width(),
+                self.film.height(),
+                width,
+                height
+            )));
+        }
+        if self.scene != scene {
+            return Err(Error::InvalidSettings(format!(
+                "the checkpoint is of the scene {}, but {} was requested",
+                self.scene, scene
+            )));
+        }
+        if self.spectral != spectral {
+            return Err(Error::InvalidSettings(format!(
+                "the checkpoint was rendered {}, resume it {} --spectral",
+                match self.spectral {
+                    true => "spectrally",
+                    false => "in RGB",
+                },
+                match self.spectral {
+                    true => "with",
+                    false => "without",
+                }
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Writes the accumulated film to `path`. The data goes to a temporary file first and is renamed
+/// over the previous checkpoint, so a crash while writing never leaves a truncated checkpoint behind.
+pub fn write_checkpoint(
+    path: &Path,
+    film: &Film,
+    scene: &str,
+    seed: u64,
+    max_depth: i32,
+    spectral: bool,
+) -> Result<()> {
+    write_checkpoint_file(path, film, scene, seed, max_depth, spectral).map_err(|source| {
+        Error::WriteCheckpoint {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+pub fn read_checkpoint(path: &Path) -> Result<Checkpoint> {
+    read_checkpoint_file(path).map_err(|source| Error::ReadCheckpoint {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write_checkpoint_file(
+    path: &Path,
+    film: &Film,
+    scene: &str,
+    seed: u64,
+    max_depth: i32,
+    spectral: bool,
+) -> io::Result<()> {
+    // Appended rather than replacing the extension, which may already be .tmp
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+    {
+        let mut writer = BufWriter::new(File::create(&temporary_path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(film.width() as u64).to_le_bytes())?;
+        writer.write_all(&(film.height() as u64).to_le_bytes())?;
+        writer.write_all(&(scene.len() as u64).to_le_bytes())?;
+        writer.write_all(scene.as_bytes())?;
+        writer.write_all(&seed.to_le_bytes())?;
+        writer.write_all(&max_depth.to_le_bytes())?;
+        writer.write_all(&[spectral as u8])?;
+        writer.write_all(&[film.aovs().is_some() as u8])?;
+        for pixel in film.pixels() {
+            write_color(&mut writer, pixel.sum)?;
+            write_f64(&mut writer, pixel.weight)?;
+            writer.write_all(&pixel.samples.to_le_bytes())?;
+        }
+        for aov in film.aovs().unwrap_or_default() {
+            write_color(&mut writer, aov.albedo)?;
+            write_vector(&mut writer, aov.normal)?;
+            write_vector(&mut writer, aov.position)?;
+            write_f64(&mut writer, aov.depth)?;
+            writer.write_all(&aov.hits.to_le_bytes())?;
+            writer.write_all(&aov.object_id.to_le_bytes())?;
+            writer.write_all(&aov.material_id.to_le_bytes())?;
+            write_color(&mut writer, aov.emission)?;
+            write_color(&mut writer, aov.diffuse_direct)?;
+            write_color(&mut writer, aov.diffuse_indirect)?;
+            write_color(&mut writer, aov.specular_direct)?;
+            write_color(&mut writer, aov.specular_indirect)?;
+            write_color(&mut writer, aov.sum_squares)?;
+        }
+        writer.into_inner()?.sync_all()?;
+    }
+    fs::rename(&temporary_path, path)
+}
+
+fn read_checkpoint_file(path: &Path) -> io::Result<Checkpoint> {
+    let file = File::open(path)?;
+    let file_length = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a render checkpoint"));
+    }
+
+    let width = read_u64(&mut reader)? as usize;
+    let height = read_u64(&mut reader)? as usize;
+    let scene = read_string(&mut reader)?;
+    let seed = read_u64(&mut reader)?;
+    let max_depth = i32::from_le_bytes(read_bytes(&mut reader)?);
+    let [spectral] = read_bytes(&mut reader)?;
+    let [has_aovs] = read_bytes(&mut reader)?;
+
+    // Checked before allocating the film, a corrupt size would otherwise abort
+    let pixel_count = pixel_count(width, height)?;
+    if pixel_count as u64 * PIXEL_BYTES > file_length {
+        return Err(invalid_data("truncated checkpoint"));
+    }
+
+    let mut pixels = Vec::with_capacity(pixel_count);
+    for _ in 0..pixel_count {
+        let sum = read_color(&mut reader)?;
+        let weight = read_f64(&mut reader)?;
+        let samples = u32::from_le_bytes(read_bytes(&mut reader)?);
+        pixels.push(FilmPixel {
+            sum,
+            weight,
+            samples,
+        });
+    }
+
+    let aovs = match has_aovs {
+        0 => None,
+        _ => Some(
+            (0..pixel_count)
+                .map(|_| read_aov_pixel(&mut reader))
+                .collect::<io::Result<Vec<_>>>()?,
+        ),
+    };
+
+    let film = Film::from_pixels(width, height, pixels, aovs)
+        .ok_or_else(|| invalid_data("checkpoint size mismatch"))?;
+    Ok(Checkpoint {
+        scene,
+        seed,
+        max_depth,
+        spectral: spectral != 0,
+        film,
+    })
+}
+
+/// Scene names are short, a longer one means the file is corrupt.
+const MAX_STRING_LENGTH: u64 = 256;
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let length = read_u64(reader)?;
+    if length > MAX_STRING_LENGTH {
+        return Err(invalid_data("checkpoint string too long"));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid_data("checkpoint string is not UTF-8"))
+}
+
+/// Values are stored as `f64` whatever the precision of the renderer, so checkpoints can be
+/// resumed by either build.
+fn read_f64(reader: &mut impl Read) -> io::Result<Float> {
+    Ok(Float::from_f64(f64::from_le_bytes(read_bytes(reader)?)))
+}
+
+fn write_f64(writer: &mut impl Write, value: Float) -> io::Result<()> {
+    writer.write_all(&value.to_f64().to_le_bytes())
+}
+
+fn read_color(reader: &mut impl Read) -> io::Result<Color> {
+    Ok(Color::new(
+        read_f64(reader)?,
+        read_f64(reader)?,
+        read_f64(reader)?,
+    ))
+}
+
+fn write_color(writer: &mut impl Write, color: Color) -> io::Result<()> {
+    write_f64(writer, color.x)?;
+    write_f64(writer, color.y)?;
+    write_f64(writer, color.z)
+}
+
+fn read_vector(reader: &mut impl Read) -> io::Result<Vec3> {
+    Ok(Vec3::new(
+        read_f64(reader)?,
+        read_f64(reader)?,
+        read_f64(reader)?,
+    ))
+}
+
+fn write_vector(writer: &mut impl Write, vector: Vec3) -> io::Result<()> {
+    write_f64(writer, vector.x)?;
+    write_f64(writer, vector.y)?;
+    write_f64(writer, vector.z)
+}
+
+fn read_aov_pixel(reader: &mut impl Read) -> io::Result<AovPixel> {
+    Ok(AovPixel {
+        albedo: read_color(reader)?,
+        normal: read_vector(reader)?,
+        position: read_vector(reader)?,
+        depth: read_f64(reader)?,
+        hits: u32::from_le_bytes(read_bytes(reader)?),
+        object_id: u32::from_le_bytes(read_bytes(reader)?),
+        material_id: u32::from_le_bytes(read_bytes(reader)?),
+        emission: read_color(reader)?,
+        diffuse_direct: read_color(reader)?,
+        diffuse_indirect: read_color(reader)?,
+        specular_direct: read_color(reader)?,
+        specular_indirect: read_color(reader)?,
+        sum_squares: read_color(reader)?,
+    })
+}
@@ -0,0 +1,134 @@
+use crate::clamp;
+use crate::math::{Color, Float};
+
+/// Curve compressing scene-referred radiance into the displayable 0 to 1 range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ToneMapping {
+    /// Clip everything above 1
+    #[default]
+    None,
+    /// x / (1 + x) per channel
+    Reinhard,
+    /// Stephen Hill's fit of the ACES reference rendering and sRGB output transforms
+    Aces,
+    /// Troy Sobotka's AgX, polynomial approximation of the default contrast look
+    Agx,
+    /// John Hable's Uncharted 2 filmic curve
+    Hable,
+}
+
+/// Turns linear film radiance into sRGB encoded values for 8 and 16-bit outputs.
+/// EXR and Radiance HDR outputs skip this and store the film as it is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisplayTransform {
+    /// Exposure adjustment in stops, applied before tone mapping
+    pub exposure: Float,
+    pub tone_mapping: ToneMapping,
+}
+
+impl DisplayTransform {
+    /// sRGB encoded color, each channel between 0 and 1.
+    pub fn apply(&self, linear: Color) -> Color {
+        let exposed = linear * Float::powf(2.0, self.exposure);
+        let exposed = Color::new(exposed.x.max(0.0), exposed.y.max(0.0), exposed.z.max(0.0));
+        let mapped = match self.tone_mapping {
+            ToneMapping::None => exposed,
+            ToneMapping::Reinhard => map_channels(exposed, |x| x / (1.0 + x)),
+            ToneMapping::Aces => aces(exposed),
+            ToneMapping::Agx => agx(exposed),
+            ToneMapping::Hable => hable(exposed),
+        };
+        map_channels(mapped, |x| srgb_oetf(clamp(x, 0.0, 1.0)))
+    }
+}
+
+/// sRGB opto-electronic transfer function, linear light to encoded value.
+pub fn srgb_oetf(linear: Float) -> Float {
+    if linear <= 0.0031308 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `srgb_oetf`.
+pub fn srgb_eotf(encoded: Float) -> Float {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn map_channels(color: Color, function: impl Fn(Float) -> Float) -> Color {
+    Color::new(function(color.x), function(color.y), function(color.z))
+}
+
+fn aces(color: Color) -> Color {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[Float; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[Float; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+
+    let color = color.transform(&INPUT);
+    let color = map_channels(color, |v| {
+        let a = v * (v + 0.0245786) - 0.000090537;
+        let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+        a / b
+    });
+    color.transform(&OUTPUT)
+}
+
+fn agx(color: Color) -> Color {
+    const INSET: [[Float; 3]; 3] = [
+        [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+        [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+        [0.0423756549057051, 0.0784336, 0.879142973793104],
+    ];
+    const OUTSET: [[Float; 3]; 3] = [
+        [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+        [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+        [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+    ];
+    const MIN_EV: Float = -12.47393;
+    const MAX_EV: Float = 4.026069;
+
+    let color = color.transform(&INSET);
+    let color = map_channels(color, |x| {
+        // Log2 encoding between MIN_EV and MAX_EV, then the sigmoid approximation
+        let x = (clamp(x.max(1e-10).log2(), MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    });
+    // The look is authored for a 2.2 power display, undo it to get back to linear
+    map_channels(color.transform(&OUTSET), |x| x.max(0.0).powf(2.2))
+}
+
+fn hable(color: Color) -> Color {
+    const EXPOSURE_BIAS: Float = 2.0;
+    const WHITE_POINT: Float = 11.2;
+
+    fn curve(x: Float) -> Float {
+        const A: Float = 0.15; // Shoulder strength
+        const B: Float = 0.50; // Linear strength
+        const C: Float = 0.10; // Linear angle
+        const D: Float = 0.20; // Toe strength
+        const E: Float = 0.02; // Toe numerator
+        const F: Float = 0.30; // Toe denominator
+        ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+    }
+
+    let white_scale = 1.0 / curve(WHITE_POINT);
+    map_channels(color, |x| curve(x * EXPOSURE_BIAS) * white_scale)
+}
@@ -1,150 +1,240 @@
-use crate::{clamp, Color};
-use exr::prelude::*;
-use rayon::prelude::*;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Seek, Write};
+use std::path::Path;
 
-fn write_test_image() {
-    const IMAGE_WIDTH: usize = 256;
-    const IMAGE_HEIGHT: usize = 256;
-    const MAX_COLOR: i32 = 255;
-
-    write_header(IMAGE_WIDTH, IMAGE_HEIGHT, MAX_COLOR);
+use exr::error::UnitResult;
+use exr::prelude::*;
 
-    for vertical_index in (0..IMAGE_HEIGHT).rev() {
-        eprintln!("Scanlines remaining: {}", vertical_index);
-        for horizontal_index in 0..IMAGE_WIDTH {
-            let r: f64 = horizontal_index as f64 / (IMAGE_WIDTH - 1) as f64;
-            let g: f64 = vertical_index as f64 / (IMAGE_HEIGHT - 1) as f64;
-            let b: f64 = 0.25;
+use crate::error::{Error, Result};
+use crate::math::Float;
+use crate::output::aov::Aov;
+use crate::output::display::DisplayTransform;
+use crate::output::{png, ppm, radiance};
+use crate::rendering::Film;
+use crate::{clamp, Color};
 
-            let ir: i32 = (255.999 * r) as i32;
-            let ig: i32 = (255.999 * g) as i32;
-            let ib: i32 = (255.999 * b) as i32;
+/// File formats the film can be written as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ImageFormat {
+    /// Portable Network Graphics, 8 or 16 bits per channel
+    Png,
+    /// Netpbm pixmap, ASCII (P3) or binary (P6)
+    Ppm,
+    /// OpenEXR, 32-bit float
+    #[default]
+    Exr,
+    /// Radiance RGBE
+    Hdr,
+}
 
-            write_pixel(ir as f64, ig as f64, ib as f64);
+impl ImageFormat {
+    /// Infers the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::Ppm),
+            "exr" => Some(ImageFormat::Exr),
+            "hdr" | "pic" => Some(ImageFormat::Hdr),
+            _ => None,
         }
     }
 
-    eprintln!("Done");
+    /// Format of an image written to `path`: `format` if it is given, otherwise the one matching
+    /// the extension. Stdout defaults to PPM.
+    pub fn for_output(path: &Path, format: Option<ImageFormat>) -> Result<ImageFormat> {
+        match format.or_else(|| ImageFormat::from_path(path)) {
+            Some(format) => Ok(format),
+            None if path.as_os_str() == "-" => Ok(ImageFormat::Ppm),
+            None => Err(Error::UnknownImageFormat(path.to_path_buf())),
+        }
+    }
 }
 
-fn write_pixel(red: f64, green: f64, blue: f64) {
-    println!("{} {} {}", red, green, blue);
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum BitDepth {
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "8"))]
+    Eight,
+    #[cfg_attr(feature = "cli", value(name = "16"))]
+    Sixteen,
 }
 
-fn write_color(pixel_color: Color) {
-    let red: i32 = (pixel_color.x * 255.999) as i32;
-    let green: i32 = (pixel_color.y * 255.999) as i32;
-    let blue: i32 = (pixel_color.z * 255.999) as i32;
-    println!("{} {} {}", red, green, blue);
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PpmEncoding {
+    /// P3, one decimal number per channel
+    Ascii,
+    /// P6, one byte per channel
+    #[default]
+    Binary,
 }
 
-pub fn write_color_multisample(pixel_color: Color, samples_per_pixel: i32) {
-    let mut red = pixel_color.x;
-    let mut green = pixel_color.y;
-    let mut blue = pixel_color.z;
+#[derive(Clone, Default)]
+pub struct OutputOptions {
+    pub format: ImageFormat,
+    /// Display transform of the 8 and 16-bit formats
+    pub display: DisplayTransform,
+    pub bit_depth: BitDepth,
+    pub ppm_encoding: PpmEncoding,
+    /// Extra layers of the EXR output
+    pub aovs: Vec<Aov>,
+}
 
-    let scale = 1.0 / samples_per_pixel as f64;
-    red = (red * scale).sqrt();
-    green = (green * scale).sqrt();
-    blue = (blue * scale).sqrt();
+/// Writes the film to `path`, or to stdout if `path` is "-". Fails before creating the file if
+/// the EXR output asks for render passes the film has no data for.
+pub fn write_image(path: &Path, film: &Film, options: &OutputOptions) -> Result<()> {
+    if options.format == ImageFormat::Exr {
+        let missing: Vec<_> = options
+            .aovs
+            .iter()
+            .filter(|aov| aov.needs_aov_buffers() && film.aovs().is_none())
+            .map(Aov::layer_name)
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::InvalidSettings(format!(
+                "the {} render passes were not rendered",
+                missing.join(", ")
+            )));
+        }
+    }
+    write_image_file(path, film, options).map_err(|source| Error::WriteImage {
+        path: path.to_path_buf(),
+        source,
+    })
+}
 
-    let clamped_red = (256.0 * clamp(red, 0.0, 0.999)) as i32;
-    let clamped_green = (256.0 * clamp(green, 0.0, 0.999)) as i32;
-    let clamped_blue = (256.0 * clamp(blue, 0.0, 0.999)) as i32;
+fn write_image_file(path: &Path, film: &Film, options: &OutputOptions) -> io::Result<()> {
+    if path.as_os_str() == "-" {
+        let mut writer = BufWriter::new(io::stdout().lock());
+        if options.format == ImageFormat::Exr {
+            // The EXR encoder needs to seek, which stdout can't do
+            let mut buffer = Cursor::new(Vec::new());
+            write_exr(&mut buffer, film, &options.aovs).map_err(exr_to_io_error)?;
+            writer.write_all(buffer.get_ref())?;
+        } else {
+            write_ldr_or_hdr(&mut writer, film, options)?;
+        }
+        writer.flush()
+    } else {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if options.format == ImageFormat::Exr {
+            write_exr(&mut writer, film, &options.aovs).map_err(exr_to_io_error)?;
+        } else {
+            write_ldr_or_hdr(&mut writer, film, options)?;
+        }
+        writer.flush()
+    }
+}
 
-    println!("{} {} {}", clamped_red, clamped_green, clamped_blue);
+/// Reads an image in any of the output formats as linear colors. PNG and PPM values are decoded
+/// with the sRGB transfer function, without undoing any tone mapping.
+pub fn read_image(path: &Path) -> Result<Film> {
+    let format = ImageFormat::from_path(path)
+        .ok_or_else(|| Error::UnknownImageFormat(path.to_path_buf()))?;
+    read_image_file(path, format).map_err(|source| Error::ReadImage {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
-pub fn write_color_multisample_batch(pixels: Vec<(usize, Color)>, samples_per_pixel: i32) {
-    for (_pixel, pixel_color) in pixels {
-        let mut red = pixel_color.x;
-        let mut green = pixel_color.y;
-        let mut blue = pixel_color.z;
+fn read_image_file(path: &Path, format: ImageFormat) -> io::Result<Film> {
+    if format == ImageFormat::Exr {
+        return read_exr(path);
+    }
+    let mut reader = BufReader::new(File::open(path)?);
+    match format {
+        ImageFormat::Png => png::read_png(reader),
+        ImageFormat::Ppm => ppm::read_ppm(&mut reader),
+        ImageFormat::Hdr => radiance::read_hdr(&mut reader),
+        ImageFormat::Exr => unreachable!(),
+    }
+}
 
-        let scale = 1.0 / samples_per_pixel as f64;
-        red = (red * scale).sqrt();
-        green = (green * scale).sqrt();
-        blue = (blue * scale).sqrt();
+/// Reads the RGB channels of the first layer of an EXR file.
+fn read_exr(path: &Path) -> io::Result<Film> {
+    let image = read_first_rgba_layer_from_file(
+        path,
+        |resolution, _| (resolution.width(), vec![Color::ZERO; resolution.area()]),
+        |(width, colors), position, (red, green, blue, _): (f32, f32, f32, f32)| {
+            colors[position.y() * *width + position.x()] =
+                Color::new(red as Float, green as Float, blue as Float);
+        },
+    )
+    .map_err(exr_to_io_error)?;
+    let size = image.layer_data.size;
+    let (_, colors) = image.layer_data.channel_data.pixels;
+    Film::from_colors(size.width(), size.height(), colors)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "EXR size mismatch"))
+}
 
-        let clamped_red = (256.0 * clamp(red, 0.0, 0.999)) as i32;
-        let clamped_green = (256.0 * clamp(green, 0.0, 0.999)) as i32;
-        let clamped_blue = (256.0 * clamp(blue, 0.0, 0.999)) as i32;
+/// Unwraps I/O errors so they aren't reported twice, wraps every other EXR error.
+fn exr_to_io_error(error: exr::error::Error) -> io::Error {
+    match error {
+        exr::error::Error::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
 
-        println!("{} {} {}", clamped_red, clamped_green, clamped_blue);
+fn write_ldr_or_hdr<W: Write>(
+    writer: &mut W,
+    film: &Film,
+    options: &OutputOptions,
+) -> io::Result<()> {
+    match options.format {
+        ImageFormat::Png => png::write_png(writer, film, options.bit_depth, &options.display),
+        ImageFormat::Ppm => ppm::write_ppm(writer, film, options.ppm_encoding, &options.display),
+        ImageFormat::Hdr => radiance::write_hdr(writer, film),
+        ImageFormat::Exr => unreachable!("EXR needs a seekable writer"),
     }
 }
-//TODO: implement in parallel
-#[cfg(feature = "parallel")]
-pub fn write_color_multisample_iter(pixels: Vec<(i32, Color)>, samples_per_pixel: i32) {
-    pixels.into_par_iter().chunks(4).for_each(|chunk| {
-        let mut red = pixel_color.x;
-        let mut green = pixel_color.y;
-        let mut blue = pixel_color.z;
-
-        let scale = 1.0 / samples_per_pixel as f64;
-        red = (red * scale).sqrt();
-        green = (green * scale).sqrt();
-        blue = (blue * scale).sqrt();
-
-        let clamped_red = (256.0 * clamp(red, 0.0, 0.999)) as i32;
-        let clamped_green = (256.0 * clamp(green, 0.0, 0.999)) as i32;
-        let clamped_blue = (256.0 * clamp(blue, 0.0, 0.999)) as i32;
-
-        println!("{} {} {}", clamped_red, clamped_green, clamped_blue);
-    });
+
+/// Quantizes an encoded color channel between 0 and 1 to 8 bits.
+pub fn to_u8(channel: Float) -> u8 {
+    (256.0 * clamp(channel, 0.0, 0.999)) as u8
 }
 
-pub fn write_header(image_width: usize, image_height: usize, max_color: i32) {
-    println!("P3");
-    println!("{} {}", image_width, image_height);
-    println!("{}", max_color);
+/// Quantizes an encoded color channel between 0 and 1 to 16 bits.
+pub fn to_u16(channel: Float) -> u16 {
+    (65535.0 * clamp(channel, 0.0, 1.0)).round() as u16
 }
 
-pub fn write_exr(
-    path: PathBuf,
-    (x_size, y_size): (usize, usize),
-    pixels: Vec<(usize, Color)>,
-    samples_per_pixel: i32,
-) {
-    let scale = 1.0 / samples_per_pixel as f64;
-
-    let channel = SpecificChannels::rgb(|position: Vec2<usize>| {
-        let x = position.0;
-        let y = position.1;
-        let pixel_position = y * x_size + x;
-        let color: Color = pixels[pixel_position].1;
-        (
-            (color.x * scale).sqrt() as f32,
-            (color.y * scale).sqrt() as f32,
-            (color.z * scale).sqrt() as f32,
-        )
-    });
-    // let channel = SpecificChannels::build()
-    //     .with_channel("B")
-    //     .with_channel("G")
-    //     .with_channel("R")
-    //     .with_pixel_fn(|position| {
-    //         let x = position.0;
-    //         let y = position.1;
-    //         let pixel_position = y * x_size + x;
-    //         let color: Color = pixels[pixel_position].1;
-    //         (color.z as f32, color.y as f32, color.x as f32)
-    //     });
-    let image = Image::from_channels((x_size, y_size), channel);
-    let mut current_progress_percentage = 0;
-
-    image
-        .write()
-        .on_progress(|progress| {
-            let new_progress = (progress * 100.0) as usize;
-            if new_progress != current_progress_percentage {
-                current_progress_percentage = new_progress;
-                println!("progress: {}%", current_progress_percentage)
+/// Writes the film as linear, scene-referred radiance, with every AOV in `aovs` as an extra layer.
+/// Fails if the film has no data for one of them.
+pub fn write_exr<W: Write + Seek>(writer: &mut W, film: &Film, aovs: &[Aov]) -> UnitResult {
+    if aovs.is_empty() {
+        let channel = SpecificChannels::rgb(|position: Vec2<usize>| {
+            let color: Color = film.pixel(position.0, position.1);
+            (color.x as f32, color.y as f32, color.z as f32)
+        });
+        let image = Image::from_channels((film.width, film.height), channel);
+        return image.write().to_buffered(writer);
+    }
+
+    let mut channels = Vec::new();
+    for (axis, name) in ["R", "G", "B"].into_iter().enumerate() {
+        let samples = (0..film.height)
+            .flat_map(|y| (0..film.width).map(move |x| (x, y)))
+            .map(|(x, y)| film.pixel(x, y)[axis] as f32)
+            .collect();
+        channels.push(AnyChannel::new(name, FlatSamples::F32(samples)));
+    }
+    for aov in aovs {
+        match aov.channels(film) {
+            Some(aov_channels) => channels.extend(aov_channels),
+            None => {
+                return Err(exr::error::Error::Invalid(
+                    format!("the {} render pass was not rendered", aov.layer_name()).into(),
+                ))
             }
-        })
-        .to_file(&path)
-        .unwrap();
+        }
+    }
 
-    eprintln!("created file {}", path.to_str().unwrap());
+    let image = Image::from_channels(
+        (film.width, film.height),
+        AnyChannels::sort(SmallVec::from_vec(channels)),
+    );
+    image.write().to_buffered(writer)
 }
@@ -1,3 +1,8 @@
-#![allow(dead_code)]
-
-pub(crate) mod image;
\ No newline at end of file
+pub mod aov;
+pub(crate) mod binary;
+pub mod checkpoint;
+pub mod display;
+pub mod image;
+mod png;
+mod ppm;
+mod radiance;
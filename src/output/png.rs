@@ -0,0 +1,80 @@
+use std::io::{self, BufRead, Seek, Write};
+
+use crate::math::{Color, Float};
+use crate::output::display::{srgb_eotf, DisplayTransform};
+use crate::output::image::{to_u16, to_u8, BitDepth};
+use crate::rendering::Film;
+
+pub fn write_png<W: Write>(
+    writer: &mut W,
+    film: &Film,
+    bit_depth: BitDepth,
+    display: &DisplayTransform,
+) -> io::Result<()> {
+    let mut encoder = ::png::Encoder::new(writer, film.width as u32, film.height as u32);
+    encoder.set_color(::png::ColorType::Rgb);
+    encoder.set_source_srgb(::png::SrgbRenderingIntent::Perceptual);
+
+    let bytes_per_channel = match bit_depth {
+        BitDepth::Eight => 1,
+        BitDepth::Sixteen => 2,
+    };
+    let mut data = Vec::with_capacity(film.width * film.height * 3 * bytes_per_channel);
+    for y in 0..film.height {
+        for x in 0..film.width {
+            let color = display.apply(film.pixel(x, y));
+            for channel in [color.x, color.y, color.z] {
+                match bit_depth {
+                    BitDepth::Eight => data.push(to_u8(channel)),
+                    // PNG stores 16-bit samples big-endian
+                    BitDepth::Sixteen => data.extend_from_slice(&to_u16(channel).to_be_bytes()),
+                }
+            }
+        }
+    }
+
+    encoder.set_depth(match bit_depth {
+        BitDepth::Eight => ::png::BitDepth::Eight,
+        BitDepth::Sixteen => ::png::BitDepth::Sixteen,
+    });
+    let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+    png_writer
+        .write_image_data(&data)
+        .map_err(io::Error::other)?;
+    png_writer.finish().map_err(io::Error::other)
+}
+
+/// Reads an 8 or 16-bit PNG, decoding the sRGB values to linear colors. Alpha is ignored.
+pub fn read_png<R: BufRead + Seek>(reader: R) -> io::Result<Film> {
+    let mut decoder = ::png::Decoder::new(reader);
+    decoder.set_transformations(::png::Transformations::EXPAND);
+    let mut png_reader = decoder.read_info().map_err(io::Error::other)?;
+    let size = png_reader
+        .output_buffer_size()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PNG is too large"))?;
+    let mut data = vec![0; size];
+    let info = png_reader.next_frame(&mut data).map_err(io::Error::other)?;
+
+    let channels = info.color_type.samples();
+    let bytes_per_channel = match info.bit_depth {
+        ::png::BitDepth::Sixteen => 2,
+        _ => 1,
+    };
+    let channel = |pixel: &[u8], index: usize| match bytes_per_channel {
+        2 => u16::from_be_bytes([pixel[2 * index], pixel[2 * index + 1]]) as Float / 65535.0,
+        _ => pixel[index] as Float / 255.0,
+    };
+    let colors = data[..info.buffer_size()]
+        .chunks_exact(channels * bytes_per_channel)
+        .map(|pixel| {
+            let (red, green, blue) = match channels {
+                // Grayscale, with or without alpha
+                1 | 2 => (channel(pixel, 0), channel(pixel, 0), channel(pixel, 0)),
+                _ => (channel(pixel, 0), channel(pixel, 1), channel(pixel, 2)),
+            };
+            Color::new(srgb_eotf(red), srgb_eotf(green), srgb_eotf(blue))
+        })
+        .collect();
+    Film::from_colors(info.width as usize, info.height as usize, colors)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "truncated PNG data"))
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Read, Write};
+
+use crate::math::{Color, Float};
+use crate::output::binary::{invalid_data, pixel_count};
+use crate::output::display::{srgb_eotf, DisplayTransform};
+use crate::output::image::{to_u8, PpmEncoding};
+use crate::rendering::Film;
+
+pub fn write_ppm<W: Write>(
+    writer: &mut W,
+    film: &Film,
+    encoding: PpmEncoding,
+    display: &DisplayTransform,
+) -> io::Result<()> {
+    let magic = match encoding {
+        PpmEncoding::Ascii => "P3",
+        PpmEncoding::Binary => "P6",
+    };
+    write_header(writer, magic, film.width, film.height, 255)?;
+
+    for y in 0..film.height {
+        for x in 0..film.width {
+            let color = display.apply(film.pixel(x, y));
+            let (red, green, blue) = (to_u8(color.x), to_u8(color.y), to_u8(color.z));
+            match encoding {
+                PpmEncoding::Ascii => writeln!(writer, "{} {} {}", red, green, blue)?,
+                PpmEncoding::Binary => writer.write_all(&[red, green, blue])?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(
+    writer: &mut W,
+    magic: &str,
+    image_width: usize,
+    image_height: usize,
+    max_color: i32,
+) -> io::Result<()> {
+    writeln!(writer, "{}", magic)?;
+    writeln!(writer, "{} {}", image_width, image_height)?;
+    writeln!(writer, "{}", max_color)
+}
+
+/// Reads an ASCII (P3) or binary (P6) PPM, decoding the sRGB values to linear colors.
+pub fn read_ppm<R: Read>(reader: &mut R) -> io::Result<Film> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut position = 0;
+    let magic = next_token(&data, &mut position)?;
+    let width = parse_number(next_token(&data, &mut position)?)?;
+    let height = parse_number(next_token(&data, &mut position)?)?;
+    let max_color = parse_number(next_token(&data, &mut position)?)?;
+    if max_color == 0 || max_color > 65535 {
+        return Err(invalid_data("PPM maximum color value is out of range"));
+    }
+
+    let count = pixel_count(width, height)? * 3;
+    // Every value takes at least a byte, so a larger count means the file is cut short
+    if count > data.len() - position {
+        return Err(invalid_data("truncated PPM data"));
+    }
+    let values: Vec<usize> = match magic {
+        b"P3" => (0..count)
+            .map(|_| parse_number(next_token(&data, &mut position)?))
+            .collect::<io::Result<_>>()?,
+        b"P6" => {
+            // A single whitespace character separates the header from the binary data
+            let start = position + 1;
+            let bytes_per_value = if max_color < 256 { 1 } else { 2 };
+            let body = data
+                .get(start..start + count * bytes_per_value)
+                .ok_or_else(|| invalid_data("truncated PPM data"))?;
+            match bytes_per_value {
+                1 => body.iter().map(|&value| value as usize).collect(),
+                _ => body
+                    .chunks_exact(2)
+                    .map(|value| u16::from_be_bytes([value[0], value[1]]) as usize)
+                    .collect(),
+            }
+        }
+        _ => return Err(invalid_data("not a P3 or P6 PPM")),
+    };
+
+    let decode = |value: usize| srgb_eotf(value.min(max_color) as Float / max_color as Float);
+    let colors = values
+        .chunks_exact(3)
+        .map(|rgb| Color::new(decode(rgb[0]), decode(rgb[1]), decode(rgb[2])))
+        .collect();
+    Film::from_colors(width, height, colors).ok_or_else(|| invalid_data("truncated PPM data"))
+}
+
+/// Next whitespace separated token, skipping comments that run from '#' to the end of the line.
+fn next_token<'a>(data: &'a [u8], position: &mut usize) -> io::Result<&'a [u8]> {
+    loop {
+        match data.get(*position) {
+            Some(byte) if byte.is_ascii_whitespace() => *position += 1,
+            Some(b'#') => {
+                while data.get(*position).is_some_and(|&byte| byte != b'\n') {
+                    *position += 1;
+                }
+            }
+            Some(_) => break,
+            None => return Err(invalid_data("truncated PPM header")),
+        }
+    }
+    let start = *position;
+    while data
+        .get(*position)
+        .is_some_and(|byte| !byte.is_ascii_whitespace())
+    {
+        *position += 1;
+    }
+    Ok(&data[start..*position])
+}
+
+fn parse_number(token: &[u8]) -> io::Result<usize> {
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| invalid_data("invalid number in PPM"))
+}
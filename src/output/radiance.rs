@@ -0,0 +1,166 @@
+use std::io::{self, BufRead, Read, Write};
+
+use crate::math::{Color, Float};
+use crate::output::binary::{invalid_data, pixel_count};
+use crate::rendering::Film;
+
+/// Writes the film as an uncompressed Radiance RGBE image holding linear radiance.
+pub fn write_hdr<W: Write>(writer: &mut W, film: &Film) -> io::Result<()> {
+    writeln!(writer, "#?RADIANCE")?;
+    writeln!(writer, "FORMAT=32-bit_rle_rgbe")?;
+    writeln!(writer)?;
+    writeln!(writer, "-Y {} +X {}", film.height, film.width)?;
+
+    for y in 0..film.height {
+        for x in 0..film.width {
+            writer.write_all(&to_rgbe(film.pixel(x, y)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Shared-exponent encoding: three 8-bit mantissas and the exponent of the largest channel.
+fn to_rgbe(color: Color) -> [u8; 4] {
+    let red = color.x.max(0.0);
+    let green = color.y.max(0.0);
+    let blue = color.z.max(0.0);
+    let largest = red.max(green).max(blue);
+    if largest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // largest = mantissa * 2^exponent with mantissa in [0.5, 1)
+    let exponent = largest.log2().floor() as i32 + 1;
+    let scale = 256.0 / Float::powi(2.0, exponent);
+    [
+        (red * scale).min(255.0) as u8,
+        (green * scale).min(255.0) as u8,
+        (blue * scale).min(255.0) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+/// Reads a Radiance RGBE image, flat or run-length encoded, with the standard "-Y h +X w"
+/// orientation.
+pub fn read_hdr<R: Read>(reader: &mut R) -> io::Result<Film> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let mut reader = data.as_slice();
+
+    let mut line = String::new();
+    // Header lines run until an empty line
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("truncated Radiance header"));
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        if line.starts_with("FORMAT=") && line.trim() != "FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid_data("only RGBE Radiance images are supported"));
+        }
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let resolution: Vec<&str> = line.split_whitespace().collect();
+    let (height, width) = match resolution.as_slice() {
+        ["-Y", height, "+X", width] => (
+            height
+                .parse::<usize>()
+                .map_err(|_| invalid_data("invalid height"))?,
+            width
+                .parse::<usize>()
+                .map_err(|_| invalid_data("invalid width"))?,
+        ),
+        _ => return Err(invalid_data("unsupported Radiance orientation")),
+    };
+
+    let count = pixel_count(width, height)?;
+    if reader.len() / min_scanline_bytes(width) < height {
+        return Err(invalid_data("truncated Radiance data"));
+    }
+
+    let mut colors = Vec::with_capacity(count);
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        read_scanline(&mut reader, &mut scanline)?;
+        colors.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+    }
+    Film::from_colors(width, height, colors).ok_or_else(|| invalid_data("truncated Radiance data"))
+}
+
+/// Fewest bytes a scanline of `width` pixels can be stored in: flat, or run-length encoded with
+/// the longest runs.
+fn min_scanline_bytes(width: usize) -> usize {
+    match run_length_encodable(width) {
+        true => (4 + 4 * 2 * width.div_ceil(127)).min(4 * width),
+        false => 4 * width,
+    }
+}
+
+fn run_length_encodable(width: usize) -> bool {
+    (8..0x8000).contains(&width)
+}
+
+fn read_scanline<R: BufRead>(reader: &mut R, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let mut first = [0u8; 4];
+    reader.read_exact(&mut first)?;
+    let width = scanline.len();
+    let run_length_encoded = run_length_encodable(width)
+        && first[0] == 2
+        && first[1] == 2
+        && ((first[2] as usize) << 8 | first[3] as usize) == width;
+    if !run_length_encoded {
+        scanline[0] = first;
+        for pixel in &mut scanline[1..] {
+            reader.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+
+    // Every component is stored separately as runs of a repeated byte or literal bytes
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            reader.read_exact(&mut count)?;
+            let (repeated, count) = match count[0] {
+                count @ 129.. => (true, count as usize - 128),
+                count => (false, count as usize),
+            };
+            if count == 0 || x + count > width {
+                return Err(invalid_data("corrupt run-length encoded scanline"));
+            }
+            if repeated {
+                let mut value = [0u8; 1];
+                reader.read_exact(&mut value)?;
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[component] = value[0];
+                }
+            } else {
+                for pixel in &mut scanline[x..x + count] {
+                    let mut value = [0u8; 1];
+                    reader.read_exact(&mut value)?;
+                    pixel[component] = value[0];
+                }
+            }
+            x += count;
+        }
+    }
+    Ok(())
+}
+
+/// Inverse of `to_rgbe`, taking the center of each mantissa step.
+fn from_rgbe([red, green, blue, exponent]: [u8; 4]) -> Color {
+    if exponent == 0 {
+        return Color::ZERO;
+    }
+    let scale = Float::powi(2.0, exponent as i32 - 136);
+    Color::new(
+        (red as Float + 0.5) * scale,
+        (green as Float + 0.5) * scale,
+        (blue as Float + 0.5) * scale,
+    )
+}
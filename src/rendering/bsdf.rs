@@ -0,0 +1,124 @@
+use crate::math::{random_scalar, Color, Float, Vec3, PI};
+
+/// Bidirectional scattering distribution function in a local shading frame where the normal is
+/// +z. `wo` points towards the viewer and `wi` towards the light, both normalized.
+pub trait Bsdf {
+    /// Fraction of the radiance arriving from `wi` that is scattered towards `wo`, per steradian.
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color;
+
+    /// Picks an incoming direction for `wo`, `None` if the sample is absorbed.
+    fn sample(&self, wo: Vec3) -> Option<Vec3>;
+
+    /// Density of `sample` returning `wi`, per steradian.
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> Float;
+}
+
+pub struct Lambertian {
+    pub albedo: Color,
+}
+
+impl Bsdf for Lambertian {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        match wo.z > 0.0 && wi.z > 0.0 {
+            true => self.albedo / PI,
+            false => Color::ZERO,
+        }
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<Vec3> {
+        (wo.z > 0.0).then(Vec3::random_cosine_direction)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> Float {
+        match wo.z > 0.0 && wi.z > 0.0 {
+            true => wi.z / PI,
+            false => 0.0,
+        }
+    }
+}
+
+/// Rough conductor with the GGX (Trowbridge-Reitz) microfacet distribution, the Smith masking
+/// function and Schlick's Fresnel approximation.
+pub struct Ggx {
+    /// Reflectance at normal incidence
+    pub reflectance: Color,
+    /// Width of the distribution, the square of the perceptual roughness
+    pub alpha: Float,
+}
+
+impl Ggx {
+    pub fn from_roughness(reflectance: Color, roughness: Float) -> Ggx {
+        Ggx {
+            reflectance,
+            // Perfectly smooth surfaces would make the distribution a Dirac delta
+            alpha: (roughness * roughness).max(1e-4),
+        }
+    }
+
+    /// Density of microfacet normals `half` per steradian, D(h).
+    pub fn distribution(&self, half: Vec3) -> Float {
+        if half.z <= 0.0 {
+            return 0.0;
+        }
+        let alpha2 = self.alpha * self.alpha;
+        let cos2 = half.z * half.z;
+        let denominator = cos2 * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    /// Microfacet normal distributed as D(h) cos(theta_h).
+    pub fn sample_normal(&self) -> Vec3 {
+        let r1 = random_scalar();
+        let r2 = random_scalar();
+        let phi = 2.0 * PI * r1;
+        let tan2 = self.alpha * self.alpha * r2 / (1.0 - r2);
+        let cos_theta = 1.0 / (1.0 + tan2).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+
+    /// Density of `sample_normal` per steradian.
+    pub fn normal_pdf(&self, half: Vec3) -> Float {
+        self.distribution(half) * half.z
+    }
+
+    /// Smith masking of one direction.
+    fn masking(&self, direction: Vec3) -> Float {
+        let cos2 = direction.z * direction.z;
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        2.0 / (1.0 + (1.0 + self.alpha * self.alpha * tan2).sqrt())
+    }
+
+    fn fresnel(&self, cos_theta: Float) -> Color {
+        self.reflectance + (Color::ONE - self.reflectance) * (1.0 - cos_theta).max(0.0).powi(5)
+    }
+}
+
+impl Bsdf for Ggx {
+    fn eval(&self, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::ZERO;
+        }
+        let half = (wo + wi).unit_vector();
+        let cos_half = Vec3::dot_product(&wo, &half);
+        self.fresnel(cos_half) * self.distribution(half) * self.masking(wo) * self.masking(wi)
+            / (4.0 * wo.z * wi.z)
+    }
+
+    fn sample(&self, wo: Vec3) -> Option<Vec3> {
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wi = Vec3::reflect(-wo, self.sample_normal());
+        (wi.z > 0.0).then_some(wi)
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3) -> Float {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let half = (wo + wi).unit_vector();
+        // Change of variables from the half vector to the reflected direction
+        self.normal_pdf(half) / (4.0 * Vec3::dot_product(&wo, &half))
+    }
+}
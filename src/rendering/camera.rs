@@ -1,7 +1,7 @@
-use crate::math::{Point3, Ray, Vec3};
+use crate::math::{degrees_to_radians, Float, Point3, Ray, Vec3};
 
 pub struct Camera {
-    pub(crate) aspect_ratio: f64,
+    pub(crate) aspect_ratio: Float,
     pub(crate) origin: Point3,
     pub(crate) lower_left_corner: Point3,
     pub(crate) horizontal: Vec3,
@@ -9,9 +9,15 @@ pub struct Camera {
     // pub u: Vec3,
     // pub v: Vec3,
     // pub w: Vec3,
-    // pub lens_radius: f64,
-    // pub time0: f64,
-    // pub time1: f64,
+    // pub lens_radius: Float,
+    // pub time0: Float,
+    // pub time1: Float,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
 }
 
 impl Camera {
@@ -21,7 +27,7 @@ impl Camera {
         let viewport_width = aspect_ratio * viewport_height;
         let focal_length = 1.0;
 
-        let origin = Point3::ZERO;
+        let origin = Point3::ORIGIN;
         let horizontal = Vec3 {
             x: viewport_width,
             y: 0.0,
@@ -32,7 +38,7 @@ impl Camera {
             y: viewport_height,
             z: 0.0,
         };
-        let lower_left_corner: Vec3 = origin
+        let lower_left_corner: Point3 = origin
             - horizontal / 2.0
             - vertical / 2.0
             - Vec3 {
@@ -49,7 +55,39 @@ impl Camera {
             lower_left_corner,
         }
     }
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
+    /// Camera at `look_from` looking towards `look_at`, with `up` pointing roughly upwards in the
+    /// image. `vertical_fov` is the vertical field of view in degrees.
+    pub fn look_at(
+        look_from: Point3,
+        look_at: Point3,
+        up: Vec3,
+        vertical_fov: Float,
+        aspect_ratio: Float,
+    ) -> Camera {
+        let viewport_height = 2.0 * (degrees_to_radians(vertical_fov) / 2.0).tan();
+        let viewport_width = aspect_ratio * viewport_height;
+
+        // Orthonormal basis, w points backwards out of the screen
+        let w = (look_from - look_at).unit_vector();
+        let u = Vec3::cross_product(&up, &w).unit_vector();
+        let v = Vec3::cross_product(&w, &u);
+
+        let horizontal = viewport_width * u;
+        let vertical = viewport_height * v;
+        Camera {
+            aspect_ratio,
+            origin: look_from,
+            horizontal,
+            vertical,
+            lower_left_corner: look_from - horizontal / 2.0 - vertical / 2.0 - w,
+        }
+    }
+
+    pub fn aspect_ratio(&self) -> Float {
+        self.aspect_ratio
+    }
+
+    pub fn get_ray(&self, u: Float, v: Float) -> Ray {
         Ray {
             origin: self.origin,
             direction: self.lower_left_corner + u * self.horizontal + v * self.vertical
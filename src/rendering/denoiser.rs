@@ -0,0 +1,281 @@
+use rayon::prelude::*;
+
+use crate::math::{Color, Float, Vec3};
+use crate::rendering::film::{Film, FilmPixel};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Denoiser {
+    #[default]
+    None,
+    /// Edge-avoiding à-trous wavelet filter guided by albedo, normal and depth
+    Atrous,
+}
+
+pub struct DenoiseSettings {
+    /// Number of wavelet passes, the filter footprint doubles every pass
+    pub iterations: u32,
+    /// Tolerance of luminance differences, in standard deviations of the noise
+    pub sigma_luminance: Float,
+    /// Exponent of the normal similarity, higher values keep creases sharper
+    pub sigma_normal: Float,
+    /// Tolerance of depth differences, relative to the local depth gradient
+    pub sigma_depth: Float,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings {
+            iterations: 5,
+            sigma_luminance: 4.0,
+            sigma_normal: 128.0,
+            sigma_depth: 1.0,
+        }
+    }
+}
+
+/// B3 spline, the 1D kernel of every wavelet pass.
+const KERNEL: [Float; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Below this sample count the per-pixel variance is too unreliable and is estimated from the
+/// neighbouring pixels instead.
+const MIN_SAMPLES_FOR_VARIANCE: u32 = 4;
+
+/// Geometry the filter weights compare to avoid blurring across edges.
+struct Guide {
+    albedo: Color,
+    normal: Vec3,
+    depth: Float,
+    depth_gradient: Float,
+    hit: bool,
+}
+
+/// Denoises the film with an edge-avoiding à-trous wavelet filter (Dammertz et al. 2010) with
+/// variance guided luminance weights (Schied et al. 2017). The lighting is filtered without the
+/// albedo, so textures stay sharp. Returns `None` if the film has no AOVs to guide the filter.
+pub fn denoise(film: &Film, settings: &DenoiseSettings) -> Option<Film> {
+    let aovs = film.aovs()?;
+    let (width, height) = (film.width, film.height);
+
+    let depths: Vec<Float> = aovs.iter().map(|aov| aov.depth()).collect();
+    let guides: Vec<Guide> = film
+        .pixels()
+        .iter()
+        .zip(aovs)
+        .enumerate()
+        .map(|(index, (pixel, aov))| {
+            let albedo = match pixel.weight > 0.0 {
+                true => aov.albedo / pixel.weight,
+                false => Color::ONE,
+            };
+            Guide {
+                // Keep dark albedos from blowing up the demodulated lighting
+                albedo: Color::new(albedo.x.max(0.01), albedo.y.max(0.01), albedo.z.max(0.01)),
+                normal: match aov.hits {
+                    0 => Vec3::ZERO,
+                    _ => Vec3::from(aov.normal()).unit_vector(),
+                },
+                depth: depths[index],
+                depth_gradient: depth_gradient(&depths, width, height, index),
+                hit: aov.hits > 0,
+            }
+        })
+        .collect();
+
+    let mut lighting: Vec<Color> = (0..width * height)
+        .map(|index| film.pixel(index % width, index / width) / guides[index].albedo)
+        .collect();
+    let mut variance: Vec<Float> = (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            if film.pixels()[index].samples >= MIN_SAMPLES_FOR_VARIANCE {
+                luminance(film.variance(x, y)) / luminance(guides[index].albedo).powi(2)
+            } else {
+                spatial_variance(&lighting, &guides, width, height, x, y)
+            }
+        })
+        .collect();
+
+    for iteration in 0..settings.iterations {
+        let step = 1usize << iteration;
+        let blurred_variance = blur_variance(&variance, width, height);
+        let filtered: Vec<(Color, Float)> = (0..width * height)
+            .into_par_iter()
+            .map(|index| {
+                filter_pixel(
+                    &lighting,
+                    &variance,
+                    &blurred_variance,
+                    &guides,
+                    (width, height),
+                    index,
+                    step,
+                    settings,
+                )
+            })
+            .collect();
+        (lighting, variance) = filtered.into_iter().unzip();
+    }
+
+    let pixels = film
+        .pixels()
+        .iter()
+        .zip(lighting.iter().zip(&guides))
+        .map(|(pixel, (lighting, guide))| FilmPixel {
+            // Keep the weight so the AOVs, which are divided by it, stay intact
+            sum: *lighting * guide.albedo * pixel.weight,
+            ..*pixel
+        })
+        .collect();
+    Film::from_pixels(width, height, pixels, Some(aovs.to_vec()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn filter_pixel(
+    lighting: &[Color],
+    variance: &[Float],
+    blurred_variance: &[Float],
+    guides: &[Guide],
+    (width, height): (usize, usize),
+    index: usize,
+    step: usize,
+    settings: &DenoiseSettings,
+) -> (Color, Float) {
+    let (x, y) = ((index % width) as isize, (index / width) as isize);
+    let center = &guides[index];
+    let center_luminance = luminance(lighting[index]);
+    let luminance_scale =
+        settings.sigma_luminance * blurred_variance[index].max(0.0).sqrt() + 1e-10;
+
+    let mut weight_sum = 0.0;
+    let mut color_sum = Color::ZERO;
+    let mut variance_sum = 0.0;
+    for (j, kernel_y) in KERNEL.iter().enumerate() {
+        for (i, kernel_x) in KERNEL.iter().enumerate() {
+            let offset_x = (i as isize - 2) * step as isize;
+            let offset_y = (j as isize - 2) * step as isize;
+            let (qx, qy) = (x + offset_x, y + offset_y);
+            if qx < 0 || qy < 0 || qx >= width as isize || qy >= height as isize {
+                continue;
+            }
+            let q = qy as usize * width + qx as usize;
+            let other = &guides[q];
+
+            let weight = if q == index {
+                kernel_x * kernel_y
+            } else {
+                let distance = ((offset_x * offset_x + offset_y * offset_y) as Float).sqrt();
+                let luminance_weight =
+                    (-(center_luminance - luminance(lighting[q])).abs() / luminance_scale).exp();
+                kernel_x
+                    * kernel_y
+                    * luminance_weight
+                    * geometry_weight(center, other, distance, settings)
+            };
+
+            weight_sum += weight;
+            color_sum += weight * lighting[q];
+            variance_sum += weight * weight * variance[q];
+        }
+    }
+
+    (
+        color_sum / weight_sum,
+        variance_sum / (weight_sum * weight_sum),
+    )
+}
+
+/// Edge stopping weight from normals and depth, 0 between a surface and the background.
+fn geometry_weight(
+    center: &Guide,
+    other: &Guide,
+    distance: Float,
+    settings: &DenoiseSettings,
+) -> Float {
+    match (center.hit, other.hit) {
+        (false, false) => 1.0,
+        (true, true) => {
+            let normal_weight = Vec3::dot_product(&center.normal, &other.normal)
+                .max(0.0)
+                .powf(settings.sigma_normal);
+            let depth_weight = (-(center.depth - other.depth).abs()
+                / (settings.sigma_depth * center.depth_gradient * distance + 1e-10))
+                .exp();
+            normal_weight * depth_weight
+        }
+        _ => 0.0,
+    }
+}
+
+/// Largest depth difference to a horizontal or vertical neighbour that isn't background.
+fn depth_gradient(depths: &[Float], width: usize, height: usize, index: usize) -> Float {
+    let (x, y) = (index % width, index / width);
+    let depth = depths[index];
+    if !depth.is_finite() {
+        return 0.0;
+    }
+    [
+        (x > 0).then(|| index - 1),
+        (x + 1 < width).then(|| index + 1),
+        (y > 0).then(|| index - width),
+        (y + 1 < height).then(|| index + width),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|neighbour| depths[neighbour])
+    .filter(|neighbour| neighbour.is_finite())
+    .map(|neighbour| (neighbour - depth).abs())
+    .fold(0.0, Float::max)
+}
+
+/// Luminance variance of the 5x5 neighbourhood on the same side of the background boundary.
+fn spatial_variance(
+    lighting: &[Color],
+    guides: &[Guide],
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> Float {
+    let hit = guides[y * width + x].hit;
+    let (mut sum, mut sum_squares, mut count) = (0.0, 0.0, 0.0);
+    for qy in y.saturating_sub(2)..(y + 3).min(height) {
+        for qx in x.saturating_sub(2)..(x + 3).min(width) {
+            let q = qy * width + qx;
+            if guides[q].hit == hit {
+                let value = luminance(lighting[q]);
+                sum += value;
+                sum_squares += value * value;
+                count += 1.0;
+            }
+        }
+    }
+    let mean = sum / count;
+    (sum_squares / count - mean * mean).max(0.0)
+}
+
+/// 3x3 Gaussian blur, makes the variance estimate used by the luminance weights more stable.
+fn blur_variance(variance: &[Float], width: usize, height: usize) -> Vec<Float> {
+    const GAUSSIAN: [Float; 3] = [0.25, 0.5, 0.25];
+    (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let (mut sum, mut weight_sum) = (0.0, 0.0);
+            for (j, kernel_y) in GAUSSIAN.iter().enumerate() {
+                for (i, kernel_x) in GAUSSIAN.iter().enumerate() {
+                    let (qx, qy) = ((x + i).wrapping_sub(1), (y + j).wrapping_sub(1));
+                    if qx < width && qy < height {
+                        sum += kernel_x * kernel_y * variance[qy * width + qx];
+                        weight_sum += kernel_x * kernel_y;
+                    }
+                }
+            }
+            sum / weight_sum
+        })
+        .collect()
+}
+
+fn luminance(color: Color) -> Float {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
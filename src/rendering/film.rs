@@ -0,0 +1,238 @@
+use crate::math::{Color, Float, Normal3, Point3, Vec3};
+use crate::rendering::sampling::PathSample;
+use crate::rendering::tiles::Tile;
+
+/// Accumulated state of a single pixel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilmPixel {
+    /// Sum of the weighted radiance of every sample
+    pub sum: Color,
+    /// Sum of the sample weights
+    pub weight: Float,
+    /// Number of samples taken so far
+    pub samples: u32,
+}
+
+impl FilmPixel {
+    pub fn add_sample(&mut self, color: Color) {
+        self.sum += color;
+        self.weight += 1.0;
+        self.samples += 1;
+    }
+
+    /// Averaged color of the pixel, black if no sample has been taken yet.
+    pub fn color(&self) -> Color {
+        if self.weight > 0.0 {
+            self.sum / self.weight
+        } else {
+            Color::ZERO
+        }
+    }
+}
+
+/// Accumulated arbitrary output variables of a single pixel. Radiance and albedo are summed over all
+/// samples, geometric data only over the samples that hit a surface.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AovPixel {
+    pub albedo: Color,
+    pub normal: Vec3,
+    /// Sum of the positions as vectors from the origin
+    pub position: Vec3,
+    pub depth: Float,
+    /// Number of samples that hit a surface
+    pub hits: u32,
+    /// IDs of the first sample that hit a surface, 0 for the background
+    pub object_id: u32,
+    pub material_id: u32,
+    pub emission: Color,
+    pub diffuse_direct: Color,
+    pub diffuse_indirect: Color,
+    pub specular_direct: Color,
+    pub specular_indirect: Color,
+    /// Sum of the squared sample colors, for the variance
+    pub sum_squares: Color,
+}
+
+impl AovPixel {
+    pub fn add_sample(&mut self, sample: &PathSample) {
+        self.albedo += sample.albedo;
+        if let Some(hit) = &sample.first_hit {
+            if self.hits == 0 {
+                self.object_id = hit.object_id;
+                self.material_id = hit.material_id;
+            }
+            self.normal += Vec3::from(hit.normal);
+            self.position += Vec3::from(hit.position);
+            self.depth += hit.depth;
+            self.hits += 1;
+        }
+        self.emission += sample.components.emission;
+        self.diffuse_direct += sample.components.diffuse_direct;
+        self.diffuse_indirect += sample.components.diffuse_indirect;
+        self.specular_direct += sample.components.specular_direct;
+        self.specular_indirect += sample.components.specular_indirect;
+        self.sum_squares += sample.color * sample.color;
+    }
+
+    /// Averaged shading normal, zero for the background.
+    pub fn normal(&self) -> Normal3 {
+        match self.hits {
+            0 => Normal3::default(),
+            hits => Normal3::from(self.normal / hits as Float),
+        }
+    }
+
+    /// Averaged world space position, zero for the background.
+    pub fn position(&self) -> Point3 {
+        match self.hits {
+            0 => Point3::ORIGIN,
+            hits => Point3::from(self.position / hits as Float),
+        }
+    }
+
+    /// Averaged distance from the camera, infinite for the background.
+    pub fn depth(&self) -> Float {
+        match self.hits {
+            0 => Float::INFINITY,
+            hits => self.depth / hits as Float,
+        }
+    }
+}
+
+/// Accumulation buffer for the whole image. Pixels are stored row-major with row 0 at the top.
+pub struct Film {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pixels: Vec<FilmPixel>,
+    /// Only allocated when AOVs are requested, they take several times the memory of the pixels
+    aovs: Option<Vec<AovPixel>>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Film {
+        Film {
+            width,
+            height,
+            pixels: vec![FilmPixel::default(); width * height],
+            aovs: None,
+        }
+    }
+
+    /// Film that also accumulates the arbitrary output variables.
+    pub fn with_aovs(width: usize, height: usize) -> Film {
+        Film {
+            aovs: Some(vec![AovPixel::default(); width * height]),
+            ..Film::new(width, height)
+        }
+    }
+
+    /// Builds a film from previously accumulated pixels, e.g. a checkpoint.
+    /// Returns `None` if the pixel count does not match the dimensions.
+    pub fn from_pixels(
+        width: usize,
+        height: usize,
+        pixels: Vec<FilmPixel>,
+        aovs: Option<Vec<AovPixel>>,
+    ) -> Option<Film> {
+        let pixel_count = width * height;
+        if pixels.len() != pixel_count
+            || aovs.as_ref().is_some_and(|aovs| aovs.len() != pixel_count)
+        {
+            return None;
+        }
+        Some(Film {
+            width,
+            height,
+            pixels,
+            aovs,
+        })
+    }
+
+    /// Film holding a finished image, every pixel counts as a single sample of its color. Returns
+    /// `None` if the number of colors doesn't match the size.
+    pub fn from_colors(width: usize, height: usize, colors: Vec<Color>) -> Option<Film> {
+        let pixels = colors
+            .into_iter()
+            .map(|color| FilmPixel {
+                sum: color,
+                weight: 1.0,
+                samples: 1,
+            })
+            .collect();
+        Film::from_pixels(width, height, pixels, None)
+    }
+
+    /// Copies the accumulated state of the pixels covered by `tile` into `pixels` and `aovs`.
+    /// `aovs` is left empty if the film has no AOVs.
+    pub fn read_tile(&self, tile: &Tile, pixels: &mut Vec<FilmPixel>, aovs: &mut Vec<AovPixel>) {
+        pixels.clear();
+        aovs.clear();
+        for row in 0..tile.height {
+            let start = (tile.y + row) * self.width + tile.x;
+            pixels.extend_from_slice(&self.pixels[start..start + tile.width]);
+            if let Some(film_aovs) = &self.aovs {
+                aovs.extend_from_slice(&film_aovs[start..start + tile.width]);
+            }
+        }
+    }
+
+    /// Stores the accumulated state of a rendered tile back into the film.
+    pub fn write_tile(&mut self, tile: &Tile, pixels: &[FilmPixel], aovs: &[AovPixel]) {
+        for row in 0..tile.height {
+            let start = (tile.y + row) * self.width + tile.x;
+            let tile_row = row * tile.width..(row + 1) * tile.width;
+            self.pixels[start..start + tile.width].copy_from_slice(&pixels[tile_row.clone()]);
+            if let Some(film_aovs) = &mut self.aovs {
+                film_aovs[start..start + tile.width].copy_from_slice(&aovs[tile_row]);
+            }
+        }
+    }
+
+    /// Averaged color of the pixel at (x, y).
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x].color()
+    }
+
+    pub fn pixels(&self) -> &[FilmPixel] {
+        &self.pixels
+    }
+
+    pub fn aovs(&self) -> Option<&[AovPixel]> {
+        self.aovs.as_deref()
+    }
+
+    /// Variance of the averaged color of the pixel at (x, y), i.e. the sample variance divided by
+    /// the sample count. Zero if the film has no AOVs or the pixel fewer than two samples.
+    pub fn variance(&self, x: usize, y: usize) -> Color {
+        let index = y * self.width + x;
+        let pixel = &self.pixels[index];
+        let (Some(aovs), true) = (&self.aovs, pixel.samples > 1) else {
+            return Color::ZERO;
+        };
+        let n = pixel.samples as Float;
+        let mean = pixel.sum / n;
+        let sample_variance = (aovs[index].sum_squares - n * mean * mean) / (n - 1.0);
+        Color::new(
+            sample_variance.x.max(0.0),
+            sample_variance.y.max(0.0),
+            sample_variance.z.max(0.0),
+        ) / n
+    }
+
+    /// Lowest sample count of any pixel.
+    pub fn min_samples(&self) -> u32 {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.samples)
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
@@ -0,0 +1,287 @@
+//! Voxel grids of density for heterogeneous volumes, and a small binary file format for them.
+//!
+//! A grid file starts with the magic `RTGRID01`, then the resolution as three little-endian
+//! `u64` and an encoding byte. Values are little-endian `f32` with x varying fastest, then y,
+//! then z.
+//!
+//! - Encoding 0 is dense: every voxel of the grid follows.
+//! - Encoding 1 is sparse, like the leaves of an OpenVDB tree: a `u64` count of bricks follows,
+//!   each with its position in bricks as three `u64` and the 8x8x8 voxels of the brick. Bricks
+//!   that aren't in the file are empty, and voxels of bricks past the edge of the grid are
+//!   ignored.
+//!
+//! NanoVDB and OpenVDB files themselves aren't read, they can be converted with a short script.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::math::{Float, Point3};
+use crate::output::binary::{invalid_data, read_bytes, read_u64};
+
+const MAGIC: &[u8; 8] = b"RTGRID01";
+/// Voxels along each side of a brick
+const BRICK: usize = 8;
+const BRICK_VOXELS: usize = BRICK * BRICK * BRICK;
+/// Index of a brick with only empty voxels
+const EMPTY: u32 = u32::MAX;
+
+/// Grid of non-negative densities, stored as bricks of 8x8x8 voxels where the bricks with only
+/// empty voxels take no memory. Voxel values are at the centers of the cells of the unit cube
+/// split into `resolution` cells.
+#[derive(Debug, Clone)]
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    bricks_per_axis: [usize; 3],
+    /// Index in `bricks` of every brick, x fastest, `EMPTY` if none of its voxels is set
+    table: Vec<u32>,
+    bricks: Vec<[f32; BRICK_VOXELS]>,
+    max: f32,
+}
+
+impl DensityGrid {
+    /// Grid of the dense `values`, x varying fastest. `None` if they don't fill `resolution`, if
+    /// a side is 0 or if a value is negative or not finite.
+    pub fn new(resolution: [usize; 3], values: &[f32]) -> Option<DensityGrid> {
+        let [nx, ny, nz] = resolution;
+        if nx * ny * nz == 0 || values.len() != nx * ny * nz {
+            return None;
+        }
+        let mut grid = DensityGrid::empty(resolution);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    grid.set(x, y, z, values[x + nx * (y + ny * z)])?;
+                }
+            }
+        }
+        Some(grid)
+    }
+
+    /// Grid of `density` at the center of every voxel, for points in the unit cube. Negative
+    /// densities are set to 0.
+    pub fn from_fn(resolution: [usize; 3], density: impl Fn(Point3) -> Float) -> DensityGrid {
+        let [nx, ny, nz] = resolution.map(|side| side.max(1));
+        let mut values = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let point = Point3::new(
+                        (x as Float + 0.5) / nx as Float,
+                        (y as Float + 0.5) / ny as Float,
+                        (z as Float + 0.5) / nz as Float,
+                    );
+                    let value = density(point);
+                    values.push(match value.is_finite() {
+                        true => value.max(0.0) as f32,
+                        false => 0.0,
+                    });
+                }
+            }
+        }
+        DensityGrid::new([nx, ny, nz], &values).expect("values fill the grid")
+    }
+
+    fn empty(resolution: [usize; 3]) -> DensityGrid {
+        let bricks_per_axis = resolution.map(|side| side.div_ceil(BRICK));
+        DensityGrid {
+            resolution,
+            bricks_per_axis,
+            table: vec![EMPTY; bricks_per_axis.iter().product()],
+            bricks: Vec::new(),
+            max: 0.0,
+        }
+    }
+
+    /// Sets a voxel inside the grid, `None` if the value is negative or not finite.
+    fn set(&mut self, x: usize, y: usize, z: usize, value: f32) -> Option<()> {
+        if !(value.is_finite() && value >= 0.0) {
+            return None;
+        }
+        if value == 0.0 {
+            return Some(());
+        }
+        let [bx, by, _] = self.bricks_per_axis;
+        let brick = x / BRICK + bx * (y / BRICK + by * (z / BRICK));
+        if self.table[brick] == EMPTY {
+            self.table[brick] = self.bricks.len() as u32;
+            self.bricks.push([0.0; BRICK_VOXELS]);
+        }
+        let voxel = x % BRICK + BRICK * (y % BRICK + BRICK * (z % BRICK));
+        self.bricks[self.table[brick] as usize][voxel] = value;
+        self.max = self.max.max(value);
+        Some(())
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    /// Largest density in the grid.
+    pub fn max(&self) -> Float {
+        self.max as Float
+    }
+
+    /// Bricks of 8x8x8 voxels that have a voxel set, and take memory.
+    pub fn occupied_bricks(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// Value of a voxel, 0 outside of the grid.
+    pub fn voxel(&self, x: usize, y: usize, z: usize) -> Float {
+        let [nx, ny, nz] = self.resolution;
+        if x >= nx || y >= ny || z >= nz {
+            return 0.0;
+        }
+        let [bx, by, _] = self.bricks_per_axis;
+        match self.table[x / BRICK + bx * (y / BRICK + by * (z / BRICK))] {
+            EMPTY => 0.0,
+            brick => {
+                let voxel = x % BRICK + BRICK * (y % BRICK + BRICK * (z % BRICK));
+                self.bricks[brick as usize][voxel] as Float
+            }
+        }
+    }
+
+    /// Density at `point` of the unit cube, interpolated between the 8 closest voxel centers and
+    /// held at the value of the outer voxels up to the faces of the cube. 0 outside of it.
+    pub fn density(&self, point: Point3) -> Float {
+        if !(0.0..=1.0).contains(&point.x)
+            || !(0.0..=1.0).contains(&point.y)
+            || !(0.0..=1.0).contains(&point.z)
+        {
+            return 0.0;
+        }
+        // Lower voxel and weight of the upper one along each axis
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut weight = [0.0; 3];
+        for axis in 0..3 {
+            let side = self.resolution[axis];
+            let position = (point[axis] * side as Float - 0.5).clamp(0.0, (side - 1) as Float);
+            lower[axis] = (position as usize).min(side - 1);
+            upper[axis] = (lower[axis] + 1).min(side - 1);
+            weight[axis] = position - lower[axis] as Float;
+        }
+        let mut density = 0.0;
+        for corner in 0..8 {
+            let mut corner_weight = 1.0;
+            let mut voxel = [0; 3];
+            for axis in 0..3 {
+                let is_upper = corner >> axis & 1 == 1;
+                voxel[axis] = if is_upper { upper[axis] } else { lower[axis] };
+                corner_weight *= if is_upper {
+                    weight[axis]
+                } else {
+                    1.0 - weight[axis]
+                };
+            }
+            if corner_weight > 0.0 {
+                density += corner_weight * self.voxel(voxel[0], voxel[1], voxel[2]);
+            }
+        }
+        density
+    }
+}
+
+/// Reads a grid from a file in either encoding.
+pub fn read_grid(path: &Path) -> Result<DensityGrid> {
+    read_grid_file(path).map_err(|source| Error::AssetLoad {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Writes a grid to a file with the sparse encoding, leaving out the empty bricks.
+pub fn write_grid(path: &Path, grid: &DensityGrid) -> Result<()> {
+    write_grid_file(path, grid).map_err(|source| Error::WriteGrid {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_grid_file(path: &Path) -> io::Result<DensityGrid> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a density grid"));
+    }
+
+    let mut resolution = [0; 3];
+    for side in &mut resolution {
+        *side = read_u64(&mut reader)? as usize;
+    }
+    let voxels = resolution
+        .iter()
+        .try_fold(1usize, |product, &side| product.checked_mul(side))
+        .filter(|&voxels| voxels > 0 && voxels <= u32::MAX as usize)
+        .ok_or_else(|| invalid_data("unsupported grid resolution"))?;
+
+    let [encoding] = read_bytes(&mut reader)?;
+    match encoding {
+        0 => {
+            let values = (0..voxels)
+                .map(|_| read_f32(&mut reader))
+                .collect::<io::Result<Vec<_>>>()?;
+            DensityGrid::new(resolution, &values)
+                .ok_or_else(|| invalid_data("negative or infinite density"))
+        }
+        1 => {
+            let mut grid = DensityGrid::empty(resolution);
+            let count = read_u64(&mut reader)?;
+            for _ in 0..count {
+                let mut origin = [0; 3];
+                for (axis, start) in origin.iter_mut().enumerate() {
+                    let brick = read_u64(&mut reader)? as usize;
+                    if brick >= grid.bricks_per_axis[axis] {
+                        return Err(invalid_data("brick outside of the grid"));
+                    }
+                    *start = brick * BRICK;
+                }
+                for voxel in 0..BRICK_VOXELS {
+                    let value = read_f32(&mut reader)?;
+                    let x = origin[0] + voxel % BRICK;
+                    let y = origin[1] + voxel / BRICK % BRICK;
+                    let z = origin[2] + voxel / (BRICK * BRICK);
+                    if x < resolution[0] && y < resolution[1] && z < resolution[2] {
+                        grid.set(x, y, z, value)
+                            .ok_or_else(|| invalid_data("negative or infinite density"))?;
+                    }
+                }
+            }
+            Ok(grid)
+        }
+        _ => Err(invalid_data("unknown grid encoding")),
+    }
+}
+
+fn write_grid_file(path: &Path, grid: &DensityGrid) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    for side in grid.resolution {
+        writer.write_all(&(side as u64).to_le_bytes())?;
+    }
+    writer.write_all(&[1])?;
+    writer.write_all(&(grid.bricks.len() as u64).to_le_bytes())?;
+    let [bx, by, _] = grid.bricks_per_axis;
+    for (index, &brick) in grid.table.iter().enumerate() {
+        if brick == EMPTY {
+            continue;
+        }
+        let position = [index % bx, index / bx % by, index / (bx * by)];
+        for coordinate in position {
+            writer.write_all(&(coordinate as u64).to_le_bytes())?;
+        }
+        for value in grid.bricks[brick as usize] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    writer.flush()
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_le_bytes(read_bytes(reader)?))
+}
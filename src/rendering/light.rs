@@ -0,0 +1,88 @@
+use crate::hittables::{Disk, HitRecord, Hittable, Quad};
+use crate::math::Float;
+use crate::rendering::Material;
+use crate::{Color, Normal3, Point3, Ray, Vec3};
+
+/// Emissive shape that paths sample directly, instead of waiting to hit it by chance.
+#[derive(Clone)]
+pub enum Light {
+    Quad(Quad),
+    Disk(Disk),
+}
+
+/// Direction from a point towards a light, picked by `Light::sample`.
+pub struct LightSample {
+    /// Unit direction towards the light
+    pub direction: Vec3,
+    pub distance: Float,
+    /// Density of picking `direction`, per steradian
+    pub pdf: Float,
+    pub emission: Color,
+}
+
+impl Light {
+    /// Picks a point on the light uniformly by area and returns the direction to it. Lights emit
+    /// from both sides.
+    pub fn sample(&self, from: Point3) -> LightSample {
+        let (point, normal) = match self {
+            Light::Quad(quad) => quad.sample(),
+            Light::Disk(disk) => disk.sample(),
+        };
+        let offset = point - from;
+        let distance = offset.length();
+        let direction = offset / distance;
+        LightSample {
+            direction,
+            distance,
+            pdf: self.area_to_solid_angle(distance, direction, normal),
+            emission: self.emission(),
+        }
+    }
+
+    /// Density of `sample` picking the direction of `ray` from its origin, 0 if the ray misses
+    /// the light or hits it further than `t_max`.
+    pub fn pdf(&self, ray: &Ray, t_max: Float) -> Float {
+        match self.hit(ray, 0.0, t_max) {
+            Some(record) => {
+                let direction = ray.direction.unit_vector();
+                self.area_to_solid_angle(
+                    record.t * ray.direction.length(),
+                    direction,
+                    record.normal,
+                )
+            }
+            None => 0.0,
+        }
+    }
+
+    pub fn emission(&self) -> Color {
+        match self {
+            Light::Quad(quad) => quad.material.emitted(),
+            Light::Disk(disk) => disk.material.emitted(),
+        }
+    }
+
+    fn area(&self) -> Float {
+        match self {
+            Light::Quad(quad) => quad.area(),
+            Light::Disk(disk) => disk.area(),
+        }
+    }
+
+    /// Turns the uniform density over the area into a density over directions, which grows with
+    /// the distance and as the light is seen more edge-on.
+    fn area_to_solid_angle(&self, distance: Float, direction: Vec3, normal: Normal3) -> Float {
+        let cosine = normal.dot(direction).abs();
+        match cosine > 1e-9 {
+            true => distance * distance / (cosine * self.area()),
+            false => 0.0,
+        }
+    }
+
+    fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        match self {
+            Light::Quad(quad) => quad.hit(ray, t_min, t_max),
+            Light::Disk(disk) => disk.hit(ray, t_min, t_max),
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use crate::hittables::HitRecord;
-use crate::math::Vec3;
+use crate::math::{random_scalar, Float, Onb, Point3, Vec3};
+use crate::rendering::bsdf::{Bsdf, Ggx, Lambertian};
+use crate::rendering::{Medium, Texture};
 use crate::{Color, Ray};
 
 pub trait Material {
     fn scatter(&self, ray_in: &Ray, record: &HitRecord) -> Option<ScatteringResult>;
+
+    /// Radiance emitted from the surface, black for everything but lights.
+    fn emitted(&self) -> Color {
+        Color::ZERO
+    }
 }
 
 pub struct ScatteringResult {
@@ -11,25 +18,50 @@ pub struct ScatteringResult {
     pub attenuation: Color,
 }
 
+/// How an `UberShader` interacts with light.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+enum Surface {
+    #[default]
+    Lambertian,
+    Metallic,
+    /// Rough conductor with a GGX microfacet distribution of the given roughness
+    Glossy(Float),
+    /// Glass-like, reflects and refracts with the given index of refraction
+    Dielectric(Float),
+    /// Emits light and absorbs everything that hits it
+    Light,
+    /// Invisible boundary of a volume, rays go straight through
+    Interface,
+    /// Translucent, lets light in and out diffusely and walks it through the medium inside
+    Subsurface,
+}
+
 #[derive(Default, Copy, Clone)]
 pub struct UberShader {
-    albedo: Color,
-    metallic: bool,
-    fuzz: f64,
+    albedo: Texture,
+    surface: Surface,
+    fuzz: Float,
+    emission: Color,
+    /// Medium filling the inside of the closed surface
+    interior: Option<Medium>,
+    /// Coefficient B of Cauchy's equation in square nanometers, how much faster the index of
+    /// refraction of a dielectric grows towards short wavelengths
+    dispersion: Float,
+    id: u32,
 }
 
 impl Material for UberShader {
     fn scatter(&self, ray_in: &Ray, record: &HitRecord) -> Option<ScatteringResult> {
-        match self.metallic {
-            // Metallic
-            true => {
-                let reflected: Vec3 = Vec3::reflect(ray_in.direction.unit_vector(), record.normal);
+        match self.surface {
+            Surface::Metallic => {
+                let reflected: Vec3 =
+                    Vec3::reflect(ray_in.direction.unit_vector(), Vec3::from(record.normal));
                 let scattered = Ray::new(
                     record.point,
                     reflected + self.fuzz * Vec3::random_point_in_unit_sphere(),
                 );
-                let attenuation = self.albedo;
-                if Vec3::dot_product(&scattered.direction, &record.normal) > 0.0_f64 {
+                let attenuation = self.albedo(record.point);
+                if record.normal.dot(scattered.direction) > 0.0 {
                     #[cfg(feature = "debug_prints")]
                     eprintln!("Not absorbed: metallic");
 
@@ -41,32 +73,294 @@ impl Material for UberShader {
                     None
                 }
             }
-            // Lambertian
-            false => {
+            Surface::Lambertian => {
                 let mut scatter_direction: Vec3 =
-                    record.normal + Vec3::random_point_in_unit_vector();
+                    Vec3::from(record.normal) + Vec3::random_point_in_unit_vector();
 
                 if scatter_direction.near_zero() {
-                    scatter_direction = record.normal;
+                    scatter_direction = Vec3::from(record.normal);
                 }
                 #[cfg(feature = "debug_prints")]
                 eprintln!("Lambertian");
 
                 Some(ScatteringResult {
                     ray: Ray::new(record.point, scatter_direction),
-                    attenuation: self.albedo,
+                    attenuation: self.albedo(record.point),
+                })
+            }
+            Surface::Glossy(roughness) => {
+                let bsdf = Ggx::from_roughness(self.albedo(record.point), roughness);
+                let frame = Onb::from_w(Vec3::from(record.normal));
+                let wo = frame.to_local(-ray_in.direction.unit_vector());
+                let wi = bsdf.sample(wo)?;
+                let pdf = bsdf.pdf(wo, wi);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(ScatteringResult {
+                    ray: Ray::new(record.point, frame.to_world(wi)),
+                    attenuation: bsdf.eval(wo, wi) * wi.z / pdf,
+                })
+            }
+            Surface::Dielectric(refraction_index) => self.refract(ray_in, record, refraction_index),
+            Surface::Light => None,
+            Surface::Interface => Some(ScatteringResult {
+                ray: Ray::new(record.point, ray_in.direction),
+                attenuation: Color::ONE,
+            }),
+            Surface::Subsurface => {
+                // Through the surface either way, the normal faces the side the ray comes from
+                let frame = Onb::from_w(-Vec3::from(record.normal));
+                Some(ScatteringResult {
+                    ray: Ray::new(
+                        record.point,
+                        frame.to_world(Vec3::random_cosine_direction()),
+                    ),
+                    attenuation: Color::ONE,
                 })
             }
         }
     }
+
+    fn emitted(&self) -> Color {
+        self.emission
+    }
+}
+
+/// Wavelengths of the Fraunhofer F, d and C lines in nanometers, which Abbe numbers are measured
+/// at
+const FRAUNHOFER_F: Float = 486.13;
+const FRAUNHOFER_D: Float = 587.56;
+const FRAUNHOFER_C: Float = 656.27;
+
+/// Schlick's approximation of the Fresnel reflectance.
+fn reflectance(cosine: Float, ratio: Float) -> Float {
+    let r0 = ((1.0 - ratio) / (1.0 + ratio)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
 
 impl UberShader {
-    pub fn new(albedo: Color, metallic: bool, fuzz: f64) -> UberShader {
+    pub fn new(albedo: Color, metallic: bool, fuzz: Float) -> UberShader {
         UberShader {
-            albedo,
-            metallic,
+            albedo: Texture::Solid(albedo),
+            surface: match metallic {
+                true => Surface::Metallic,
+                false => Surface::Lambertian,
+            },
             fuzz,
+            ..UberShader::default()
+        }
+    }
+
+    /// Rough metal, `reflectance` is the color at normal incidence. A roughness of 0 is a mirror,
+    /// 1 is close to diffuse.
+    pub fn glossy(reflectance: Color, roughness: Float) -> UberShader {
+        UberShader {
+            albedo: Texture::Solid(reflectance),
+            surface: Surface::Glossy(roughness),
+            ..UberShader::default()
         }
     }
+
+    /// Clear glass-like material, 1.5 is a typical index of refraction for glass.
+    pub fn dielectric(refraction_index: Float) -> UberShader {
+        UberShader {
+            albedo: Texture::Solid(Color::ONE),
+            surface: Surface::Dielectric(refraction_index),
+            ..UberShader::default()
+        }
+    }
+
+    /// Area light emitting `emission` from every point of the surface.
+    pub fn light(emission: Color) -> UberShader {
+        UberShader {
+            surface: Surface::Light,
+            emission,
+            ..UberShader::default()
+        }
+    }
+
+    /// Lambertian material with a spatially varying albedo.
+    pub fn textured(texture: Texture) -> UberShader {
+        UberShader {
+            albedo: texture,
+            ..UberShader::default()
+        }
+    }
+
+    /// Translucent material like skin, wax or marble, for closed surfaces. Light goes in and
+    /// scatters inside until it comes out somewhere else, a random walk through a medium whose
+    /// scattering makes a thick slab reflect about `albedo`. `mean_free_path` is the average
+    /// distance between interactions in each channel and needs to be positive, larger ones let
+    /// the channel bleed further.
+    pub fn subsurface(albedo: Color, mean_free_path: Color) -> UberShader {
+        let extinction = Color::new(
+            1.0 / mean_free_path.x,
+            1.0 / mean_free_path.y,
+            1.0 / mean_free_path.z,
+        );
+        let single_scattering = Color::new(
+            single_scattering_albedo(albedo.x),
+            single_scattering_albedo(albedo.y),
+            single_scattering_albedo(albedo.z),
+        );
+        UberShader {
+            albedo: Texture::Solid(albedo),
+            surface: Surface::Subsurface,
+            interior: Some(Medium::new(
+                extinction * (Color::ONE - single_scattering),
+                extinction * single_scattering,
+            )),
+            ..UberShader::default()
+        }
+    }
+
+    /// Surface a volume has in place of its boundary, which only marks where its medium starts.
+    pub(crate) fn interface(medium: Medium) -> UberShader {
+        UberShader {
+            surface: Surface::Interface,
+            interior: Some(medium),
+            ..UberShader::default()
+        }
+    }
+
+    /// Spreads the index of refraction of a dielectric over the wavelengths, for the rainbow
+    /// fringes of prisms and gems in spectral mode. The Abbe number is about 60 for crown glass,
+    /// 30 for flint glass and 55 for diamond, lower numbers disperse more. Other materials and RGB
+    /// renders ignore it.
+    pub fn with_abbe_number(mut self, abbe_number: Float) -> UberShader {
+        if let Surface::Dielectric(refraction_index) = self.surface {
+            let spread = 1.0 / (FRAUNHOFER_F * FRAUNHOFER_F) - 1.0 / (FRAUNHOFER_C * FRAUNHOFER_C);
+            self.dispersion = (refraction_index - 1.0) / (abbe_number * spread);
+        }
+        self
+    }
+
+    /// Whether the index of refraction depends on the wavelength.
+    pub fn is_dispersive(&self) -> bool {
+        matches!(self.surface, Surface::Dielectric(_)) && self.dispersion != 0.0
+    }
+
+    /// Index of refraction of a dielectric at `wavelength` in nanometers, `None` for other
+    /// materials. The index given to `dielectric` is the one at the Fraunhofer d line, 587.56 nm.
+    pub fn refraction_index(&self, wavelength: Float) -> Option<Float> {
+        match self.surface {
+            Surface::Dielectric(refraction_index) => Some(
+                refraction_index
+                    + self.dispersion
+                        * (1.0 / (wavelength * wavelength) - 1.0 / (FRAUNHOFER_D * FRAUNHOFER_D)),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Scatters like `scatter` for light of a single wavelength, which only makes a difference
+    /// for dispersive dielectrics.
+    pub(crate) fn scatter_at(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        wavelength: Float,
+    ) -> Option<ScatteringResult> {
+        match self.refraction_index(wavelength) {
+            Some(refraction_index) => self.refract(ray_in, record, refraction_index),
+            None => self.scatter(ray_in, record),
+        }
+    }
+
+    /// Reflects or refracts through a dielectric, picked by the Fresnel reflectance.
+    fn refract(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        refraction_index: Float,
+    ) -> Option<ScatteringResult> {
+        let ratio = match record.front_face {
+            true => 1.0 / refraction_index,
+            false => refraction_index,
+        };
+        let unit_direction = ray_in.direction.unit_vector();
+        let cos_theta = record.normal.dot(-unit_direction).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let direction =
+            if ratio * sin_theta > 1.0 || reflectance(cos_theta, ratio) > random_scalar() {
+                Vec3::reflect(unit_direction, Vec3::from(record.normal))
+            } else {
+                Vec3::refract(unit_direction, Vec3::from(record.normal), ratio)
+            };
+        Some(ScatteringResult {
+            ray: Ray::new(record.point, direction),
+            attenuation: self.albedo(record.point),
+        })
+    }
+
+    /// Fills the inside of a closed surface with `medium`, like murky water in glass. The
+    /// surfaces of a volume don't nest, a ray entering one leaves every other.
+    pub fn with_interior(mut self, medium: Medium) -> UberShader {
+        self.interior = Some(medium);
+        self
+    }
+
+    pub fn interior(&self) -> Option<&Medium> {
+        self.interior.as_ref()
+    }
+
+    /// Sets the ID written to the material ID AOV.
+    pub fn with_id(mut self, id: u32) -> UberShader {
+        self.id = id;
+        self
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Albedo at a point of the surface.
+    pub fn albedo(&self, point: Point3) -> Color {
+        self.albedo.value(point)
+    }
+
+    /// BSDF of a diffuse surface, `None` for the other kinds, which scatter in too narrow a lobe
+    /// for light sampling to help.
+    pub fn diffuse(&self, point: Point3) -> Option<Lambertian> {
+        match self.surface {
+            Surface::Lambertian => Some(Lambertian {
+                albedo: self.albedo(point),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the material scatters specularly rather than diffusely.
+    pub fn is_specular(&self) -> bool {
+        matches!(
+            self.surface,
+            Surface::Metallic | Surface::Glossy(_) | Surface::Dielectric(_)
+        )
+    }
+
+    pub fn is_light(&self) -> bool {
+        self.surface == Surface::Light
+    }
+
+    /// Whether the surface is only the boundary of a volume, which rays pass without a bounce.
+    pub fn is_interface(&self) -> bool {
+        self.surface == Surface::Interface
+    }
+
+    /// Whether light goes into the surface and walks through the medium inside.
+    pub fn is_subsurface(&self) -> bool {
+        self.surface == Surface::Subsurface
+    }
+}
+
+/// Albedo of single scattering that makes a random walk reflect `albedo` of the light, from the
+/// fit of Chiang et al., "Practical and Controllable Subsurface Scattering for Production Path
+/// Tracing".
+fn single_scattering_albedo(albedo: Float) -> Float {
+    let albedo = albedo.clamp(0.0, 1.0);
+    let root = 4.09712 + 4.20863 * albedo
+        - (9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo).sqrt();
+    1.0 - root * root
 }
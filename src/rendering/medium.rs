@@ -0,0 +1,354 @@
+use std::sync::Arc;
+
+use crate::math::{random_scalar, Aabb, Color, Float, Matrix4, Onb, Point3, Ray, Vec3, PI};
+use crate::rendering::{DensityGrid, SampledWavelengths};
+
+/// Henyey-Greenstein phase function, the distribution of directions light scatters to in a
+/// medium. Like a BSDF, `wo` points towards the viewer and `wi` towards the light.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HenyeyGreenstein {
+    /// Average cosine of the scattering angle: positive scatters forward, negative back, 0 is
+    /// isotropic
+    pub asymmetry: Float,
+}
+
+impl HenyeyGreenstein {
+    /// Density of scattering from `wi` to `wo` per steradian, also the density of `sample`.
+    pub fn eval(&self, wo: Vec3, wi: Vec3) -> Float {
+        let g = self.asymmetry;
+        // Light travelling along -wi goes on along wo, the angle between the two is scattering
+        let cos_theta = -Vec3::dot_product(&wo, &wi);
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Picks an incoming direction for `wo` with the density `eval`.
+    pub fn sample(&self, wo: Vec3) -> Vec3 {
+        let g = self.asymmetry;
+        let cos_theta = match g.abs() < 1e-3 {
+            true => 1.0 - 2.0 * random_scalar(),
+            false => {
+                let ratio = (1.0 - g * g) / (1.0 - g + 2.0 * g * random_scalar());
+                ((1.0 + g * g - ratio * ratio) / (2.0 * g)).clamp(-1.0, 1.0)
+            }
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * random_scalar();
+        // Measured from the direction the light keeps going, which is -wi
+        let frame = Onb::from_w(wo);
+        -frame.to_world(Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+/// Participating medium of constant density, like smoke, fog or murky water. The coefficients
+/// are per unit of length and per color channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Medium {
+    pub(crate) absorption: Color,
+    pub(crate) scattering: Color,
+    pub(crate) phase: HenyeyGreenstein,
+}
+
+/// Outcome of following a ray through a medium.
+pub(crate) enum FreeFlight {
+    /// The ray scattered at `t`
+    Scattered { t: Float, weight: Color },
+    /// The ray got through to the end of the medium
+    Passed { weight: Color },
+}
+
+impl Medium {
+    /// Medium that scatters the same amount in every direction.
+    pub fn new(absorption: Color, scattering: Color) -> Medium {
+        Medium {
+            absorption,
+            scattering,
+            phase: HenyeyGreenstein::default(),
+        }
+    }
+
+    /// Medium with `density` interactions per unit of length, of which the fraction `albedo`
+    /// scatters and the rest is absorbed.
+    pub fn fog(density: Float, albedo: Color) -> Medium {
+        Medium::new(density * (Color::ONE - albedo), density * albedo)
+    }
+
+    /// Scatters forward for a positive `asymmetry` up to 1, backward for a negative one.
+    pub fn with_asymmetry(mut self, asymmetry: Float) -> Medium {
+        self.phase = HenyeyGreenstein { asymmetry };
+        self
+    }
+
+    pub fn phase(&self) -> HenyeyGreenstein {
+        self.phase
+    }
+
+    /// Interactions of either kind per unit of length.
+    pub fn extinction(&self) -> Color {
+        self.absorption + self.scattering
+    }
+
+    /// Fraction of the light that scatters at an interaction.
+    pub fn albedo(&self) -> Color {
+        let extinction = self.extinction();
+        let ratio = |scattering: Float, extinction: Float| match extinction > 0.0 {
+            true => scattering / extinction,
+            false => 0.0,
+        };
+        Color::new(
+            ratio(self.scattering.x, extinction.x),
+            ratio(self.scattering.y, extinction.y),
+            ratio(self.scattering.z, extinction.z),
+        )
+    }
+
+    /// The medium at the wavelengths of a spectral path, with its coefficients upsampled from
+    /// their colors.
+    pub(crate) fn at_wavelengths(&self, wavelengths: &SampledWavelengths) -> Medium {
+        Medium {
+            absorption: wavelengths.unbounded(self.absorption),
+            scattering: wavelengths.unbounded(self.scattering),
+            phase: self.phase,
+        }
+    }
+
+    /// Fraction of the light that gets through `distance` without an interaction.
+    pub fn transmittance(&self, distance: Float) -> Color {
+        let extinction = self.extinction();
+        Color::new(
+            (-extinction.x * distance).exp(),
+            (-extinction.y * distance).exp(),
+            (-extinction.z * distance).exp(),
+        )
+    }
+
+    /// Samples where the ray first interacts with the medium between `t_min` and `t_max`, with
+    /// the coefficients scaled by `density` if there is one. Absorption is left to the weight,
+    /// so only scattering stops the ray.
+    pub(crate) fn sample_distance(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        density: Option<&DensityField>,
+    ) -> FreeFlight {
+        match density {
+            Some(density) => self.delta_tracking(ray, t_min, t_max, density),
+            None => self.sample_homogeneous(ray, t_min, t_max),
+        }
+    }
+
+    /// Fraction of the light that gets from `t_min` to `t_max` along the ray, with the
+    /// coefficients scaled by `density` if there is one. Heterogeneous media give an unbiased
+    /// estimate.
+    pub(crate) fn transmittance_along(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        density: Option<&DensityField>,
+    ) -> Color {
+        match density {
+            Some(density) => self.ratio_tracking(ray, t_min, t_max, density),
+            None => self.transmittance((t_max - t_min) * ray.direction.length()),
+        }
+    }
+
+    /// Distance drawn for one channel picked at random, with the channels combined by the
+    /// average of their densities.
+    fn sample_homogeneous(&self, ray: &Ray, t_min: Float, t_max: Float) -> FreeFlight {
+        let extinction = self.extinction();
+        if extinction == Color::ZERO {
+            return FreeFlight::Passed { weight: Color::ONE };
+        }
+        let length = ray.direction.length();
+        let channel = ((random_scalar() * 3.0) as usize).min(2);
+        let distance = -(1.0 - random_scalar()).ln() / extinction[channel];
+        let t = t_min + distance / length;
+        if t < t_max {
+            let transmittance = self.transmittance(distance);
+            let pdf = average(extinction * transmittance);
+            return FreeFlight::Scattered {
+                t,
+                weight: self.scattering * transmittance / pdf,
+            };
+        }
+        let transmittance = self.transmittance((t_max - t_min) * length);
+        let pdf = average(transmittance);
+        FreeFlight::Passed {
+            weight: match pdf > 0.0 {
+                true => transmittance / pdf,
+                false => Color::ZERO,
+            },
+        }
+    }
+
+    /// Greatest extinction of any channel anywhere in `density`, which bounds the medium as if
+    /// it was made denser with particles that do nothing.
+    fn majorant(&self, density: &DensityField) -> Float {
+        let extinction = self.extinction();
+        density.max() * extinction.x.max(extinction.y).max(extinction.z)
+    }
+
+    /// Delta tracking: steps through the homogeneous medium of the majorant, and at each point
+    /// either scatters or goes on through the null particles, picked by the average of their
+    /// densities and weighted per channel.
+    fn delta_tracking(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        density: &DensityField,
+    ) -> FreeFlight {
+        let majorant = self.majorant(density);
+        if majorant <= 0.0 {
+            return FreeFlight::Passed { weight: Color::ONE };
+        }
+        let step = majorant * ray.direction.length();
+        let mut weight = Color::ONE;
+        let mut t = t_min;
+        loop {
+            t -= (1.0 - random_scalar()).ln() / step;
+            if t >= t_max {
+                return FreeFlight::Passed { weight };
+            }
+            let value = density.density(ray.at(t));
+            let scattering = value * self.scattering;
+            let null = Color::from_float(majorant) - value * self.extinction();
+            let (scatter_pdf, null_pdf) = (average(scattering), average(null));
+            let total = scatter_pdf + null_pdf;
+            if total <= 0.0 {
+                // Everything is absorbed here
+                return FreeFlight::Passed {
+                    weight: Color::ZERO,
+                };
+            }
+            if random_scalar() * total < scatter_pdf {
+                weight *= scattering * (total / (majorant * scatter_pdf));
+                return FreeFlight::Scattered { t, weight };
+            }
+            weight *= null * (total / (majorant * null_pdf));
+        }
+    }
+
+    /// Ratio tracking: steps through the homogeneous medium of the majorant, keeping the
+    /// fraction of null particles at each point.
+    fn ratio_tracking(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        density: &DensityField,
+    ) -> Color {
+        let majorant = self.majorant(density);
+        if majorant <= 0.0 {
+            return Color::ONE;
+        }
+        let step = majorant * ray.direction.length();
+        let mut transmittance = Color::ONE;
+        let mut t = t_min;
+        loop {
+            t -= (1.0 - random_scalar()).ln() / step;
+            if t >= t_max || transmittance == Color::ZERO {
+                return transmittance;
+            }
+            let value = density.density(ray.at(t));
+            transmittance *= Color::ONE - value * self.extinction() / majorant;
+        }
+    }
+}
+
+fn average(color: Color) -> Float {
+    (color.x + color.y + color.z) / 3.0
+}
+
+/// Density that scales the coefficients of a medium, from a grid stretched over a box.
+#[derive(Debug, Clone)]
+pub struct DensityField {
+    grid: Arc<DensityGrid>,
+    /// From the world to the unit cube of the grid
+    to_grid: Matrix4,
+}
+
+impl DensityField {
+    /// Corners may be given in any order.
+    pub fn new(grid: Arc<DensityGrid>, a: Point3, b: Point3) -> DensityField {
+        let bounds = Aabb::new(a, b);
+        let extent = bounds.max - bounds.min;
+        let scale = Vec3::new(1.0 / extent.x, 1.0 / extent.y, 1.0 / extent.z);
+        DensityField {
+            grid,
+            to_grid: Matrix4::scaling(scale) * Matrix4::translation(Point3::ORIGIN - bounds.min),
+        }
+    }
+
+    pub fn grid(&self) -> &DensityGrid {
+        &self.grid
+    }
+
+    /// Largest density anywhere.
+    pub fn max(&self) -> Float {
+        self.grid.max()
+    }
+
+    /// Density at a point of the world, 0 outside of the box.
+    pub fn density(&self, point: Point3) -> Float {
+        self.grid.density(self.to_grid.transform_point(point))
+    }
+
+    /// The same field for points given in another space, `to_space` being the transform from
+    /// that space to the one of this field.
+    pub(crate) fn transformed(&self, to_space: &Matrix4) -> DensityField {
+        DensityField {
+            grid: self.grid.clone(),
+            to_grid: self.to_grid * *to_space,
+        }
+    }
+}
+
+/// Medium filling the space outside of volumes up to `radius` from the origin, like haze or a
+/// smoke-filled room. Rays that leave it reach the background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub(crate) medium: Medium,
+    pub(crate) radius: Float,
+}
+
+impl Fog {
+    pub fn new(medium: Medium, radius: Float) -> Fog {
+        Fog { medium, radius }
+    }
+
+    pub fn medium(&self) -> &Medium {
+        &self.medium
+    }
+
+    /// The fog at the wavelengths of a spectral path.
+    pub(crate) fn at_wavelengths(&self, wavelengths: &SampledWavelengths) -> Fog {
+        Fog {
+            medium: self.medium.at_wavelengths(wavelengths),
+            radius: self.radius,
+        }
+    }
+
+    /// Part of the ray from `t_min` to `t_max` inside the fog.
+    pub(crate) fn interval(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<(Float, Float)> {
+        // Fog is centered on the origin
+        let origin = ray.origin - Point3::ORIGIN;
+        let a = ray.direction.squared_length();
+        let half_b = Vec3::dot_product(&origin, &ray.direction);
+        let c = origin.squared_length() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let sqrt_discriminant = discriminant.sqrt();
+        let start = ((-half_b - sqrt_discriminant) / a).max(t_min);
+        let end = ((-half_b + sqrt_discriminant) / a).min(t_max);
+        (start < end).then_some((start, end))
+    }
+}
@@ -1,8 +1,26 @@
-#![allow(dead_code)]
-
 pub use camera::Camera;
+pub use denoiser::{denoise, DenoiseSettings, Denoiser};
+pub use film::{AovPixel, Film, FilmPixel};
+pub use grid::{read_grid, write_grid, DensityGrid};
+pub use light::{Light, LightSample};
 pub use material::{Material, ScatteringResult, UberShader};
+pub use medium::{DensityField, Fog, HenyeyGreenstein, Medium};
+pub use renderer::{render, render_progressive, RenderSettings, RenderStats, TileStats};
+pub use sampling::Background;
+pub use spectrum::{cie_xyz, xyz_to_rgb, SampledWavelengths};
+pub use texture::Texture;
+pub use tiles::{generate_tiles, Tile, TileOrder};
 
+pub mod bsdf;
 mod camera;
+mod denoiser;
+mod film;
+mod grid;
+mod light;
 mod material;
-pub(crate) mod sampling;
+mod medium;
+mod renderer;
+pub mod sampling;
+mod spectrum;
+mod texture;
+mod tiles;
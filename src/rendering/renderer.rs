@@ -0,0 +1,333 @@
+use std::array;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::hittables::Hittable;
+use crate::math::{
+    self, random_scalar, restore_rng, sample_seed, save_rng, seed_rng, Float, Ray, RayPacket, LANES,
+};
+use crate::rendering::film::{AovPixel, Film, FilmPixel};
+use crate::rendering::sampling::{self, PathSample};
+use crate::rendering::tiles::{generate_tiles, Tile, TileOrder};
+use crate::rendering::SampledWavelengths;
+use crate::scene::Scene;
+
+/// Image size, sampling and scheduling parameters of a render.
+pub struct RenderSettings {
+    pub image_width: usize,
+    pub image_height: usize,
+    pub samples_per_pixel: i32,
+    /// Samples added to every pixel before `render_progressive` reports back, 0 adds all of them
+    /// in a single pass
+    pub samples_per_pass: i32,
+    pub max_depth: i32,
+    /// Base seed of the per-sample random number generators
+    pub seed: u64,
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    /// Trace wavelengths instead of RGB, for dispersion and smoother color mixing
+    pub spectral: bool,
+    /// Find the first hits of the samples of a pixel with the camera rays in a packet of
+    /// `math::LANES`, which gives the same image. Slower than single rays in all but the
+    /// `instances` scene, see the README
+    pub packets: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            image_width: 1920,
+            image_height: 1080,
+            samples_per_pixel: 50,
+            samples_per_pass: 0,
+            max_depth: 20,
+            seed: 0,
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            spectral: false,
+            packets: false,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Checks the settings describe an image that can be rendered.
+    pub fn validate(&self) -> Result<()> {
+        if self.image_width == 0 || self.image_height == 0 {
+            return Err(Error::InvalidSettings(format!(
+                "the image is {}x{} pixels, both sides need at least one",
+                self.image_width, self.image_height
+            )));
+        }
+        if self.samples_per_pixel <= 0 {
+            return Err(Error::InvalidSettings(format!(
+                "{} samples per pixel, at least one is needed",
+                self.samples_per_pixel
+            )));
+        }
+        if self.samples_per_pass < 0 {
+            return Err(Error::InvalidSettings(format!(
+                "{} samples per pass can't be negative",
+                self.samples_per_pass
+            )));
+        }
+        if self.max_depth < 0 {
+            return Err(Error::InvalidSettings(format!(
+                "a maximum depth of {} can't be negative",
+                self.max_depth
+            )));
+        }
+        if self.tile_size == 0 {
+            return Err(Error::InvalidSettings(
+                "tiles need to be at least one pixel wide".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rays traced in one tile and how long it took.
+pub struct TileStats {
+    pub tile: Tile,
+    pub rays: u64,
+    pub elapsed: Duration,
+}
+
+impl TileStats {
+    pub fn rays_per_second(&self) -> f64 {
+        self.rays as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Totals of a `render_progressive` call.
+pub struct RenderStats {
+    pub tiles: usize,
+    pub rays: u64,
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    pub fn rays_per_second(&self) -> f64 {
+        self.rays as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Renders the scene from scratch in a single call.
+pub fn render(settings: &RenderSettings, scene: &Scene) -> Result<Film> {
+    let mut film = Film::new(settings.image_width, settings.image_height);
+    render_progressive(settings, scene, &mut film, |_| {}, |_| {})?;
+    Ok(film)
+}
+
+/// Adds samples to `film` until every pixel has `settings.samples_per_pixel` of them, calling
+/// `on_tile` from the worker threads whenever a tile is done and `on_pass` after every
+/// `settings.samples_per_pass` samples. Samples already in the film are kept, so a film restored
+/// from a checkpoint continues where it left off. Fails without rendering anything if the
+/// settings are invalid or don't match the size of the film.
+pub fn render_progressive<T, F>(
+    settings: &RenderSettings,
+    scene: &Scene,
+    film: &mut Film,
+    on_tile: T,
+    mut on_pass: F,
+) -> Result<RenderStats>
+where
+    T: Fn(&TileStats) + Sync,
+    F: FnMut(&Film),
+{
+    settings.validate()?;
+    if film.width() != settings.image_width || film.height() != settings.image_height {
+        return Err(Error::InvalidSettings(format!(
+            "the film is {}x{}, but the settings ask for a {}x{} image",
+            film.width(),
+            film.height(),
+            settings.image_width,
+            settings.image_height
+        )));
+    }
+
+    let tiles = generate_tiles(
+        settings.image_width,
+        settings.image_height,
+        settings.tile_size,
+        settings.tile_order,
+    );
+    let samples_per_pixel = settings.samples_per_pixel as u32;
+    let samples_per_pass = match settings.samples_per_pass {
+        0 => samples_per_pixel,
+        samples => samples as u32,
+    };
+    let start = Instant::now();
+    let mut total_rays = 0;
+
+    let mut completed = film.min_samples();
+    while completed < samples_per_pixel {
+        let target = (completed + samples_per_pass).min(samples_per_pixel);
+        total_rays += render_pass(settings, scene, &tiles, film, target, &on_tile);
+        completed = target;
+        on_pass(film);
+    }
+
+    Ok(RenderStats {
+        tiles: tiles.len(),
+        rays: total_rays,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Renders every tile up to `target` samples per pixel. Worker threads pull tiles from a shared
+/// queue in the order given by `settings.tile_order`, so neighbouring tiles are traced at roughly
+/// the same time.
+fn render_pass(
+    settings: &RenderSettings,
+    scene: &Scene,
+    tiles: &[Tile],
+    film: &mut Film,
+    target: u32,
+    on_tile: &(impl Fn(&TileStats) + Sync),
+) -> u64 {
+    let film = Mutex::new(film);
+    let next_tile = AtomicUsize::new(0);
+    let total_rays = AtomicU64::new(0);
+
+    rayon::broadcast(|_| {
+        let mut pixels = Vec::with_capacity(settings.tile_size * settings.tile_size);
+        let mut aovs = Vec::new();
+        loop {
+            let index = next_tile.fetch_add(1, Ordering::Relaxed);
+            let Some(tile) = tiles.get(index) else {
+                break;
+            };
+
+            film.lock().unwrap().read_tile(tile, &mut pixels, &mut aovs);
+            let stats = render_tile(settings, scene, tile, &mut pixels, &mut aovs, target);
+            film.lock().unwrap().write_tile(tile, &pixels, &aovs);
+
+            total_rays.fetch_add(stats.rays, Ordering::Relaxed);
+            on_tile(&stats);
+        }
+    });
+
+    total_rays.into_inner()
+}
+
+fn render_tile(
+    settings: &RenderSettings,
+    scene: &Scene,
+    tile: &Tile,
+    pixels: &mut [FilmPixel],
+    aovs: &mut [AovPixel],
+    target: u32,
+) -> TileStats {
+    let start = Instant::now();
+    let mut rays: u64 = 0;
+
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let x = tile.x + index % tile.width;
+        let y = tile.y + index / tile.width;
+        let first = pixel.samples;
+        let mut add = |sample: PathSample| {
+            pixel.add_sample(sample.color);
+            if let Some(aov) = aovs.get_mut(index) {
+                aov.add_sample(&sample);
+            }
+            rays += sample.rays as u64;
+        };
+
+        let mut next = first;
+        // The camera ray isn't traced without a bounce
+        if settings.packets && settings.max_depth > 0 {
+            while next + LANES as u32 <= target {
+                trace_packet(settings, scene, x, y, next, &mut add);
+                next += LANES as u32;
+            }
+        }
+        for s in next..target {
+            let (ray, wavelengths) = camera_ray(settings, scene, x, y, s);
+            add(match wavelengths {
+                Some(wavelengths) => sampling::trace_spectral_path(
+                    ray,
+                    &scene.world,
+                    &scene.lights,
+                    &scene.background,
+                    scene.fog.as_ref(),
+                    &wavelengths,
+                    settings.max_depth,
+                ),
+                None => sampling::trace_path(
+                    ray,
+                    &scene.world,
+                    &scene.lights,
+                    &scene.background,
+                    scene.fog.as_ref(),
+                    settings.max_depth,
+                ),
+            });
+        }
+    }
+
+    TileStats {
+        tile: *tile,
+        rays,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Seeds the random number generator for sample `s` of pixel (`x`, `y`) and draws its camera ray,
+/// and its wavelengths in a spectral render.
+fn camera_ray(
+    settings: &RenderSettings,
+    scene: &Scene,
+    x: usize,
+    y: usize,
+    s: u32,
+) -> (Ray, Option<SampledWavelengths>) {
+    let pixel_index = (y * settings.image_width + x) as u64;
+    seed_rng(sample_seed(settings.seed, pixel_index, s as u64));
+    // Film rows go top to bottom, camera v goes bottom to top
+    let row = (settings.image_height - 1 - y) as Float;
+    let u = (x as Float + random_scalar()) / settings.image_width as Float;
+    let v = (row + random_scalar()) / settings.image_height as Float;
+    let ray = scene.camera.get_ray(u, v);
+    (ray, settings.spectral.then(SampledWavelengths::sample))
+}
+
+/// Traces `LANES` samples of pixel (`x`, `y`) from sample `first` on and passes them to `add` in
+/// order. Their camera rays go through the scene together in a packet, and every path goes on from
+/// its first hit on its own. The random numbers are drawn in the same order as for one sample
+/// after the other, so the samples are the same.
+fn trace_packet<F: FnMut(PathSample)>(
+    settings: &RenderSettings,
+    scene: &Scene,
+    x: usize,
+    y: usize,
+    first: u32,
+    add: &mut F,
+) {
+    let cameras: [_; LANES] = array::from_fn(|lane| {
+        let (ray, wavelengths) = camera_ray(settings, scene, x, y, first + lane as u32);
+        (ray, wavelengths, save_rng())
+    });
+    let packet = RayPacket::new(cameras.each_ref().map(|(ray, _, _)| *ray));
+    let mut t_max = [math::INFINITY; LANES];
+    let mut hits = array::from_fn(|_| None);
+    scene
+        .world
+        .hit_packet(&packet, sampling::T_MIN, &mut t_max, &mut hits);
+
+    for ((ray, wavelengths, rng), hit) in cameras.into_iter().zip(hits) {
+        restore_rng(rng);
+        add(sampling::trace_from_hit(
+            ray,
+            hit,
+            &scene.world,
+            &scene.lights,
+            &scene.background,
+            scene.fog.as_ref(),
+            wavelengths.as_ref(),
+            settings.max_depth,
+        ));
+    }
+}
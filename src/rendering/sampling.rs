@@ -1,49 +1,589 @@
-use crate::rendering::Material;
-use crate::{math, Color, Hittable, HittableList, Point3, Ray, Vec3};
+use crate::hittables::HitRecord;
+use crate::math::{random_scalar, Float, Normal3, Onb};
+use crate::rendering::bsdf::{Bsdf, Lambertian};
+use crate::rendering::medium::FreeFlight;
+use crate::rendering::{
+    DensityField, Fog, Light, LightSample, Material, Medium, SampledWavelengths,
+};
+use crate::{math, Color, Hittable, Point3, Ray, Vec3};
 
-pub fn ray_color<T: Hittable>(mut ray: Ray, world: &HittableList<T>, mut depth: i32) -> Color {
-    let mut color: Color = Color::ONE;
+pub fn ray_color<W: Hittable>(
+    ray: Ray,
+    world: &W,
+    lights: &[Light],
+    background: &Background,
+    fog: Option<&Fog>,
+    depth: i32,
+) -> Color {
+    trace_path(ray, world, lights, background, fog, depth).color
+}
 
-    loop {
-        if depth <= 0 {
-            color = Color::ZERO;
-            break;
+/// Radiance arriving from outside the scene.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Background {
+    /// Vertical gradient from white to light blue
+    #[default]
+    Sky,
+    /// The same radiance from every direction
+    Uniform(Color),
+}
+
+impl Background {
+    pub fn radiance(&self, ray: &Ray) -> Color {
+        match self {
+            Background::Sky => sky_color(ray),
+            Background::Uniform(color) => *color,
+        }
+    }
+}
+
+/// Result of tracing a single camera ray through the scene.
+pub struct PathSample {
+    pub color: Color,
+    /// Number of rays intersected against the world, including the camera ray
+    pub rays: u32,
+    /// Albedo of the first surface, or the background color if the camera ray escaped
+    pub albedo: Color,
+    /// First surface hit by the camera ray
+    pub first_hit: Option<FirstHit>,
+    /// `color` split up by the kind of path that carried it
+    pub components: PathComponents,
+}
+
+pub struct FirstHit {
+    pub position: Point3,
+    pub normal: Normal3,
+    /// Distance from the camera
+    pub depth: Float,
+    pub object_id: u32,
+    pub material_id: u32,
+}
+
+/// Which lobe the first bounce of a path sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lobe {
+    Diffuse,
+    Specular,
+}
+
+/// Radiance arriving at the camera, grouped by the first bounce. Direct light reached a light
+/// source or the sky right after the first bounce, indirect light bounced more than once.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathComponents {
+    /// Light sources and the sky seen directly by the camera
+    pub emission: Color,
+    pub diffuse_direct: Color,
+    pub diffuse_indirect: Color,
+    pub specular_direct: Color,
+    pub specular_indirect: Color,
+}
+
+impl PathComponents {
+    fn add(&mut self, first_lobe: Option<Lobe>, bounces: u32, radiance: Color) {
+        match (first_lobe, bounces) {
+            (None, _) => self.emission += radiance,
+            (Some(Lobe::Diffuse), 1) => self.diffuse_direct += radiance,
+            (Some(Lobe::Diffuse), _) => self.diffuse_indirect += radiance,
+            (Some(Lobe::Specular), 1) => self.specular_direct += radiance,
+            (Some(Lobe::Specular), _) => self.specular_indirect += radiance,
         }
+    }
+}
+
+/// Follows a path from the camera until it escapes, is absorbed or reaches `depth` bounces.
+/// Diffuse surfaces and points where the path scatters in a medium also sample one of `lights`
+/// directly, and the two ways of reaching a light are weighted with multiple importance
+/// sampling. In volumes and the fog, the distance to the next scattering is sampled before
+/// every surface.
+pub fn trace_path<W: Hittable>(
+    ray: Ray,
+    world: &W,
+    lights: &[Light],
+    background: &Background,
+    fog: Option<&Fog>,
+    depth: i32,
+) -> PathSample {
+    trace(ray, None, world, lights, background, fog, None, depth)
+}
+
+/// Follows a path like `trace_path` that carries `wavelengths` instead of RGB, with the colors of
+/// the scene upsampled to spectra. Dispersive dielectrics bend every wavelength their own way, so
+/// only the hero wavelength goes on through them. The radiance, albedo and components of the
+/// sample are converted back to linear sRGB.
+pub fn trace_spectral_path<W: Hittable>(
+    ray: Ray,
+    world: &W,
+    lights: &[Light],
+    background: &Background,
+    fog: Option<&Fog>,
+    wavelengths: &SampledWavelengths,
+    depth: i32,
+) -> PathSample {
+    let sample = trace(
+        ray,
+        None,
+        world,
+        lights,
+        background,
+        fog,
+        Some(wavelengths),
+        depth,
+    );
+    to_rgb(sample, wavelengths)
+}
+
+/// `trace_path`, or `trace_spectral_path` with `wavelengths`, for a camera ray whose closest hit
+/// was already found by tracing it in a packet.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn trace_from_hit<W: Hittable>(
+    ray: Ray,
+    hit: Option<HitRecord>,
+    world: &W,
+    lights: &[Light],
+    background: &Background,
+    fog: Option<&Fog>,
+    wavelengths: Option<&SampledWavelengths>,
+    depth: i32,
+) -> PathSample {
+    let sample = trace(
+        ray,
+        Some(hit),
+        world,
+        lights,
+        background,
+        fog,
+        wavelengths,
+        depth,
+    );
+    match wavelengths {
+        Some(wavelengths) => to_rgb(sample, wavelengths),
+        None => sample,
+    }
+}
+
+/// Converts the radiance, albedo and components of a spectral sample to linear sRGB.
+fn to_rgb(mut sample: PathSample, wavelengths: &SampledWavelengths) -> PathSample {
+    sample.color = wavelengths.to_rgb(sample.color);
+    sample.albedo = wavelengths.to_rgb(sample.albedo);
+    let components = &mut sample.components;
+    for component in [
+        &mut components.emission,
+        &mut components.diffuse_direct,
+        &mut components.diffuse_indirect,
+        &mut components.specular_direct,
+        &mut components.specular_indirect,
+    ] {
+        *component = wavelengths.to_rgb(*component);
+    }
+    sample
+}
+
+/// `camera_hit` is the closest hit of `ray` if it is already known.
+#[allow(clippy::too_many_arguments)]
+fn trace<W: Hittable>(
+    mut ray: Ray,
+    mut camera_hit: Option<Option<HitRecord>>,
+    world: &W,
+    lights: &[Light],
+    background: &Background,
+    fog: Option<&Fog>,
+    wavelengths: Option<&SampledWavelengths>,
+    mut depth: i32,
+) -> PathSample {
+    let fog = match wavelengths {
+        Some(wavelengths) => fog.map(|fog| fog.at_wavelengths(wavelengths)),
+        None => fog.copied(),
+    };
+    let fog = fog.as_ref();
+    let mut color = Color::ZERO;
+    let mut throughput = Color::ONE;
+    let mut rays = 0;
+    let mut bounces = 0;
+    let mut albedo = Color::ZERO;
+    let mut first_hit = None;
+    let mut first_lobe = None;
+    let mut components = PathComponents::default();
+    // Density of the BSDF picking the direction of `ray`, when the surface it left also sampled
+    // a light
+    let mut bsdf_pdf = None;
+    // Medium of the volume the path is in, `None` outside of volumes where the fog is
+    let mut interior: Option<Interior> = None;
+    // How far along the direction the path went through boundaries of volumes since it last
+    // scattered, as they start a new ray without a bounce
+    let mut t_offset = 0.0;
+    // Steps of the random walk under a translucent surface so far
+    let mut walk_steps = 0;
+    // Whether a dispersive surface left only the hero wavelength of a spectral path
+    let mut hero_only = false;
+
+    while depth > 0 {
+        rays += 1;
+        let hit = match camera_hit.take() {
+            Some(hit) => hit,
+            None => world.hit(&ray, T_MIN, math::INFINITY),
+        };
+        let t_end = hit.as_ref().map_or(math::INFINITY, |record| record.t);
+        if hit.is_none() {
+            // Scattered closer to the boundary of the volume than the ray can see
+            interior = None;
+        }
+        if let Some((medium, density, t_start, t_stop)) =
+            medium_along(interior.as_ref(), fog, &ray, t_end)
+        {
+            let walking = interior.as_ref().is_some_and(|interior| interior.walk);
+            match medium.sample_distance(&ray, t_start, t_stop, density) {
+                FreeFlight::Passed { weight } => throughput *= weight,
+                // Steps of a walk are not bounces, the walk ends when the path comes out
+                FreeFlight::Scattered { t, weight } if walking => {
+                    walk_steps += 1;
+                    if walk_steps > MAX_WALK_STEPS {
+                        break;
+                    }
+                    throughput *= weight;
+                    let wi = medium.phase().sample(-ray.direction.unit_vector());
+                    bsdf_pdf = None;
+                    ray = Ray::new(ray.at(t), wi);
+                    t_offset = 0.0;
+                    continue;
+                }
+                FreeFlight::Scattered { t, weight } => {
+                    throughput *= weight;
+                    let point = ray.at(t);
+                    let wo = -ray.direction.unit_vector();
+                    let phase = medium.phase();
+                    if bounces == 0 {
+                        albedo = medium.albedo();
+                        first_lobe = Some(Lobe::Diffuse);
+                    }
+
+                    let light_sampling = depth > 1 && !lights.is_empty();
+                    if light_sampling {
+                        let (sample, light_pdf) = pick_light(lights, point);
+                        if sample.pdf > 0.0 {
+                            let shadow_ray = Ray::new(point, sample.direction);
+                            let t_max = sample.distance * (1.0 - SHADOW_MARGIN);
+                            let transmittance = transmittance(
+                                world,
+                                shadow_ray,
+                                t_max,
+                                interior.clone(),
+                                fog,
+                                wavelengths,
+                                &mut rays,
+                            );
+                            let phase_value = phase.eval(wo, sample.direction.unit_vector());
+                            let weight = power_heuristic(light_pdf, phase_value);
+                            let radiance = throughput
+                                * transmittance
+                                * light(wavelengths, sample.emission)
+                                * (phase_value * weight / light_pdf);
+                            color += radiance;
+                            components.add(first_lobe, bounces + 1, radiance);
+                        }
+                    }
+
+                    let wi = phase.sample(wo);
+                    bsdf_pdf = light_sampling.then(|| phase.eval(wo, wi));
+                    depth -= 1;
+                    bounces += 1;
+                    ray = Ray::new(point, wi);
+                    t_offset = 0.0;
+                    continue;
+                }
+            }
+        }
+
+        if let Some(record) = hit {
+            // Entering or leaving a volume is not a bounce
+            if record.material.is_interface() {
+                interior = match record.front_face {
+                    true => entered(&record, wavelengths),
+                    false => None,
+                };
+                t_offset += record.t;
+                ray = Ray::new(record.spawn_point(ray.direction), ray.direction);
+                continue;
+            }
 
-        if let Some(record) = world.hit(ray, 0.0001, math::INFINITY) {
-            if let Some(scatter) = record.material.scatter(&ray, &record) {
-                color *= scatter.attenuation;
+            let emitted = light(wavelengths, record.material.emitted());
+            let weight = match bsdf_pdf {
+                Some(bsdf_pdf) if record.material.is_light() => {
+                    // From where the path last scattered
+                    let segment = Ray::new(ray.origin - t_offset * ray.direction, ray.direction);
+                    power_heuristic(bsdf_pdf, light_pdf(lights, &segment, t_offset + record.t))
+                }
+                _ => 1.0,
+            };
+            let radiance = throughput * emitted * weight;
+            color += radiance;
+            components.add(first_lobe, bounces, radiance);
+
+            if bounces == 0 {
+                // Lights are black, their albedo is what they emit like for the background
+                albedo = reflectance(wavelengths, record.material.albedo(record.point)) + emitted;
+                first_lobe = Some(match record.material.is_specular() {
+                    true => Lobe::Specular,
+                    false => Lobe::Diffuse,
+                });
+                first_hit = Some(FirstHit {
+                    position: record.point,
+                    normal: record.normal,
+                    depth: (t_offset + record.t) * ray.direction.length(),
+                    object_id: record.object_id,
+                    material_id: record.material.id(),
+                });
+            }
+
+            // Lights sampled here are only reached if the path has a bounce left. Translucent
+            // surfaces let light in diffusely where a walk comes out, from outside their medium,
+            // and the path seen from that side goes on through the surface.
+            let diffuse = match record.material.is_subsurface() {
+                true if !record.front_face => Some((
+                    Lambertian { albedo: Color::ONE },
+                    -record.normal,
+                    ray.direction,
+                    None,
+                )),
+                true => None,
+                false => record
+                    .material
+                    .diffuse(record.point)
+                    .map(|bsdf| (bsdf, record.normal, -ray.direction, interior.clone())),
+            };
+            let light_sampling = match diffuse {
+                Some((bsdf, normal, view, outside)) if depth > 1 && !lights.is_empty() => {
+                    let frame = Onb::from_w(Vec3::from(normal));
+                    let wo = frame.to_local(view.unit_vector());
+                    Some((bsdf, frame, wo, outside))
+                }
+                _ => None,
+            };
+            bsdf_pdf = None;
+            if let Some((bsdf, frame, wo, outside)) = &light_sampling {
+                let (sample, light_pdf) = pick_light(lights, record.point);
+                let wi = frame.to_local(sample.direction);
+                if sample.pdf > 0.0 && wi.z > 0.0 {
+                    // From off the surface to the sampled point, which is at t = 1
+                    let origin = record.spawn_point(sample.direction);
+                    let target = record.point + sample.distance * sample.direction;
+                    let shadow_ray = Ray::new(origin, target - origin);
+                    let t_max = 1.0 - SHADOW_MARGIN;
+                    let transmittance = transmittance(
+                        world,
+                        shadow_ray,
+                        t_max,
+                        outside.clone(),
+                        fog,
+                        wavelengths,
+                        &mut rays,
+                    );
+                    if transmittance != Color::ZERO {
+                        let weight = power_heuristic(light_pdf, bsdf.pdf(*wo, wi));
+                        let radiance = throughput
+                            * transmittance
+                            * reflectance(wavelengths, bsdf.eval(*wo, wi))
+                            * light(wavelengths, sample.emission)
+                            * (wi.z * weight / light_pdf);
+                        color += radiance;
+                        components.add(first_lobe, bounces + 1, radiance);
+                    }
+                }
+            }
+
+            let scatter = match wavelengths {
+                Some(wavelengths) if record.material.is_dispersive() => {
+                    // The other wavelengths would go other ways, the hero one carries their share
+                    if !hero_only {
+                        throughput = Color::new(3.0 * throughput.x, 0.0, 0.0);
+                        hero_only = true;
+                    }
+                    record
+                        .material
+                        .scatter_at(&ray, &record, wavelengths.hero())
+                }
+                _ => record.material.scatter(&ray, &record),
+            };
+            if let Some(scatter) = scatter {
+                if let Some((bsdf, frame, wo, _)) = &light_sampling {
+                    let wi = frame.to_local(scatter.ray.direction.unit_vector());
+                    bsdf_pdf = Some(bsdf.pdf(*wo, wi));
+                }
+                // Rays going through a surface are inside it afterwards, in its medium if it
+                // has one, or back outside
+                if record.normal.dot(scatter.ray.direction) < 0.0 {
+                    interior = match record.front_face {
+                        true => Some(entered(&record, wavelengths).unwrap_or_default()),
+                        false => None,
+                    };
+                    walk_steps = 0;
+                }
+                throughput *= reflectance(wavelengths, scatter.attenuation);
                 depth -= 1;
-                ray = scatter.ray;
+                bounces += 1;
+                ray = Ray::new(
+                    record.spawn_point(scatter.ray.direction),
+                    scatter.ray.direction,
+                );
+                t_offset = 0.0;
             } else {
-                color = Color::ZERO;
                 break;
             }
         } else {
-            // Background color, kinda skyish
-            let unit_direction = &ray.direction.unit_vector();
-            let t = 0.5 * (unit_direction.y + 1.0);
-            color *= (1.0 - t) * Color::ONE
-                + t * Color {
-                    x: 0.5,
-                    y: 0.7,
-                    z: 1.0,
-                };
+            let background = light(wavelengths, background.radiance(&ray));
+            if bounces == 0 {
+                albedo = background;
+            }
+            let radiance = throughput * background;
+            color += radiance;
+            components.add(first_lobe, bounces, radiance);
             break;
         }
     }
-    color
+
+    PathSample {
+        color,
+        rays,
+        albedo,
+        first_hit,
+        components,
+    }
+}
+
+/// Longest random walk under a translucent surface, paths that haven't come out by then are
+/// dropped.
+const MAX_WALK_STEPS: u32 = 256;
+
+/// Fraction of the distance to a sampled light that shadow rays stop short of it, so they don't
+/// hit the light itself.
+const SHADOW_MARGIN: Float = if cfg!(feature = "f32") { 1e-4 } else { 1e-6 };
+
+/// Closest hit a ray can have, so it doesn't hit the surface it starts from.
+pub(crate) const T_MIN: Float = 0.0001;
+
+/// Medium a path is in.
+#[derive(Clone, Default)]
+struct Interior {
+    medium: Medium,
+    /// Density field of a volume filled from a grid
+    density: Option<DensityField>,
+    /// Whether the path walks under a translucent surface, where scattering is not a bounce
+    walk: bool,
+}
+
+/// Medium behind a surface a path goes into, at the wavelengths of a spectral path.
+fn entered(record: &HitRecord, wavelengths: Option<&SampledWavelengths>) -> Option<Interior> {
+    let medium = record.material.interior()?;
+    Some(Interior {
+        medium: match wavelengths {
+            Some(wavelengths) => medium.at_wavelengths(wavelengths),
+            None => *medium,
+        },
+        density: record.density.clone(),
+        walk: record.material.is_subsurface(),
+    })
 }
 
-fn hit_sphere(center: Point3, radius: f64, ray: &Ray) -> f64 {
-    let oc = ray.origin - center;
-    let a = ray.direction.squared_length();
-    let half_b = Vec3::dot_product(&oc, &ray.direction);
-    let c = oc.squared_length() - radius * radius;
-    let discriminant = half_b * half_b - a * c;
-    if discriminant < 0.0_f64 {
-        -1.0
-    } else {
-        (-half_b - discriminant.sqrt()) / a
+/// Medium along `ray` up to `t_end`, its density field if it varies, and the part of the ray in
+/// it: all of it inside a volume, outside only where the fog is.
+fn medium_along<'a>(
+    interior: Option<&'a Interior>,
+    fog: Option<&'a Fog>,
+    ray: &Ray,
+    t_end: Float,
+) -> Option<(&'a Medium, Option<&'a DensityField>, Float, Float)> {
+    match interior {
+        Some(interior) => Some((&interior.medium, interior.density.as_ref(), 0.0, t_end)),
+        None => {
+            let fog = fog?;
+            let (start, end) = fog.interval(ray, 0.0, t_end)?;
+            Some((&fog.medium, None, start, end))
+        }
     }
 }
+
+/// Color of a surface as a path carries it, at the wavelengths of a spectral path.
+fn reflectance(wavelengths: Option<&SampledWavelengths>, color: Color) -> Color {
+    wavelengths.map_or(color, |wavelengths| wavelengths.reflectance(color))
+}
+
+/// Color of a light as a path carries it, at the wavelengths of a spectral path.
+fn light(wavelengths: Option<&SampledWavelengths>, color: Color) -> Color {
+    wavelengths.map_or(color, |wavelengths| wavelengths.unbounded(color))
+}
+
+/// One of `lights` picked at random and a point on it seen from `point`, with the density of
+/// picking both.
+fn pick_light(lights: &[Light], point: Point3) -> (LightSample, Float) {
+    let index = (random_scalar() * lights.len() as Float) as usize;
+    let sample = lights[index.min(lights.len() - 1)].sample(point);
+    let pdf = sample.pdf / lights.len() as Float;
+    (sample, pdf)
+}
+
+/// Fraction of the light that gets along `ray` up to `t_max`: none if a surface is in the way,
+/// and what the media on the way let through. Boundaries of volumes don't block the ray.
+fn transmittance<W: Hittable>(
+    world: &W,
+    mut ray: Ray,
+    mut t_max: Float,
+    mut interior: Option<Interior>,
+    fog: Option<&Fog>,
+    wavelengths: Option<&SampledWavelengths>,
+    rays: &mut u32,
+) -> Color {
+    let mut transmittance = Color::ONE;
+    loop {
+        *rays += 1;
+        let hit = world.hit(&ray, T_MIN, t_max);
+        let t_end = hit.as_ref().map_or(t_max, |record| record.t);
+        if let Some((medium, density, t_start, t_stop)) =
+            medium_along(interior.as_ref(), fog, &ray, t_end)
+        {
+            transmittance *= medium.transmittance_along(&ray, t_start, t_stop, density);
+        }
+        match hit {
+            None => return transmittance,
+            Some(record) if record.material.is_interface() => {
+                interior = match record.front_face {
+                    true => entered(&record, wavelengths),
+                    false => None,
+                };
+                t_max -= record.t;
+                ray = Ray::new(record.spawn_point(ray.direction), ray.direction);
+            }
+            Some(_) => return Color::ZERO,
+        }
+    }
+}
+
+/// Density of any light being sampled in the direction of `ray`, which hit a light at `t`.
+fn light_pdf(lights: &[Light], ray: &Ray, t: Float) -> Float {
+    // Only the light that was hit counts, not the ones behind it
+    let t_max = t * (1.0 + math::EPSILON);
+    lights
+        .iter()
+        .map(|light| light.pdf(ray, t_max))
+        .sum::<Float>()
+        / lights.len() as Float
+}
+
+/// Weight of a sample taken with the density `pdf` when `other_pdf` could also have produced it.
+fn power_heuristic(pdf: Float, other_pdf: Float) -> Float {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    match a + b > 0.0 {
+        true => a / (a + b),
+        false => 0.0,
+    }
+}
+
+/// Background color, kinda skyish
+fn sky_color(ray: &Ray) -> Color {
+    let unit_direction = &ray.direction.unit_vector();
+    let t = 0.5 * (unit_direction.y + 1.0);
+    (1.0 - t) * Color::ONE
+        + t * Color {
+            x: 0.5,
+            y: 0.7,
+            z: 1.0,
+        }
+}
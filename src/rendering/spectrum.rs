@@ -0,0 +1,178 @@
+use std::sync::OnceLock;
+
+use crate::math::{random_scalar, Color, Float, Vec3};
+
+/// Shortest wavelength traced in spectral mode, in nanometers
+pub const LAMBDA_MIN: Float = 380.0;
+/// Longest wavelength traced in spectral mode, in nanometers
+pub const LAMBDA_MAX: Float = 780.0;
+
+/// Wavelengths a path carries in spectral mode, one in each channel of its colors: a hero
+/// wavelength picked uniformly and two more a third of the visible range apart from it, wrapping
+/// around. The three share the path, so one path estimates three wavelengths at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampledWavelengths {
+    lambda: [Float; 3],
+}
+
+impl SampledWavelengths {
+    /// Hero wavelength at `u` between 0 and 1 across the visible range.
+    pub fn new(u: Float) -> SampledWavelengths {
+        let at = |offset: Float| LAMBDA_MIN + (u + offset).fract() * (LAMBDA_MAX - LAMBDA_MIN);
+        SampledWavelengths {
+            lambda: [at(0.0), at(1.0 / 3.0), at(2.0 / 3.0)],
+        }
+    }
+
+    pub fn sample() -> SampledWavelengths {
+        SampledWavelengths::new(random_scalar())
+    }
+
+    pub fn hero(&self) -> Float {
+        self.lambda[0]
+    }
+
+    pub fn wavelengths(&self) -> [Float; 3] {
+        self.lambda
+    }
+
+    /// Values at the wavelengths of a smooth reflectance spectrum with the color `rgb`, kept
+    /// between 0 and 1.
+    pub fn reflectance(&self, rgb: Color) -> Color {
+        let values = self.upsample(rgb);
+        Color::new(
+            values.x.clamp(0.0, 1.0),
+            values.y.clamp(0.0, 1.0),
+            values.z.clamp(0.0, 1.0),
+        )
+    }
+
+    /// Values at the wavelengths of a smooth spectrum with the color `rgb` that can go above 1,
+    /// for lights and the coefficients of media.
+    pub fn unbounded(&self, rgb: Color) -> Color {
+        let values = self.upsample(rgb);
+        Color::new(values.x.max(0.0), values.y.max(0.0), values.z.max(0.0))
+    }
+
+    fn upsample(&self, rgb: Color) -> Color {
+        let weights = conversion().basis_weights(rgb);
+        let [a, b, c] = self
+            .lambda
+            .map(|lambda| Vec3::dot_product(&weights, &basis(lambda)));
+        Color::new(a, b, c)
+    }
+
+    /// Linear sRGB of the radiance carried at the wavelengths, white balanced so a flat spectrum
+    /// is white.
+    pub fn to_rgb(&self, radiance: Color) -> Color {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let xyz = (0..3)
+            .map(|channel| radiance[channel] * cie_xyz(self.lambda[channel]))
+            .fold(Color::ZERO, |sum, xyz| sum + xyz)
+            * (range / 3.0);
+        conversion().balanced_rgb(xyz)
+    }
+}
+
+/// Value of the CIE 1931 color matching functions at `wavelength` in nanometers, from the
+/// multi-lobe fit of Wyman et al., "Simple Analytic Approximations to the CIE XYZ Color Matching
+/// Functions".
+pub fn cie_xyz(wavelength: Float) -> Color {
+    let lobe = |mean: Float, below: Float, above: Float| {
+        let width = if wavelength < mean { below } else { above };
+        let t = (wavelength - mean) / width;
+        (-0.5 * t * t).exp()
+    };
+    Color::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+/// Linear sRGB of a CIE XYZ color, for the D65 white point.
+pub fn xyz_to_rgb(xyz: Color) -> Color {
+    Color::new(
+        3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+        -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+        0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+    )
+}
+
+/// Blue, green and red spectra that add up to 1 at every wavelength, which RGB colors are
+/// upsampled to mixes of. Their smooth edges keep the spectra of ordinary colors smooth.
+fn basis(wavelength: Float) -> Vec3 {
+    let smoothstep = |edge: Float| {
+        let t = ((wavelength - edge) / 40.0 + 0.5).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    };
+    let (blue_end, red_start) = (smoothstep(490.0), smoothstep(585.0));
+    Vec3::new(1.0 - blue_end, blue_end - red_start, red_start)
+}
+
+/// Constants of the conversions between spectra and RGB, integrated once.
+struct Conversion {
+    /// Integral of the luminance matching function, the luminance of a flat spectrum of 1
+    luminance: Float,
+    /// RGB of a flat spectrum before white balancing
+    white: Color,
+    /// Rows of the inverse of the matrix whose columns are the colors of the basis spectra
+    from_rgb: [Vec3; 3],
+}
+
+impl Conversion {
+    fn new() -> Conversion {
+        const STEPS: usize = 4000;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / STEPS as Float;
+        let mut xyz = Color::ZERO;
+        let mut basis_xyz = [Color::ZERO; 3];
+        for index in 0..STEPS {
+            let wavelength = LAMBDA_MIN + (index as Float + 0.5) * step;
+            let matching = cie_xyz(wavelength) * step;
+            let weights = basis(wavelength);
+            xyz += matching;
+            for (channel, sum) in basis_xyz.iter_mut().enumerate() {
+                *sum += weights[channel] * matching;
+            }
+        }
+        let mut conversion = Conversion {
+            luminance: xyz.y,
+            white: Color::ONE,
+            from_rgb: [Vec3::ZERO; 3],
+        };
+        conversion.white = conversion.balanced_rgb(xyz);
+        let [a, b, c] = basis_xyz.map(|xyz| as_vector(conversion.balanced_rgb(xyz)));
+        let determinant = Vec3::dot_product(&a, &Vec3::cross_product(&b, &c));
+        conversion.from_rgb = [
+            Vec3::cross_product(&b, &c) / determinant,
+            Vec3::cross_product(&c, &a) / determinant,
+            Vec3::cross_product(&a, &b) / determinant,
+        ];
+        conversion
+    }
+
+    fn balanced_rgb(&self, xyz: Color) -> Color {
+        xyz_to_rgb(xyz / self.luminance) / self.white
+    }
+
+    /// Weights of the basis spectra that mix to `rgb`.
+    fn basis_weights(&self, rgb: Color) -> Vec3 {
+        let rgb = as_vector(rgb);
+        Vec3::new(
+            Vec3::dot_product(&self.from_rgb[0], &rgb),
+            Vec3::dot_product(&self.from_rgb[1], &rgb),
+            Vec3::dot_product(&self.from_rgb[2], &rgb),
+        )
+    }
+}
+
+/// The channels of a color as a vector, to solve for the weights of the basis spectra.
+fn as_vector(color: Color) -> Vec3 {
+    Vec3::new(color.x, color.y, color.z)
+}
+
+fn conversion() -> &'static Conversion {
+    static CONVERSION: OnceLock<Conversion> = OnceLock::new();
+    CONVERSION.get_or_init(Conversion::new)
+}
@@ -0,0 +1,36 @@
+use crate::math::{Color, Float, Point3};
+
+/// Color that varies over a surface, looked up by the position of the hit point.
+#[derive(Debug, Copy, Clone)]
+pub enum Texture {
+    Solid(Color),
+    /// 3D checker pattern of cubes with sides of `scale`, filling space rather than the surface
+    Checker {
+        even: Color,
+        odd: Color,
+        scale: Float,
+    },
+}
+
+impl Default for Texture {
+    fn default() -> Self {
+        Texture::Solid(Color::ZERO)
+    }
+}
+
+impl Texture {
+    pub fn value(&self, point: Point3) -> Color {
+        match *self {
+            Texture::Solid(color) => color,
+            Texture::Checker { even, odd, scale } => {
+                let cell = (point.x / scale).floor() as i64
+                    + (point.y / scale).floor() as i64
+                    + (point.z / scale).floor() as i64;
+                match cell.rem_euclid(2) {
+                    0 => even,
+                    _ => odd,
+                }
+            }
+        }
+    }
+}
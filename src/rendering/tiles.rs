@@ -0,0 +1,128 @@
+/// Rectangular block of pixels rendered as a single unit of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// Order in which tiles are handed out to the worker threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TileOrder {
+    /// Left to right, top to bottom
+    Scanline,
+    /// Outwards from the center of the image
+    #[default]
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles next to each other
+    Hilbert,
+}
+
+/// Splits an image into tiles of at most `tile_size` x `tile_size` pixels, sorted in `order`.
+pub fn generate_tiles(
+    image_width: usize,
+    image_height: usize,
+    tile_size: usize,
+    order: TileOrder,
+) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let columns = image_width.div_ceil(tile_size);
+    let rows = image_height.div_ceil(tile_size);
+
+    let coordinates = match order {
+        TileOrder::Scanline => scanline_order(columns, rows),
+        TileOrder::Spiral => spiral_order(columns, rows),
+        TileOrder::Hilbert => hilbert_order(columns, rows),
+    };
+
+    coordinates
+        .into_iter()
+        .map(|(column, row)| {
+            let x = column * tile_size;
+            let y = row * tile_size;
+            Tile {
+                x,
+                y,
+                width: tile_size.min(image_width - x),
+                height: tile_size.min(image_height - y),
+            }
+        })
+        .collect()
+}
+
+fn scanline_order(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect()
+}
+
+fn spiral_order(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let total = columns * rows;
+    let mut order = Vec::with_capacity(total);
+    if total == 0 {
+        return order;
+    }
+
+    // Walk right, down, left, up with run lengths 1, 1, 2, 2, 3, 3... skipping tiles outside the grid
+    const DIRECTIONS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut column = (columns / 2) as isize;
+    let mut row = (rows / 2) as isize;
+    let mut run_length = 1;
+    let mut direction = 0;
+
+    order.push((column as usize, row as usize));
+    while order.len() < total {
+        for _ in 0..2 {
+            let (step_x, step_y) = DIRECTIONS[direction];
+            for _ in 0..run_length {
+                column += step_x;
+                row += step_y;
+                if column >= 0 && row >= 0 && (column as usize) < columns && (row as usize) < rows {
+                    order.push((column as usize, row as usize));
+                }
+            }
+            direction = (direction + 1) % 4;
+        }
+        run_length += 1;
+    }
+    order
+}
+
+fn hilbert_order(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let side = columns.max(rows).next_power_of_two();
+    (0..side * side)
+        .map(|distance| hilbert_distance_to_point(side, distance))
+        .filter(|&(column, row)| column < columns && row < rows)
+        .collect()
+}
+
+/// Converts a distance along a Hilbert curve filling a `side` x `side` grid into grid coordinates.
+fn hilbert_distance_to_point(side: usize, distance: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = distance;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
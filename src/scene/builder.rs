@@ -0,0 +1,337 @@
+use std::ops::Index;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::hittables::{
+    cuboid, Block, Bvh, Csg, Disk, DistanceField, Hittable, Instance, Mesh, Plane, Primitive, Quad,
+    Quadric, QuadricKind, Sphere, Torus, Volume,
+};
+use crate::math::{Float, Matrix4, Point3, Vec3, EPSILON, PI};
+use crate::rendering::{Background, Camera, Fog, Light, Medium, UberShader};
+use crate::scene::{Node, Scene};
+
+/// Assembles a `Scene` one object at a time.
+#[derive(Default)]
+pub struct SceneBuilder {
+    camera: Camera,
+    objects: Vec<Primitive>,
+    /// Roots of the scene graph, flattened into instances by `build`
+    nodes: Vec<Node>,
+    background: Background,
+    fog: Option<Fog>,
+    /// First problem found while adding objects, reported by `build`
+    error: Option<Error>,
+}
+
+impl SceneBuilder {
+    pub fn camera(mut self, camera: Camera) -> SceneBuilder {
+        self.camera = camera;
+        self
+    }
+
+    pub fn background(mut self, background: Background) -> SceneBuilder {
+        self.background = background;
+        self
+    }
+
+    /// Fills the space around the objects with a medium, like haze or smoke.
+    pub fn fog(mut self, fog: Fog) -> SceneBuilder {
+        self.fog = Some(fog);
+        self
+    }
+
+    pub fn sphere(mut self, center: Point3, radius: Float, material: UberShader) -> SceneBuilder {
+        self.objects
+            .push(Primitive::Sphere(Sphere::new(center, radius, material)));
+        self
+    }
+
+    /// Infinite plane through `point`, facing the side `normal` points to.
+    pub fn plane(mut self, point: Point3, normal: Vec3, material: UberShader) -> SceneBuilder {
+        self.objects
+            .push(Primitive::Plane(Plane::new(point, normal, material)));
+        self
+    }
+
+    pub fn disk(
+        mut self,
+        center: Point3,
+        normal: Vec3,
+        radius: Float,
+        material: UberShader,
+    ) -> SceneBuilder {
+        self.objects
+            .push(Primitive::Disk(Disk::new(center, normal, radius, material)));
+        self
+    }
+
+    /// Parallelogram with the edges `u` and `v` from `corner`. Emissive quads are sampled as
+    /// area lights.
+    pub fn quad(mut self, corner: Point3, u: Vec3, v: Vec3, material: UberShader) -> SceneBuilder {
+        self.objects
+            .push(Primitive::Quad(Quad::new(corner, u, v, material)));
+        self
+    }
+
+    /// Axis-aligned box between two opposite corners, made of six quads.
+    pub fn cuboid(mut self, a: Point3, b: Point3, material: UberShader) -> SceneBuilder {
+        self.objects
+            .extend(cuboid(a, b, material).into_iter().map(Primitive::Quad));
+        self
+    }
+
+    /// Solid axis-aligned box between two opposite corners, which can be used in `Csg`.
+    pub fn block(mut self, a: Point3, b: Point3, material: UberShader) -> SceneBuilder {
+        self.objects
+            .push(Primitive::Block(Block::new(a, b, material)));
+        self
+    }
+
+    /// Cylinder, cone, paraboloid or hyperboloid.
+    pub fn quadric(mut self, quadric: Quadric) -> SceneBuilder {
+        self.objects.push(Primitive::Quadric(quadric));
+        self
+    }
+
+    pub fn torus(mut self, torus: Torus) -> SceneBuilder {
+        self.objects.push(Primitive::Torus(torus));
+        self
+    }
+
+    /// Union, intersection or difference of closed primitives.
+    pub fn csg(mut self, csg: Csg) -> SceneBuilder {
+        self.objects.push(csg.into());
+        self
+    }
+
+    /// Medium inside a closed primitive, like a cloud of smoke.
+    pub fn volume(mut self, volume: Volume) -> SceneBuilder {
+        self.objects.push(volume.into());
+        self
+    }
+
+    /// Shape of a signed distance field, found by sphere tracing.
+    pub fn distance_field(mut self, field: DistanceField) -> SceneBuilder {
+        self.objects.push(field.into());
+        self
+    }
+
+    /// Places `mesh` with the transform `to_world`, without copying it. Add the same mesh as
+    /// often as needed.
+    pub fn instance(mut self, mesh: &Arc<Mesh>, to_world: Matrix4) -> SceneBuilder {
+        match Instance::new(Arc::clone(mesh), to_world) {
+            Some(instance) => self.objects.push(instance.into()),
+            None => {
+                let error = Error::InvalidScene(format!(
+                    "object {} is an instance with a transform that can't be inverted",
+                    self.objects.len() + 1
+                ));
+                self.error.get_or_insert(error);
+            }
+        }
+        self
+    }
+
+    /// Adds the root of a scene graph. Its instances get object IDs after the other objects.
+    pub fn node(mut self, node: Node) -> SceneBuilder {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Node at a path of names separated by `/`, starting with the name of a root, to change
+    /// it before the scene is built.
+    pub fn node_mut(&mut self, path: &str) -> Option<&mut Node> {
+        let (root, rest) = match path.split_once('/') {
+            Some((root, rest)) => (root, Some(rest)),
+            None => (path, None),
+        };
+        let root = self.nodes.iter_mut().find(|node| node.name == root)?;
+        match rest {
+            Some(rest) => root.find_mut(rest),
+            None => Some(root),
+        }
+    }
+
+    /// Fails if an object has a degenerate shape, like a sphere without a positive radius, or
+    /// an instance is squashed flat.
+    pub fn build(mut self) -> Result<Scene> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        for node in &self.nodes {
+            node.flatten(&node.name, Matrix4::IDENTITY, None, &mut self.objects)?;
+        }
+        if let Some(fog) = &self.fog {
+            if fog.radius.is_nan() || fog.radius <= 0.0 {
+                return Err(Error::InvalidScene(format!(
+                    "the fog has a radius of {}, it needs to be positive",
+                    fog.radius
+                )));
+            }
+            medium(fog.medium())
+                .map_err(|message| Error::InvalidScene(format!("the fog {}", message)))?;
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            validate(object).map_err(|message| {
+                Error::InvalidScene(format!("object {} {}", index + 1, message))
+            })?;
+        }
+        // Lights inside meshes are only found by paths that hit them
+        let lights = self
+            .objects
+            .iter()
+            .filter_map(|object| match object {
+                Primitive::Quad(quad) if quad.material.is_light() => {
+                    Some(Light::Quad(quad.clone()))
+                }
+                Primitive::Disk(disk) if disk.material.is_light() => {
+                    Some(Light::Disk(disk.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(Scene {
+            camera: self.camera,
+            world: Bvh::new(self.objects),
+            lights,
+            background: self.background,
+            fog: self.fog,
+        })
+    }
+}
+
+/// Describes what is wrong with the primitive, or anything in the mesh of an instance.
+fn validate(primitive: &Primitive) -> std::result::Result<(), String> {
+    match primitive {
+        Primitive::Sphere(sphere) => positive("sphere", "radius", sphere.radius),
+        Primitive::Plane(plane) => match finite(plane.normal) {
+            true => Ok(()),
+            false => Err("is a plane without a normal".to_string()),
+        },
+        Primitive::Disk(disk) if !finite(disk.normal) => {
+            Err("is a disk without a normal".to_string())
+        }
+        Primitive::Disk(disk) => positive("disk", "radius", disk.radius),
+        Primitive::Quad(quad) => match quad.area() > 0.0 && quad.area().is_finite() {
+            true => Ok(()),
+            false => Err("is a quad with parallel edges".to_string()),
+        },
+        Primitive::Quadric(quadric) => {
+            let shape = quadric.kind_name().trim_end_matches('s');
+            positive(shape, "height", quadric.height)?;
+            positive(shape, "radius", quadric.radius)?;
+            if quadric.kind == QuadricKind::Hyperboloid {
+                positive(shape, "waist", quadric.waist)?;
+                if quadric.waist >= quadric.radius {
+                    return Err(format!(
+                        "is a hyperboloid with waist {}, it needs to be narrower than the radius {}",
+                        quadric.waist, quadric.radius
+                    ));
+                }
+            }
+            sweep(shape, quadric.sweep)
+        }
+        Primitive::Torus(torus) if !finite(torus.axis) => {
+            Err("is a torus without an axis".to_string())
+        }
+        Primitive::Torus(torus) => {
+            positive("torus", "major radius", torus.major_radius)?;
+            positive("torus", "minor radius", torus.minor_radius)?;
+            sweep("torus", torus.sweep)
+        }
+        Primitive::Block(block) => {
+            let extent = block.max - block.min;
+            positive("block", "width", extent.x)?;
+            positive("block", "height", extent.y)?;
+            positive("block", "depth", extent.z)
+        }
+        Primitive::Csg(csg) => {
+            let operation = format!("{:?}", csg.operation).to_lowercase();
+            for (position, operand) in [("first", &csg.first), ("second", &csg.second)] {
+                if !operand.is_closed() {
+                    return Err(format!(
+                        "is a csg {} whose {} operand is not closed, only spheres, blocks, capped \
+                         quadrics, full tori and other csg solids can be combined",
+                        operation, position
+                    ));
+                }
+                validate(operand).map_err(|message| {
+                    format!(
+                        "is a csg {} whose {} operand {}",
+                        operation, position, message
+                    )
+                })?;
+            }
+            Ok(())
+        }
+        Primitive::Volume(volume) if !volume.boundary().is_closed() => Err(format!(
+            "is a volume bounded by {}, which are not closed",
+            volume.boundary().kind()
+        )),
+        Primitive::Volume(volume) => {
+            validate(volume.boundary())
+                .map_err(|message| format!("is a volume whose boundary {}", message))?;
+            medium(volume.medium())
+                .map_err(|message| format!("is a volume whose medium {}", message))
+        }
+        Primitive::DistanceField(field) => {
+            let bounds = field.bounding_box();
+            match bounds.is_bounded() && !bounds.is_empty() {
+                true => positive("distance field", "precision", field.precision),
+                false => Err("is a distance field without finite bounds".to_string()),
+            }
+        }
+        Primitive::Instance(instance) => instance
+            .mesh()
+            .primitives()
+            .iter()
+            .try_for_each(validate)
+            .map_err(|message| format!("is an instance of a mesh whose object {}", message)),
+    }
+}
+
+/// Checks the coefficients of a medium, the message follows "the fog" or "whose medium".
+fn medium(medium: &Medium) -> std::result::Result<(), String> {
+    let coefficients = [medium.absorption, medium.scattering];
+    if !coefficients
+        .iter()
+        .all(|color| finite(*color) && color.x >= 0.0 && color.y >= 0.0 && color.z >= 0.0)
+    {
+        return Err("has negative or infinite coefficients".to_string());
+    }
+    let asymmetry = medium.phase().asymmetry;
+    match asymmetry > -1.0 && asymmetry < 1.0 {
+        true => Ok(()),
+        false => Err(format!(
+            "has an asymmetry of {}, it needs to be between -1 and 1",
+            asymmetry
+        )),
+    }
+}
+
+/// Whether the three coordinates or channels of a vector, normal or color are finite.
+fn finite(value: impl Index<usize, Output = Float>) -> bool {
+    (0..3).all(|axis| value[axis].is_finite())
+}
+
+fn positive(shape: &str, name: &str, value: Float) -> std::result::Result<(), String> {
+    match value > 0.0 && value.is_finite() {
+        true => Ok(()),
+        false => Err(format!(
+            "is a {} with {} {}, it needs to be positive",
+            shape, name, value
+        )),
+    }
+}
+
+fn sweep(shape: &str, radians: Float) -> std::result::Result<(), String> {
+    // Allows for the rounding of 360 degrees
+    match radians > 0.0 && radians <= 2.0 * PI * (1.0 + EPSILON) {
+        true => Ok(()),
+        false => Err(format!(
+            "is a {} with a sweep of {} degrees, it needs to be between 0 and 360",
+            shape,
+            radians.to_degrees()
+        )),
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::hittables::{Instance, Mesh, Primitive};
+use crate::math::Matrix4;
+use crate::rendering::UberShader;
+
+/// Named group of a scene graph, placed relative to its parent. Every node with a mesh becomes an
+/// instance of it when the scene is built, so the graph can be as deep as needed without slowing
+/// down rendering.
+#[derive(Clone)]
+pub struct Node {
+    pub name: String,
+    /// Placement relative to the parent node
+    pub transform: Matrix4,
+    pub mesh: Option<Arc<Mesh>>,
+    /// Replaces the materials of the meshes of this node and its descendants, unless a
+    /// descendant overrides it again
+    pub material: Option<UberShader>,
+    /// Hidden nodes are left out of the scene together with their descendants
+    pub visible: bool,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(name: impl Into<String>) -> Node {
+        Node {
+            name: name.into(),
+            transform: Matrix4::IDENTITY,
+            mesh: None,
+            material: None,
+            visible: true,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Matrix4) -> Node {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_mesh(mut self, mesh: &Arc<Mesh>) -> Node {
+        self.mesh = Some(Arc::clone(mesh));
+        self
+    }
+
+    pub fn with_material(mut self, material: UberShader) -> Node {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn with_child(mut self, child: Node) -> Node {
+        self.children.push(child);
+        self
+    }
+
+    /// Descendant at a path of names separated by `/`, relative to this node.
+    pub fn find(&self, path: &str) -> Option<&Node> {
+        path.split('/').try_fold(self, |node, name| {
+            node.children.iter().find(|child| child.name == name)
+        })
+    }
+
+    pub fn find_mut(&mut self, path: &str) -> Option<&mut Node> {
+        path.split('/').try_fold(self, |node, name| {
+            node.children.iter_mut().find(|child| child.name == name)
+        })
+    }
+
+    /// Appends an instance for every visible mesh in the subtree. `path` names this node in
+    /// errors.
+    pub(crate) fn flatten(
+        &self,
+        path: &str,
+        parent: Matrix4,
+        material: Option<UberShader>,
+        instances: &mut Vec<Primitive>,
+    ) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        let to_world = parent * self.transform;
+        let material = self.material.or(material);
+        if let Some(mesh) = &self.mesh {
+            let instance = Instance::new(Arc::clone(mesh), to_world).ok_or_else(|| {
+                Error::InvalidScene(format!(
+                    "node {} has a transform that can't be inverted",
+                    path
+                ))
+            })?;
+            let instance = match material {
+                Some(material) => instance.with_material(material),
+                None => instance,
+            };
+            instances.push(instance.into());
+        }
+        for child in &self.children {
+            let child_path = format!("{}/{}", path, child.name);
+            child.flatten(&child_path, to_world, material, instances)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+pub use builder::SceneBuilder;
+pub use graph::Node;
+
+pub mod presets;
+
+mod builder;
+mod graph;
+
+use std::mem;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::hittables::{Bvh, Mesh, Primitive};
+use crate::rendering::{self, Background, Camera, Film, Fog, Light, RenderSettings};
+
+/// Summary of a scene printed by the `info` command.
+pub struct SceneStats {
+    /// Number of objects of every kind of primitive
+    pub primitives: Vec<(&'static str, usize)>,
+    /// Number of emissive objects
+    pub lights: usize,
+    /// Depth of the bounding volume hierarchy, 0 without one
+    pub bvh_depth: usize,
+    /// Bytes used by the scene and its objects
+    pub memory: usize,
+}
+
+impl SceneStats {
+    pub fn primitive_count(&self) -> usize {
+        self.primitives.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// Everything that is rendered: the objects and the camera looking at them.
+pub struct Scene {
+    pub camera: Camera,
+    /// Top level of the BVH, over the instances and the objects added directly
+    pub world: Bvh<Primitive>,
+    /// Emissive quads and disks added directly, sampled by every diffuse bounce
+    pub lights: Vec<Light>,
+    /// Radiance of rays that escape the scene
+    pub background: Background,
+    /// Medium around the objects, if the air isn't clear
+    pub fog: Option<Fog>,
+}
+
+impl Scene {
+    pub fn builder() -> SceneBuilder {
+        SceneBuilder::default()
+    }
+
+    /// Object counts and memory use of the scene. Meshes count once, no matter how many
+    /// instances place them.
+    pub fn stats(&self) -> SceneStats {
+        let mut primitives: Vec<(&'static str, usize)> = Vec::new();
+        let mut meshes: Vec<&Arc<Mesh>> = Vec::new();
+        let mut memory =
+            mem::size_of::<Scene>() - mem::size_of::<Bvh<Primitive>>() + self.world.memory();
+        let mut mesh_depth = 0;
+
+        let mut pending: Vec<&Primitive> = self.world.objects().iter().collect();
+        while let Some(primitive) = pending.pop() {
+            match primitives
+                .iter_mut()
+                .find(|(kind, _)| *kind == primitive.kind())
+            {
+                Some((_, count)) => *count += 1,
+                None => primitives.push((primitive.kind(), 1)),
+            }
+            if let Primitive::Instance(instance) = primitive {
+                let mesh = instance.mesh();
+                if !meshes.iter().any(|shared| Arc::ptr_eq(shared, mesh)) {
+                    meshes.push(mesh);
+                    memory += mesh.bvh().memory();
+                    mesh_depth = mesh_depth.max(mesh.bvh().depth());
+                    pending.extend(mesh.primitives());
+                }
+            }
+        }
+        primitives.sort_by_key(|(kind, _)| *kind);
+
+        SceneStats {
+            primitives,
+            lights: self.world.objects().iter().map(Primitive::lights).sum(),
+            // Rays go through the top level and then through the BVH of a mesh
+            bvh_depth: self.world.depth() + mesh_depth,
+            memory,
+        }
+    }
+
+    /// Renders the scene from scratch into a new film.
+    pub fn render(&self, settings: &RenderSettings) -> Result<Film> {
+        rendering::render(settings, self)
+    }
+}
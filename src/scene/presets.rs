@@ -0,0 +1,874 @@
+//! Built-in scenes.
+
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::hittables::{
+    cuboid, Block, Csg, DistanceField, Mesh, Primitive, Quadric, Sdf, Sphere, Torus, Volume,
+};
+use crate::math::{Color, Float, Matrix4, Point3, Vec3};
+use crate::rendering::{Background, Camera, DensityGrid, Fog, Medium, Texture, UberShader};
+use crate::scene::{Node, Scene};
+
+/// Scenes that ship with the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Preset {
+    /// Diffuse and metallic spheres on a large ground sphere
+    FourSpheres,
+    /// Closed box with a red and a blue wall, lit only by a light in the ceiling
+    CornellBox,
+    /// The original Cornell box, built from quads with two turned boxes and an area light
+    CornellQuads,
+    /// The quad Cornell box filled with thin smoke, with a block of dense smoke, a cloud and a
+    /// glass ball of tinted liquid
+    SmokyRoom,
+    /// Rows of columns fading into the haze of an open landscape
+    Haze,
+    /// Cumulus cloud from a voxel grid, lit by the sun over a meadow
+    Clouds,
+    /// Glass, water and diamond spheres in front of colored diffuse spheres
+    GlassSpheres,
+    /// Flint glass and diamond in front of thin white lights, with rainbow fringes when rendered
+    /// with `--spectral`
+    Dispersion,
+    /// Wax candle, marble block, skin-colored ball and jade ring lit from behind
+    Translucent,
+    /// Sphere on a checkered ground, seen at a grazing angle
+    TexturedPlane,
+    /// Cylinder, cone, paraboloid, hyperboloid and tori, some of them cut open
+    Quadrics,
+    /// Solids combined by union, intersection and difference, including a glass lens
+    Csg,
+    /// Mandelbulb, Menger sponge and shapes blended and twisted as signed distance fields
+    DistanceFields,
+    /// Rows of snowmen that are all instances of one mesh, arranged by a scene graph
+    Instances,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 14] = [
+        Preset::FourSpheres,
+        Preset::CornellBox,
+        Preset::CornellQuads,
+        Preset::SmokyRoom,
+        Preset::Haze,
+        Preset::Clouds,
+        Preset::GlassSpheres,
+        Preset::Dispersion,
+        Preset::Translucent,
+        Preset::TexturedPlane,
+        Preset::Quadrics,
+        Preset::Csg,
+        Preset::DistanceFields,
+        Preset::Instances,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::FourSpheres => "four-spheres",
+            Preset::CornellBox => "cornell-box",
+            Preset::CornellQuads => "cornell-quads",
+            Preset::SmokyRoom => "smoky-room",
+            Preset::Haze => "haze",
+            Preset::Clouds => "clouds",
+            Preset::GlassSpheres => "glass-spheres",
+            Preset::Dispersion => "dispersion",
+            Preset::Translucent => "translucent",
+            Preset::TexturedPlane => "textured-plane",
+            Preset::Quadrics => "quadrics",
+            Preset::Csg => "csg",
+            Preset::DistanceFields => "distance-fields",
+            Preset::Instances => "instances",
+        }
+    }
+
+    pub fn build(&self) -> Result<Scene> {
+        match self {
+            Preset::FourSpheres => four_spheres(),
+            Preset::CornellBox => cornell_box(),
+            Preset::CornellQuads => cornell_quads(),
+            Preset::SmokyRoom => smoky_room(),
+            Preset::Haze => haze(),
+            Preset::Clouds => clouds(),
+            Preset::GlassSpheres => glass_spheres(),
+            Preset::Dispersion => dispersion(),
+            Preset::Translucent => translucent(),
+            Preset::TexturedPlane => textured_plane(),
+            Preset::Quadrics => quadrics(),
+            Preset::Csg => csg(),
+            Preset::DistanceFields => distance_fields(),
+            Preset::Instances => instances(),
+        }
+    }
+}
+
+/// Ground sphere with a diffuse, a red metallic and a dark metallic sphere above it.
+pub fn four_spheres() -> Result<Scene> {
+    let material_ground = UberShader::new(
+        Color {
+            x: 0.8,
+            y: 0.8,
+            z: 0.0,
+        },
+        false,
+        0.0,
+    )
+    .with_id(1);
+    let material_center = UberShader::new(
+        Color {
+            x: 0.8,
+            y: 0.8,
+            z: 0.8,
+        },
+        false,
+        0.0,
+    )
+    .with_id(2);
+    let material_left = UberShader::new(
+        Color {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        true,
+        0.01,
+    )
+    .with_id(3);
+    let material_right = UberShader::new(
+        Color {
+            x: 0.1,
+            y: 0.1,
+            z: 0.1,
+        },
+        true,
+        0.0,
+    )
+    .with_id(4);
+
+    Scene::builder()
+        .sphere(Point3::new(0.0, -100.5, -5.0), 100.0, material_ground)
+        .sphere(Point3::new(3.0, 2.0, -5.0), 0.5, material_center)
+        .sphere(Point3::new(-1.0, 0.0, -5.0), 0.5, material_left)
+        .sphere(Point3::new(1.0, 0.0, -5.0), 0.5, material_right)
+        .build()
+}
+
+/// Cornell box in the style of smallpt, with the walls made of huge spheres. Square aspect ratio.
+pub fn cornell_box() -> Result<Scene> {
+    const WALL_RADIUS: Float = 1e5;
+    let white = UberShader::new(Color::new(0.75, 0.75, 0.75), false, 0.0).with_id(1);
+    let red = UberShader::new(Color::new(0.75, 0.25, 0.25), false, 0.0).with_id(2);
+    let blue = UberShader::new(Color::new(0.25, 0.25, 0.75), false, 0.0).with_id(3);
+    let mirror = UberShader::new(Color::new(0.999, 0.999, 0.999), true, 0.0).with_id(4);
+    let glass = UberShader::dielectric(1.5).with_id(5);
+    let light = UberShader::light(Color::new(12.0, 12.0, 12.0)).with_id(6);
+
+    let look_from = Point3::new(50.0, 52.0, 295.6);
+    Scene::builder()
+        .camera(Camera::look_at(
+            look_from,
+            look_from + Vec3::new(0.0, -0.042612, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            1.0,
+        ))
+        .background(Background::Uniform(Color::ZERO))
+        // Left, right, back, floor and ceiling, the front is open towards the camera
+        .sphere(Point3::new(1.0 - WALL_RADIUS, 40.8, 81.6), WALL_RADIUS, red)
+        .sphere(
+            Point3::new(99.0 + WALL_RADIUS, 40.8, 81.6),
+            WALL_RADIUS,
+            blue,
+        )
+        .sphere(Point3::new(50.0, 40.8, -WALL_RADIUS), WALL_RADIUS, white)
+        .sphere(Point3::new(50.0, -WALL_RADIUS, 81.6), WALL_RADIUS, white)
+        .sphere(
+            Point3::new(50.0, 81.6 + WALL_RADIUS, 81.6),
+            WALL_RADIUS,
+            white,
+        )
+        .sphere(Point3::new(27.0, 16.5, 47.0), 16.5, mirror)
+        .sphere(Point3::new(73.0, 16.5, 78.0), 16.5, glass)
+        // Only a small cap of the light pokes through the ceiling
+        .sphere(Point3::new(50.0, 681.6 - 0.27, 81.6), 600.0, light)
+        .build()
+}
+
+/// Cornell box with the measurements of the original, in millimeters. The boxes are instances of
+/// one unit cube mesh. Square aspect ratio.
+pub fn cornell_quads() -> Result<Scene> {
+    const SIZE: Float = 555.0;
+    let white = UberShader::new(Color::new(0.73, 0.73, 0.73), false, 0.0).with_id(1);
+    let red = UberShader::new(Color::new(0.65, 0.05, 0.05), false, 0.0).with_id(2);
+    let green = UberShader::new(Color::new(0.12, 0.45, 0.15), false, 0.0).with_id(3);
+    let light = UberShader::light(Color::new(15.0, 15.0, 15.0)).with_id(4);
+    let cube = Arc::new(Mesh::new(
+        cuboid(Point3::ORIGIN, Point3::new(1.0, 1.0, 1.0), white)
+            .into_iter()
+            .map(Primitive::Quad)
+            .collect(),
+    ));
+
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(278.0, 278.0, -800.0),
+            Point3::new(278.0, 278.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.0,
+        ))
+        .background(Background::Uniform(Color::ZERO))
+        // Left, right, floor, ceiling and back, the front is open towards the camera
+        .quad(
+            Point3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, SIZE, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            green,
+        )
+        .quad(
+            Point3::ORIGIN,
+            Vec3::new(0.0, SIZE, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            red,
+        )
+        .quad(
+            Point3::ORIGIN,
+            Vec3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            white,
+        )
+        .quad(
+            Point3::new(SIZE, SIZE, SIZE),
+            Vec3::new(-SIZE, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -SIZE),
+            white,
+        )
+        .quad(
+            Point3::new(0.0, 0.0, SIZE),
+            Vec3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, SIZE, 0.0),
+            white,
+        )
+        .quad(
+            Point3::new(343.0, SIZE - 1.0, 332.0),
+            Vec3::new(-130.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -105.0),
+            light,
+        )
+        .instance(
+            &cube,
+            Matrix4::translation(Vec3::new(265.0, 0.0, 295.0))
+                * Matrix4::rotation(Vec3::UP, 15.0)
+                * Matrix4::scaling(Vec3::new(165.0, 330.0, 165.0)),
+        )
+        .instance(
+            &cube,
+            Matrix4::translation(Vec3::new(130.0, 0.0, 65.0))
+                * Matrix4::rotation(Vec3::UP, -18.0)
+                * Matrix4::scaling(Vec3::new(165.0, 165.0, 165.0)),
+        )
+        .build()
+}
+
+/// The Cornell box of `cornell_quads` with smoke in the air, which shows the light falling from
+/// the ceiling, a block of dark smoke, a white cloud that scatters forward and a glass ball of
+/// liquid that absorbs red and green.
+pub fn smoky_room() -> Result<Scene> {
+    const SIZE: Float = 555.0;
+    let white = UberShader::new(Color::new(0.73, 0.73, 0.73), false, 0.0).with_id(1);
+    let red = UberShader::new(Color::new(0.65, 0.05, 0.05), false, 0.0).with_id(2);
+    let green = UberShader::new(Color::new(0.12, 0.45, 0.15), false, 0.0).with_id(3);
+    let light = UberShader::light(Color::new(15.0, 15.0, 15.0)).with_id(4);
+    let liquid = Medium::new(Color::new(0.02, 0.01, 0.001), Color::from_float(0.001));
+
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(278.0, 278.0, -800.0),
+            Point3::new(278.0, 278.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.0,
+        ))
+        .background(Background::Uniform(Color::ZERO))
+        .fog(Fog::new(
+            Medium::fog(0.0015, Color::from_float(0.9)),
+            1500.0,
+        ))
+        .quad(
+            Point3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, SIZE, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            green,
+        )
+        .quad(
+            Point3::ORIGIN,
+            Vec3::new(0.0, SIZE, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            red,
+        )
+        .quad(
+            Point3::ORIGIN,
+            Vec3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, SIZE),
+            white,
+        )
+        .quad(
+            Point3::new(SIZE, SIZE, SIZE),
+            Vec3::new(-SIZE, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -SIZE),
+            white,
+        )
+        .quad(
+            Point3::new(0.0, 0.0, SIZE),
+            Vec3::new(SIZE, 0.0, 0.0),
+            Vec3::new(0.0, SIZE, 0.0),
+            white,
+        )
+        .quad(
+            Point3::new(343.0, SIZE - 1.0, 332.0),
+            Vec3::new(-130.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -105.0),
+            light,
+        )
+        .volume(Volume::new(
+            Block::new(
+                Point3::new(330.0, 0.0, 250.0),
+                Point3::new(480.0, 300.0, 400.0),
+                white,
+            ),
+            Medium::fog(0.02, Color::from_float(0.3)),
+        ))
+        .volume(Volume::new(
+            Sphere::new(Point3::new(190.0, 330.0, 300.0), 90.0, white),
+            Medium::fog(0.06, Color::from_float(0.95)).with_asymmetry(0.6),
+        ))
+        .sphere(
+            Point3::new(150.0, 90.0, 150.0),
+            90.0,
+            UberShader::dielectric(1.33)
+                .with_interior(liquid)
+                .with_id(5),
+        )
+        .build()
+}
+
+/// Columns in rows along a plain under the sky, fading into haze in the distance.
+pub fn haze() -> Result<Scene> {
+    let stone = UberShader::new(Color::new(0.7, 0.65, 0.55), false, 0.0).with_id(2);
+    let mut builder = Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 2.0, 6.0),
+            Point3::new(0.0, 1.5, -10.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            50.0,
+            16.0 / 9.0,
+        ))
+        .fog(Fog::new(
+            Medium::fog(0.02, Color::new(0.85, 0.88, 0.95)).with_asymmetry(0.3),
+            80.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.4, 0.45, 0.3), false, 0.0).with_id(1),
+        );
+    for row in 0..12 {
+        for x in [-3.0, 3.0] {
+            builder = builder.quadric(
+                Quadric::cylinder(
+                    Point3::new(x, 0.0, -6.0 * row as Float),
+                    Vec3::new(0.0, 4.0, 0.0),
+                    0.4,
+                    stone,
+                )
+                .with_caps(),
+            );
+        }
+    }
+    builder.build()
+}
+
+/// Cloud of a voxel grid over a meadow, with the sun behind and to the side of the camera.
+pub fn clouds() -> Result<Scene> {
+    let grid = Arc::new(DensityGrid::from_fn([96, 40, 48], cloud_density));
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 1.5, 14.0),
+            Point3::new(0.0, 3.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            50.0,
+            16.0 / 9.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.3, 0.45, 0.2), false, 0.0).with_id(1),
+        )
+        .sphere(
+            Point3::new(60.0, 80.0, 70.0),
+            12.0,
+            UberShader::light(Color::new(15.0, 14.0, 11.0)).with_id(2),
+        )
+        .volume(Volume::from_grid(
+            grid,
+            Point3::new(-6.0, 2.0, -3.0),
+            Point3::new(6.0, 7.0, 3.0),
+            Medium::fog(8.0, Color::from_float(0.98)).with_asymmetry(0.5),
+        ))
+        .build()
+}
+
+/// Density of a cumulus in the unit cube: round puffs merged over a flat base, with waves eaten
+/// out of the edges for detail.
+fn cloud_density(point: Point3) -> Float {
+    const PUFFS: [(Float, Float, Float, Float); 7] = [
+        (0.5, 0.45, 0.5, 0.3),
+        (0.3, 0.35, 0.45, 0.22),
+        (0.7, 0.35, 0.55, 0.22),
+        (0.15, 0.25, 0.5, 0.14),
+        (0.85, 0.25, 0.45, 0.13),
+        (0.45, 0.7, 0.5, 0.2),
+        (0.6, 0.6, 0.4, 0.18),
+    ];
+    let shape = PUFFS
+        .iter()
+        .map(|&(x, y, z, radius)| {
+            // The grid is flatter along y and z than along x
+            let offset = Vec3::new(x - point.x, (y - point.y) * 0.4, (z - point.z) * 0.5);
+            1.0 - offset.squared_length() / (radius * radius)
+        })
+        .fold(0.0, Float::max);
+    if shape <= 0.0 {
+        return 0.0;
+    }
+    let base = ((point.y - 0.12) / 0.08).clamp(0.0, 1.0);
+    let detail = (point.x * 47.0).sin() * (point.y * 31.0 + point.z * 13.0).sin()
+        + 0.5 * (point.z * 71.0 + point.x * 11.0).sin() * (point.y * 67.0).sin();
+    ((shape * 3.0 - 0.25 * detail - 0.2) * base).clamp(0.0, 1.0)
+}
+
+/// Glass, water and diamond spheres on a gray ground, with diffuse spheres behind them to show
+/// the refraction.
+pub fn glass_spheres() -> Result<Scene> {
+    let ground = UberShader::new(Color::new(0.5, 0.5, 0.5), false, 0.0).with_id(1);
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 2.0, 8.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            30.0,
+            16.0 / 9.0,
+        ))
+        .sphere(Point3::new(0.0, -1000.0, 0.0), 1000.0, ground)
+        .sphere(
+            Point3::new(-2.2, 1.0, 0.0),
+            1.0,
+            UberShader::dielectric(1.5).with_id(2),
+        )
+        .sphere(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            UberShader::dielectric(1.33).with_id(3),
+        )
+        .sphere(
+            Point3::new(2.2, 1.0, 0.0),
+            1.0,
+            UberShader::dielectric(2.42).with_id(4),
+        )
+        .sphere(
+            Point3::new(-1.2, 0.7, -4.0),
+            0.7,
+            UberShader::new(Color::new(0.8, 0.2, 0.1), false, 0.0).with_id(5),
+        )
+        .sphere(
+            Point3::new(1.2, 0.7, -4.0),
+            0.7,
+            UberShader::new(Color::new(0.1, 0.3, 0.8), false, 0.0).with_id(6),
+        )
+        .build()
+}
+
+/// A dense flint glass sphere, a flint glass cylinder and a diamond in front of a row of thin white
+/// lights in the dark. The glass bends the strips and, in spectral mode, splits their edges into
+/// colors.
+pub fn dispersion() -> Result<Scene> {
+    let flint = UberShader::dielectric(1.72).with_abbe_number(20.0);
+    let mut builder = Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 1.6, 7.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            35.0,
+            16.0 / 9.0,
+        ))
+        .background(Background::Uniform(Color::ZERO))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.2, 0.2, 0.2), false, 0.0).with_id(1),
+        )
+        .sphere(Point3::new(-2.0, 0.9, 0.0), 0.9, flint.with_id(3))
+        .quadric(
+            Quadric::cylinder(
+                Point3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.8, 0.0),
+                0.6,
+                flint.with_id(4),
+            )
+            .with_caps(),
+        )
+        .sphere(
+            Point3::new(2.0, 0.9, 0.0),
+            0.9,
+            UberShader::dielectric(2.42)
+                .with_abbe_number(55.0)
+                .with_id(5),
+        );
+    for strip in 0..9 {
+        builder = builder.quad(
+            Point3::new(-4.0 + strip as Float, 0.0, -3.0),
+            Vec3::new(0.08, 0.0, 0.0),
+            Vec3::new(0.0, 4.0, 0.0),
+            UberShader::light(Color::from_float(20.0)).with_id(2),
+        );
+    }
+    builder.build()
+}
+
+/// Translucent objects on a dark ground with a large light behind them, so light shows through
+/// their thin parts. The mean free paths are in the units of the scene, about a decimeter.
+pub fn translucent() -> Result<Scene> {
+    let wax = UberShader::subsurface(Color::new(0.95, 0.85, 0.6), Color::new(0.5, 0.3, 0.15));
+    let marble = UberShader::subsurface(Color::new(0.92, 0.9, 0.86), Color::new(0.12, 0.12, 0.1));
+    let skin = UberShader::subsurface(Color::new(0.85, 0.6, 0.5), Color::new(0.37, 0.14, 0.08));
+    let jade = UberShader::subsurface(Color::new(0.35, 0.75, 0.45), Color::new(0.2, 0.5, 0.25));
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 2.5, 7.0),
+            Point3::new(0.0, 0.8, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .background(Background::Uniform(Color::from_float(0.15)))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.3, 0.3, 0.3), false, 0.0).with_id(1),
+        )
+        .quad(
+            Point3::new(-3.0, 3.0, -2.5),
+            Vec3::new(6.0, 0.0, 0.0),
+            Vec3::new(0.0, -1.0, 1.5),
+            UberShader::light(Color::new(8.0, 8.0, 8.0)).with_id(2),
+        )
+        .quadric(
+            Quadric::cylinder(
+                Point3::new(-2.7, 0.0, 0.0),
+                Vec3::new(0.0, 1.8, 0.0),
+                0.45,
+                wax.with_id(3),
+            )
+            .with_caps(),
+        )
+        .block(
+            Point3::new(-1.5, 0.0, -0.6),
+            Point3::new(-0.3, 1.0, 0.4),
+            marble.with_id(4),
+        )
+        .sphere(Point3::new(1.0, 0.7, 0.2), 0.7, skin.with_id(5))
+        .torus(Torus::new(
+            Point3::new(2.8, 0.65, -0.2),
+            Vec3::new(0.0, 0.2, 1.0),
+            0.5,
+            0.15,
+            jade.with_id(6),
+        ))
+        .build()
+}
+
+/// Diffuse sphere on a checkered ground that recedes to the horizon.
+pub fn textured_plane() -> Result<Scene> {
+    let checker = Texture::Checker {
+        even: Color::new(0.9, 0.9, 0.9),
+        odd: Color::new(0.2, 0.3, 0.1),
+        scale: 0.5,
+    };
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 1.5, 6.0),
+            Point3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::textured(checker).with_id(1),
+        )
+        .sphere(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            UberShader::new(Color::new(0.7, 0.5, 0.3), false, 0.0).with_id(2),
+        )
+        .build()
+}
+
+/// One of every quadric and a torus in a row, with a half torus arching over them. The
+/// paraboloid is cut open to show its inside.
+pub fn quadrics() -> Result<Scene> {
+    let up = Vec3::new(0.0, 1.5, 0.0);
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 3.0, 8.0),
+            Point3::new(0.0, 0.7, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.5, 0.5, 0.5), false, 0.0).with_id(1),
+        )
+        .quadric(
+            Quadric::cylinder(
+                Point3::new(-4.0, 0.0, 0.0),
+                up,
+                0.5,
+                UberShader::new(Color::new(0.2, 0.3, 0.7), false, 0.0).with_id(2),
+            )
+            .with_caps(),
+        )
+        .quadric(
+            Quadric::cone(
+                Point3::new(-2.0, 0.0, 0.0),
+                up,
+                0.6,
+                UberShader::new(Color::new(0.7, 0.2, 0.2), false, 0.0).with_id(3),
+            )
+            .with_caps(),
+        )
+        .quadric(
+            Quadric::paraboloid(
+                Point3::new(0.0, 0.2, 0.0),
+                Vec3::new(0.0, 1.2, 0.0),
+                0.7,
+                UberShader::new(Color::new(0.9, 0.7, 0.3), true, 0.1).with_id(4),
+            )
+            .with_sweep(270.0),
+        )
+        .quadric(Quadric::hyperboloid(
+            Point3::new(2.0, 0.0, 0.0),
+            up,
+            0.6,
+            0.3,
+            UberShader::new(Color::new(0.2, 0.6, 0.3), false, 0.0).with_id(5),
+        ))
+        .torus(Torus::new(
+            Point3::new(4.0, 0.6, 0.0),
+            Vec3::new(0.3, 1.0, 0.8),
+            0.5,
+            0.2,
+            UberShader::glossy(Color::new(0.8, 0.8, 0.8), 0.3).with_id(6),
+        ))
+        .torus(
+            Torus::new(
+                Point3::new(0.0, 0.0, -2.5),
+                Vec3::new(0.0, 0.0, 1.0),
+                3.0,
+                0.15,
+                UberShader::new(Color::new(0.8, 0.8, 0.8), true, 0.0).with_id(7),
+            )
+            .with_sweep(180.0),
+        )
+        .build()
+}
+
+/// The classic CSG example of a box intersected with a sphere and drilled through along every
+/// axis, a glass lens made of two spheres, a block with a bite taken out and a ring fused to a
+/// post.
+pub fn csg() -> Result<Scene> {
+    let red = UberShader::new(Color::new(0.8, 0.2, 0.2), false, 0.0).with_id(2);
+    let blue = UberShader::new(Color::new(0.2, 0.3, 0.8), false, 0.0).with_id(3);
+    let green = UberShader::new(Color::new(0.2, 0.7, 0.3), false, 0.0).with_id(4);
+    let gold = UberShader::new(Color::new(0.9, 0.7, 0.3), true, 0.2).with_id(5);
+
+    let center = Point3::new(-1.6, 0.8, 0.0);
+    let drill = |axis: Vec3| Quadric::cylinder(center - axis, 2.0 * axis, 0.35, green).with_caps();
+    let rounded_box = Csg::intersection(
+        Block::new(
+            center - Vec3::from_float(0.6),
+            center + Vec3::from_float(0.6),
+            red,
+        ),
+        Sphere::new(center, 0.8, blue),
+    );
+    let drilled = Csg::difference(
+        Csg::difference(
+            Csg::difference(rounded_box, drill(Vec3::new(1.0, 0.0, 0.0))),
+            drill(Vec3::new(0.0, 1.0, 0.0)),
+        ),
+        drill(Vec3::new(0.0, 0.0, 1.0)),
+    );
+
+    let glass = UberShader::dielectric(1.5).with_id(6);
+    let lens = Csg::intersection(
+        Sphere::new(Point3::new(0.0, 0.8, -1.5), 1.0, glass),
+        Sphere::new(Point3::new(0.0, 0.8, -0.1), 1.0, glass),
+    );
+
+    let bitten = Csg::difference(
+        Block::new(Point3::new(0.6, 0.0, 0.3), Point3::new(1.6, 0.8, 1.3), blue),
+        Sphere::new(Point3::new(1.6, 0.8, 1.3), 0.5, blue),
+    );
+
+    let ring = Csg::union(
+        Torus::new(
+            Point3::new(2.4, 1.2, -0.8),
+            Vec3::new(0.0, 0.0, 1.0),
+            0.45,
+            0.12,
+            gold,
+        ),
+        Quadric::cylinder(
+            Point3::new(2.4, 0.0, -0.8),
+            Vec3::new(0.0, 0.8, 0.0),
+            0.12,
+            gold,
+        )
+        .with_caps(),
+    );
+
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 2.5, 6.0),
+            Point3::new(0.3, 0.6, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.5, 0.5, 0.5), false, 0.0).with_id(1),
+        )
+        .csg(drilled)
+        .csg(lens)
+        .csg(bitten)
+        .csg(ring)
+        .build()
+}
+
+/// Fractals and blended shapes, all ray marched: a Mandelbulb, a Menger sponge, a twisted
+/// rounded bar, spheres melted into a torus and a row of capsules.
+pub fn distance_fields() -> Result<Scene> {
+    let blob = Sdf::torus(0.5, 0.15)
+        .union(Sdf::sphere(0.3).translate(Vec3::new(0.0, 0.25, 0.0)), 0.3)
+        .union(Sdf::sphere(0.2).translate(Vec3::new(0.5, 0.1, 0.0)), 0.2)
+        .subtraction(Sdf::sphere(0.15).translate(Vec3::new(0.0, 0.55, 0.0)), 0.1);
+    let bar = Sdf::rounded_cuboid(Vec3::new(0.25, 0.8, 0.25), 0.05).twist(1.5);
+    let capsules = Sdf::capsule(Point3::new(0.0, -0.2, 0.0), Point3::new(0.0, 0.2, 0.0), 0.1)
+        .rotate(Vec3::new(0.0, 0.0, 1.0), 30.0)
+        .repeat(Vec3::new(0.4, 0.0, 0.0), [3, 0, 0]);
+
+    Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 2.2, 6.0),
+            Point3::new(0.0, 0.8, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .plane(
+            Point3::ORIGIN,
+            Vec3::UP,
+            UberShader::new(Color::new(0.5, 0.5, 0.5), false, 0.0).with_id(1),
+        )
+        .distance_field(DistanceField::new(
+            Sdf::mandelbulb(8.0, 10)
+                .scale(0.9)
+                .translate(Vec3::new(-2.4, 1.1, -0.5)),
+            UberShader::new(Color::new(0.8, 0.4, 0.2), false, 0.0).with_id(2),
+        ))
+        .distance_field(DistanceField::new(
+            Sdf::menger_sponge(4)
+                .scale(0.8)
+                .rotate(Vec3::UP, 30.0)
+                .translate(Vec3::new(2.3, 0.8, -0.5)),
+            UberShader::new(Color::new(0.7, 0.7, 0.75), false, 0.0).with_id(3),
+        ))
+        .distance_field(DistanceField::new(
+            bar.translate(Vec3::new(0.0, 0.85, -1.0)),
+            UberShader::new(Color::new(0.9, 0.7, 0.3), true, 0.2).with_id(4),
+        ))
+        .distance_field(DistanceField::new(
+            blob.translate(Vec3::new(-0.7, 0.15, 1.2)),
+            UberShader::new(Color::new(0.2, 0.5, 0.8), false, 0.0).with_id(5),
+        ))
+        .distance_field(DistanceField::new(
+            capsules.translate(Vec3::new(1.0, 0.2, 1.5)),
+            UberShader::new(Color::new(0.3, 0.7, 0.3), false, 0.0).with_id(6),
+        ))
+        .build()
+}
+
+/// A field of 15 by 15 snowmen, turned and scaled differently, that all share one mesh. One row
+/// is golden through a material override and one snowman is hidden.
+pub fn instances() -> Result<Scene> {
+    const COUNT: usize = 15;
+    const SPACING: Float = 1.6;
+    let snow = UberShader::new(Color::new(0.9, 0.9, 0.9), false, 0.0).with_id(2);
+    let coal = UberShader::new(Color::new(0.05, 0.05, 0.05), true, 0.2).with_id(3);
+    let snowman = Arc::new(Mesh::new(vec![
+        Sphere::new(Point3::new(0.0, 0.5, 0.0), 0.5, snow).into(),
+        Sphere::new(Point3::new(0.0, 1.15, 0.0), 0.3, snow).into(),
+        Sphere::new(Point3::new(0.0, 1.2, 0.28), 0.06, coal).into(),
+    ]));
+
+    let mut field = Node::new("field");
+    for row in 0..COUNT {
+        let mut row_node = Node::new(format!("row-{}", row)).with_transform(Matrix4::translation(
+            Vec3::new(0.0, 0.0, -(row as Float) * SPACING),
+        ));
+        for column in 0..COUNT {
+            let offset = (column as Float - (COUNT - 1) as Float / 2.0) * SPACING;
+            let scale = 0.8 + 0.2 * ((row + column) % 3) as Float;
+            row_node = row_node.with_child(
+                Node::new(format!("snowman-{}", column))
+                    .with_mesh(&snowman)
+                    .with_transform(
+                        Matrix4::translation(Vec3::new(offset, 0.0, 0.0))
+                            * Matrix4::rotation(Vec3::UP, (row * COUNT + column) as Float * 37.0)
+                            * Matrix4::scaling(Vec3::from_float(scale)),
+                    ),
+            );
+        }
+        field = field.with_child(row_node);
+    }
+
+    let mut builder = Scene::builder()
+        .camera(Camera::look_at(
+            Point3::new(0.0, 6.0, 9.0),
+            Point3::new(0.0, 0.0, -8.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            16.0 / 9.0,
+        ))
+        .sphere(
+            Point3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            UberShader::new(Color::new(0.4, 0.5, 0.6), false, 0.0).with_id(1),
+        )
+        .node(field);
+    let gold = UberShader::new(Color::new(0.9, 0.7, 0.3), true, 0.1).with_id(4);
+    if let Some(row) = builder.node_mut("field/row-2") {
+        row.material = Some(gold);
+    }
+    if let Some(snowman) = builder.node_mut("field/row-1/snowman-7") {
+        snowman.visible = false;
+    }
+    builder.build()
+}
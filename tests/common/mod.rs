@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests. Each test file uses only some of them.
+
+#![allow(dead_code)]
+
+use raytracing_rust::hittables::Sphere;
+use raytracing_rust::rendering::UberShader;
+use raytracing_rust::{Float, Point3};
+
+/// `tolerance`, but no tighter than what an f32 build can resolve.
+pub fn tolerance(tolerance: Float) -> Float {
+    match cfg!(feature = "f32") {
+        true => tolerance.max(1e-4),
+        false => tolerance,
+    }
+}
+
+pub fn unit_sphere() -> Sphere {
+    Sphere::new(Point3::ORIGIN, 1.0, UberShader::default())
+}
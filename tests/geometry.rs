@@ -0,0 +1,1359 @@
+//! Intersections of primitives and of transformed and instanced geometry.
+
+use std::array;
+use std::sync::Arc;
+
+use raytracing_rust::hittables::{
+    cuboid, Block, Bvh, Csg, CsgOperation, Disk, DistanceField, HitRecord, Instance, Mesh, Plane,
+    Primitive, Quad, Quadric, Sdf, Solid, Sphere, Torus, Transform, Volume,
+};
+use raytracing_rust::math::{self, random_scalar, seed_rng, Matrix4};
+use raytracing_rust::math::{roots_between, Aabb, RayPacket, LANES};
+use raytracing_rust::rendering::{read_grid, write_grid, DensityGrid, Fog, Medium, UberShader};
+use raytracing_rust::scene::Node;
+use raytracing_rust::{
+    Color, Error, Float, Hittable, HittableList, Normal3, Point3, Ray, Scene, Vec3,
+};
+
+mod common;
+use common::{tolerance, unit_sphere};
+
+const EPSILON: Float = math::EPSILON;
+
+/// Compares points, vectors or normals by their coordinates.
+fn assert_close(actual: impl Into<Vec3>, expected: impl Into<Vec3>, what: &str) {
+    let (actual, expected) = (actual.into(), expected.into());
+    assert!(
+        (actual - expected).length() < tolerance(1e-6),
+        "{} is {:?}, expected {:?}",
+        what,
+        actual,
+        expected
+    );
+}
+
+/// Ray from a random point around the origin towards a random point close to it.
+fn random_ray() -> Ray {
+    let origin = Point3::ORIGIN + 6.0 * Vec3::random_point_in_unit_vector();
+    let target = Point3::ORIGIN + 2.5 * Vec3::random_point_in_unit_sphere();
+    Ray::new(origin, target - origin)
+}
+
+fn hit(object: &impl Hittable, ray: &Ray) -> Option<HitRecord> {
+    object.hit(ray, EPSILON, Float::INFINITY)
+}
+
+#[test]
+fn matrix_inverse_undoes_the_transform() {
+    let matrix = Matrix4::translation(Vec3::new(1.0, -2.0, 3.0))
+        * Matrix4::rotation(Vec3::new(1.0, 1.0, 0.0), 35.0)
+        * Matrix4::scaling(Vec3::new(2.0, 0.5, 3.0));
+    let inverse = matrix.inverse().expect("the matrix is invertible");
+    for (row, identity_row) in (matrix * inverse).rows.iter().zip(Matrix4::IDENTITY.rows) {
+        for (value, expected) in row.iter().zip(identity_row) {
+            assert!((value - expected).abs() < EPSILON, "{:?}", matrix * inverse);
+        }
+    }
+
+    let point = Point3::new(0.3, 4.0, -1.5);
+    assert_close(
+        inverse.transform_point(matrix.transform_point(point)),
+        point,
+        "round trip",
+    );
+    assert!(Matrix4::scaling(Vec3::new(1.0, 0.0, 1.0))
+        .inverse()
+        .is_none());
+}
+
+#[test]
+fn rotation_is_counterclockwise() {
+    let rotation = Matrix4::rotation(Vec3::new(0.0, 0.0, 1.0), 90.0);
+    assert_close(
+        rotation.transform_vector(Vec3::new(1.0, 0.0, 0.0)),
+        Vec3::new(0.0, 1.0, 0.0),
+        "x rotated around z",
+    );
+}
+
+#[test]
+fn normals_stay_perpendicular_when_scaled_unevenly() {
+    let matrix = Matrix4::rotation(Vec3::new(0.2, 1.0, -0.4), 50.0)
+        * Matrix4::scaling(Vec3::new(3.0, 0.25, 1.0));
+    let inverse = matrix.inverse().unwrap();
+    let tangent = Vec3::new(1.0, -1.0, 0.0);
+    let normal: Normal3 = Normal3::new(1.0, 1.0, 0.0).unit_vector();
+    assert!(normal.dot(tangent).abs() < EPSILON);
+    let transformed = inverse.transform_normal(normal);
+    assert!(transformed.dot(matrix.transform_vector(tangent)).abs() < EPSILON);
+    // Transformed like a vector, the normal leans over
+    assert!(
+        Vec3::dot_product(
+            &matrix.transform_vector(Vec3::from(normal)),
+            &matrix.transform_vector(tangent)
+        )
+        .abs()
+            > 0.1
+    );
+
+    let (a, b) = (Point3::new(1.0, 2.0, 3.0), Point3::new(-2.0, 0.5, 4.0));
+    assert_close(a + (b - a), b, "point moved by the vector between points");
+    assert_close(
+        Point3::midpoint(a, b),
+        Point3::new(-0.5, 1.25, 3.5),
+        "midpoint",
+    );
+    assert!((Point3::distance(a, b) - (b - a).length()).abs() < EPSILON);
+}
+
+#[test]
+fn transformed_sphere_matches_moved_sphere() {
+    seed_rng(1);
+    let center = Point3::new(0.5, -0.5, 1.0);
+    let transformed = Transform::new(
+        unit_sphere(),
+        Matrix4::translation(center - Point3::ORIGIN) * Matrix4::scaling(Vec3::from_float(2.0)),
+    )
+    .unwrap();
+    let moved = Sphere::new(center, 2.0, UberShader::default());
+
+    for _ in 0..1000 {
+        let ray = random_ray();
+        match (hit(&transformed, &ray), hit(&moved, &ray)) {
+            (Some(actual), Some(expected)) => {
+                assert!((actual.t - expected.t).abs() < 1e-6);
+                assert_close(actual.point, expected.point, "hit point");
+                assert_close(actual.normal, expected.normal, "normal");
+                assert_eq!(actual.front_face, expected.front_face);
+            }
+            (None, None) => {}
+            (actual, expected) => panic!(
+                "{:?}: transformed sphere hit {}, moved sphere hit {}",
+                ray,
+                actual.is_some(),
+                expected.is_some()
+            ),
+        }
+    }
+}
+
+#[test]
+fn normals_of_scaled_sphere_are_perpendicular() {
+    seed_rng(2);
+    let radii = Vec3::new(2.0, 0.5, 1.0);
+    let rotation = Matrix4::rotation(Vec3::new(1.0, 2.0, 3.0), 60.0);
+    let ellipsoid = Transform::new(unit_sphere(), rotation * Matrix4::scaling(radii)).unwrap();
+    let unrotate = rotation.inverse().unwrap();
+
+    let mut hits = 0;
+    for _ in 0..1000 {
+        let ray = random_ray();
+        let Some(record) = hit(&ellipsoid, &ray) else {
+            continue;
+        };
+        hits += 1;
+        // Gradient of x²/a² + y²/b² + z²/c² = 1 in the frame of the ellipsoid
+        let local = unrotate.transform_point(record.point);
+        let gradient = Vec3::new(
+            local.x / (radii.x * radii.x),
+            local.y / (radii.y * radii.y),
+            local.z / (radii.z * radii.z),
+        );
+        let outward = rotation.transform_vector(gradient).unit_vector();
+        let expected = match record.front_face {
+            true => outward,
+            false => -outward,
+        };
+        assert_close(record.normal, expected, "normal");
+        assert!(record.normal.dot(ray.direction) <= 0.0);
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn instances_share_their_mesh() {
+    let mesh = Arc::new(Mesh::new(vec![
+        Primitive::Sphere(unit_sphere()),
+        Primitive::Sphere(Sphere::new(
+            Point3::new(0.0, 1.5, 0.0),
+            0.5,
+            UberShader::light(Color::ONE),
+        )),
+    ]));
+    let mut builder = Scene::builder();
+    for index in 0..1000 {
+        let offset = Vec3::new((index % 10) as Float, 0.0, (index / 10) as Float) * 4.0;
+        builder = builder.instance(&mesh, Matrix4::translation(offset));
+    }
+    let scene = builder.build().unwrap();
+    assert_eq!(Arc::strong_count(&mesh), 1001);
+
+    let stats = scene.stats();
+    assert_eq!(
+        stats.primitives,
+        vec![("instances", 1000), ("spheres", 2)],
+        "the mesh is counted once"
+    );
+    assert_eq!(stats.lights, 1000);
+
+    // The mesh is hit where the instance placed it
+    let ray = Ray::new(Point3::new(8.0, 0.0, 14.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = hit(&scene.world, &ray).expect("the ray hits the instance at (8, 0, 12)");
+    assert_close(record.point, Point3::new(8.0, 0.0, 13.0), "hit point");
+}
+
+#[test]
+fn singular_instances_are_rejected() {
+    let mesh = Arc::new(Mesh::new(vec![Primitive::Sphere(unit_sphere())]));
+    let result = Scene::builder()
+        .sphere(Point3::ORIGIN, 1.0, UberShader::default())
+        .instance(&mesh, Matrix4::scaling(Vec3::new(1.0, 0.0, 1.0)))
+        .build();
+    assert!(
+        matches!(result, Err(Error::InvalidScene(message)) if message.starts_with("object 2 "))
+    );
+}
+
+#[test]
+fn bvh_finds_the_closest_hit() {
+    seed_rng(3);
+    let spheres: Vec<Sphere> = (0..500)
+        .map(|_| {
+            Sphere::new(
+                Point3::ORIGIN + 3.0 * Vec3::random_point_in_unit_sphere(),
+                0.02 + 0.2 * random_scalar(),
+                UberShader::default(),
+            )
+        })
+        .collect();
+    let list = HittableList {
+        objects: spheres.clone(),
+    };
+    let bvh = Bvh::new(spheres);
+    assert!(bvh.depth() > 1);
+
+    for _ in 0..2000 {
+        let ray = random_ray();
+        match (hit(&bvh, &ray), hit(&list, &ray)) {
+            (Some(actual), Some(expected)) => {
+                assert_eq!(actual.t, expected.t);
+                assert_eq!(actual.object_id, expected.object_id);
+            }
+            (None, None) => {}
+            (actual, expected) => panic!(
+                "{:?}: BVH hit {}, list hit {}",
+                ray,
+                actual.is_some(),
+                expected.is_some()
+            ),
+        }
+    }
+}
+
+/// Scene graph with one unit sphere mesh, `arm` and `hand` are placed relative to `robot`.
+fn robot_graph() -> (Node, UberShader, UberShader) {
+    let mesh = Arc::new(Mesh::new(vec![Primitive::Sphere(unit_sphere())]));
+    let red = UberShader::new(Color::new(1.0, 0.0, 0.0), false, 0.0).with_id(1);
+    let blue = UberShader::new(Color::new(0.0, 0.0, 1.0), false, 0.0).with_id(2);
+    let robot = Node::new("robot")
+        .with_transform(Matrix4::translation(Vec3::new(10.0, 0.0, 0.0)))
+        .with_material(red)
+        .with_child(
+            Node::new("arm")
+                .with_transform(Matrix4::translation(Vec3::new(0.0, 5.0, 0.0)))
+                .with_mesh(&mesh)
+                .with_child(
+                    Node::new("hand")
+                        .with_transform(Matrix4::translation(Vec3::new(0.0, 5.0, 0.0)))
+                        .with_material(blue)
+                        .with_mesh(&mesh),
+                ),
+        );
+    (robot, red, blue)
+}
+
+/// Hit of a ray that comes straight down from high above `x, z`.
+fn hit_from_above(scene: &Scene, x: Float, z: Float) -> Option<HitRecord> {
+    let ray = Ray::new(Point3::new(x, 100.0, z), Vec3::new(0.0, -1.0, 0.0));
+    hit(&scene.world, &ray)
+}
+
+#[test]
+fn packets_find_the_same_hits_as_single_rays() {
+    seed_rng(5);
+    let mesh = Arc::new(Mesh::new(
+        (0..20)
+            .map(|_| {
+                Primitive::Sphere(Sphere::new(
+                    Point3::ORIGIN + Vec3::random_point_in_unit_sphere(),
+                    0.1 + 0.2 * random_scalar(),
+                    UberShader::default(),
+                ))
+            })
+            .collect(),
+    ));
+    let mut objects: Vec<Primitive> = (0..30)
+        .map(|index| {
+            let to_world = Matrix4::translation(2.0 * Vec3::random_point_in_unit_sphere())
+                * Matrix4::rotation(Vec3::new(0.0, 1.0, 1.0), 12.0 * index as Float)
+                * Matrix4::scaling(Vec3::new(0.3, 0.5, 0.4));
+            let instance = Instance::new(mesh.clone(), to_world).unwrap();
+            Primitive::Instance(Box::new(match index % 3 {
+                0 => instance.with_material(UberShader::light(Color::ONE)),
+                _ => instance,
+            }))
+        })
+        .collect();
+    objects.push(Primitive::Plane(Plane::new(
+        Point3::new(0.0, -2.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        UberShader::default(),
+    )));
+    let bvh = Bvh::new(objects);
+
+    for packet in 0..500 {
+        // Rays that start together like camera rays, and rays that go anywhere
+        let rays: [Ray; LANES] = match packet % 2 {
+            0 => {
+                let origin = Point3::new(0.0, 0.5, 6.0);
+                let target = Point3::ORIGIN + 2.0 * Vec3::random_point_in_unit_sphere();
+                array::from_fn(|_| {
+                    let jitter = 0.1 * Vec3::random_point_in_unit_sphere();
+                    Ray::new(origin, target + jitter - origin)
+                })
+            }
+            _ => array::from_fn(|_| random_ray()),
+        };
+        let mut t_max = [Float::INFINITY; LANES];
+        // Rays with a `t_max` below `t_min` are left out
+        t_max[1] = Float::NEG_INFINITY;
+        let mut hits = array::from_fn(|_| None);
+        bvh.hit_packet(&RayPacket::new(rays), EPSILON, &mut t_max, &mut hits);
+
+        for (lane, ray) in rays.iter().enumerate() {
+            let expected = match lane {
+                1 => None,
+                _ => hit(&bvh, ray),
+            };
+            match (&hits[lane], expected) {
+                (Some(actual), Some(expected)) => {
+                    assert_eq!(actual.t, expected.t);
+                    assert_eq!(t_max[lane], expected.t);
+                    assert_eq!(actual.object_id, expected.object_id);
+                    assert_eq!(actual.material.id(), expected.material.id());
+                    assert_eq!(actual.point, expected.point);
+                    assert_eq!(actual.normal, expected.normal);
+                }
+                (None, None) => {}
+                (actual, expected) => panic!(
+                    "{:?}: packet hit {}, single ray hit {}",
+                    ray,
+                    actual.is_some(),
+                    expected.is_some()
+                ),
+            }
+        }
+    }
+}
+
+#[test]
+fn scene_graph_nodes_inherit_transforms_and_materials() {
+    let (robot, red, blue) = robot_graph();
+    assert!(robot.find("arm/hand").is_some());
+    assert!(robot.find("arm/foot").is_none());
+    let scene = Scene::builder().node(robot).build().unwrap();
+    assert_eq!(scene.world.objects().len(), 2);
+
+    // The hand sits on top of the arm, translated by both of its ancestors
+    let record = hit_from_above(&scene, 10.0, 0.0).unwrap();
+    assert_close(
+        record.point,
+        Point3::new(10.0, 11.0, 0.0),
+        "top of the hand",
+    );
+    assert_eq!(
+        record.material.id(),
+        blue.id(),
+        "the hand overrides the material"
+    );
+
+    let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let record = hit(&scene.world, &ray).unwrap();
+    assert_close(record.point, Point3::new(9.0, 5.0, 0.0), "side of the arm");
+    assert_eq!(
+        record.material.id(),
+        red.id(),
+        "the arm inherits the material"
+    );
+}
+
+#[test]
+fn hidden_nodes_are_left_out() {
+    let (robot, _, _) = robot_graph();
+    let mut builder = Scene::builder().node(robot);
+    builder.node_mut("robot/arm/hand").unwrap().visible = false;
+    let scene = builder.build().unwrap();
+    assert_eq!(scene.world.objects().len(), 1);
+    let record = hit_from_above(&scene, 10.0, 0.0).unwrap();
+    assert_close(record.point, Point3::new(10.0, 6.0, 0.0), "top of the arm");
+
+    let (robot, _, _) = robot_graph();
+    let mut builder = Scene::builder().node(robot);
+    builder.node_mut("robot").unwrap().visible = false;
+    assert!(builder.build().unwrap().world.objects().is_empty());
+}
+
+#[test]
+fn singular_nodes_are_reported_by_path() {
+    let (mut robot, _, _) = robot_graph();
+    robot.find_mut("arm/hand").unwrap().transform = Matrix4::scaling(Vec3::ZERO);
+    let result = Scene::builder().node(robot).build();
+    assert!(
+        matches!(result, Err(Error::InvalidScene(message)) if message.contains("robot/arm/hand"))
+    );
+}
+
+/// Allows for rounding, points on flat faces are rarely exactly on them.
+fn contains(bounds: &Aabb, point: Point3) -> bool {
+    (0..3).all(|axis| {
+        bounds.min[axis] - tolerance(1e-9) <= point[axis]
+            && point[axis] <= bounds.max[axis] + tolerance(1e-9)
+    })
+}
+
+#[test]
+fn planes_are_hit_from_both_sides() {
+    let plane = Plane::new(Point3::new(0.0, 1.0, 0.0), Vec3::UP, UberShader::default());
+    let from_above = Ray::new(Point3::new(2.0, 5.0, -3.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = hit(&plane, &from_above).unwrap();
+    assert!((record.t - 4.0).abs() < EPSILON);
+    assert!(record.front_face);
+    assert_close(record.normal, Vec3::UP, "normal seen from above");
+
+    let from_below = Ray::new(Point3::new(0.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+    let record = hit(&plane, &from_below).unwrap();
+    assert!(!record.front_face);
+    assert_close(record.normal, -Vec3::UP, "normal seen from below");
+    assert_close(record.point, Point3::new(2.0, 1.0, 0.0), "hit point");
+
+    let parallel = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 1.0));
+    assert!(hit(&plane, &parallel).is_none());
+}
+
+#[test]
+fn quads_map_their_edges_to_uvs() {
+    let quad = Quad::new(
+        Point3::new(1.0, 0.0, 0.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -4.0),
+        UberShader::default(),
+    );
+    assert!((quad.area() - 8.0).abs() < EPSILON);
+    let ray = Ray::new(Point3::new(1.5, 3.0, -3.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = hit(&quad, &ray).unwrap();
+    assert!(record.front_face, "u × v points up");
+    assert!((record.u - 0.25).abs() < EPSILON && (record.v - 0.75).abs() < EPSILON);
+
+    for (x, z) in [(0.9, -1.0), (3.1, -1.0), (2.0, 0.1), (2.0, -4.1)] {
+        let ray = Ray::new(Point3::new(x, 3.0, z), Vec3::new(0.0, -1.0, 0.0));
+        assert!(hit(&quad, &ray).is_none(), "({}, {}) is outside", x, z);
+    }
+}
+
+#[test]
+fn disks_map_radius_and_angle_to_uvs() {
+    let disk = Disk::new(
+        Point3::new(0.0, 0.0, -2.0),
+        Vec3::new(0.0, 0.0, 3.0),
+        2.0,
+        UberShader::default(),
+    );
+    let ray = Ray::new(Point3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = hit(&disk, &ray).unwrap();
+    assert!(record.front_face);
+    assert!((record.u - 0.5).abs() < EPSILON);
+    assert!((0.0..1.0).contains(&record.v));
+
+    // The same distance from the center at a different angle
+    let ray = Ray::new(Point3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let other = hit(&disk, &ray).unwrap();
+    assert!((other.u - 0.5).abs() < EPSILON);
+    let turn = (other.v - record.v).rem_euclid(1.0);
+    assert!((turn - 0.25).abs() < 1e-6 || (turn - 0.75).abs() < 1e-6);
+
+    let ray = Ray::new(Point3::new(1.5, 1.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(
+        hit(&disk, &ray).is_none(),
+        "the corner of the bounds is outside"
+    );
+}
+
+fn assert_hits_inside_bounds(objects: &[Primitive]) {
+    for object in objects {
+        let bounds = object.bounding_box();
+        assert!(bounds.is_bounded() && !bounds.is_empty());
+        let mut hits = 0;
+        for _ in 0..2000 {
+            if let Some(record) = hit(object, &random_ray()) {
+                hits += 1;
+                assert!(
+                    contains(&bounds, record.point),
+                    "{} hit at {:?} is outside",
+                    object.kind(),
+                    record.point
+                );
+            }
+        }
+        assert!(hits > 50, "{} is hardly ever hit", object.kind());
+    }
+}
+
+#[test]
+fn flat_shapes_are_inside_their_bounds() {
+    seed_rng(5);
+    let tilted = Vec3::new(1.0, 2.0, -0.5);
+    let objects: Vec<Primitive> = vec![
+        Disk::new(
+            Point3::new(0.5, 0.0, 0.0),
+            tilted,
+            1.5,
+            UberShader::default(),
+        )
+        .into(),
+        Disk::new(Point3::ORIGIN, Vec3::UP, 2.0, UberShader::default()).into(),
+        Quad::new(
+            Point3::new(-1.0, -1.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 2.0),
+            UberShader::default(),
+        )
+        .into(),
+    ];
+    assert_hits_inside_bounds(&objects);
+}
+
+#[test]
+fn cuboids_face_outwards() {
+    seed_rng(9);
+    let a = Point3::new(-1.0, -0.5, -2.0);
+    let b = Point3::new(2.0, 1.5, 1.0);
+    let sides = HittableList {
+        objects: cuboid(b, a, UberShader::default()).to_vec(),
+    };
+    let center = Point3::midpoint(a, b);
+    let bounds = Aabb::new(a, b);
+    let mut hits = 0;
+    for _ in 0..1000 {
+        let ray = random_ray();
+        let Some(record) = hit(&sides, &ray) else {
+            continue;
+        };
+        hits += 1;
+        assert!(record.front_face, "rays from outside hit the front");
+        assert!(contains(&bounds, record.point));
+        // The normal points along the axis of the face that was hit
+        let offset = record.point - center;
+        let axis = (0..3)
+            .max_by(|i, j| {
+                (offset[*i].abs() / bounds.extent()[*i])
+                    .total_cmp(&(offset[*j].abs() / bounds.extent()[*j]))
+            })
+            .unwrap();
+        assert!((record.normal[axis] - offset[axis].signum()).abs() < 1e-6);
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn degenerate_flat_shapes_are_rejected() {
+    let scenes = [
+        Scene::builder().plane(Point3::ORIGIN, Vec3::ZERO, UberShader::default()),
+        Scene::builder().disk(Point3::ORIGIN, Vec3::UP, 0.0, UberShader::default()),
+        Scene::builder().quad(
+            Point3::ORIGIN,
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            UberShader::default(),
+        ),
+    ];
+    for builder in scenes {
+        let result = builder.build();
+        assert!(
+            matches!(result, Err(Error::InvalidScene(message)) if message.starts_with("object 1 "))
+        );
+    }
+}
+
+#[test]
+fn polynomial_roots_are_found_in_order() {
+    // (x - 1) (x - 2) (x - 3) (x + 4)
+    let quartic = [1.0, -2.0, -13.0, 38.0, -24.0];
+    let roots = roots_between(&quartic, -10.0, 10.0);
+    assert_eq!(roots.as_slice().len(), 4);
+    for (root, expected) in roots.as_slice().iter().zip([-4.0, 1.0, 2.0, 3.0]) {
+        assert!(
+            (root - expected).abs() < tolerance(1e-12),
+            "{} instead of {}",
+            root,
+            expected
+        );
+    }
+    assert_eq!(roots_between(&quartic, 1.5, 2.5).as_slice().len(), 1);
+
+    // Leading zeros lower the degree, quadratics take infinite bounds
+    let roots = roots_between(
+        &[0.0, 0.0, 2.0, -6.0, 4.0],
+        Float::NEG_INFINITY,
+        Float::INFINITY,
+    );
+    assert_eq!(roots.as_slice(), &[1.0, 2.0]);
+    assert_eq!(
+        roots_between(&[0.0, 2.0, 1.0], -1.0, 1.0).as_slice(),
+        &[-0.5]
+    );
+    assert!(roots_between(&[1.0, 0.0, 1.0], -10.0, 10.0)
+        .as_slice()
+        .is_empty());
+    // x⁴ + 1 has no real roots
+    assert!(roots_between(&[1.0, 0.0, 0.0, 0.0, 1.0], -10.0, 10.0)
+        .as_slice()
+        .is_empty());
+}
+
+#[test]
+fn cylinders_have_caps_and_sweeps() {
+    let cylinder = Quadric::cylinder(
+        Point3::ORIGIN,
+        Vec3::new(0.0, 2.0, 0.0),
+        1.0,
+        UberShader::default(),
+    );
+    let ray = Ray::new(Point3::new(0.0, 1.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = hit(&cylinder, &ray).unwrap();
+    assert!((record.t - 4.0).abs() < EPSILON);
+    assert_close(record.normal, Vec3::new(0.0, 0.0, 1.0), "side normal");
+    assert!((record.v - 0.5).abs() < EPSILON, "v is the height");
+
+    // Open at the ends, so a ray down the inside touches nothing
+    let down = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(hit(&cylinder, &down).is_none());
+    let record = hit(&cylinder.clone().with_caps(), &down).unwrap();
+    assert!((record.t - 3.0).abs() < EPSILON);
+    assert_close(record.normal, Vec3::UP, "cap normal");
+    assert!(
+        (record.v - 0.5).abs() < EPSILON,
+        "v is the distance from the axis"
+    );
+
+    // Half a tube doesn't have the front wall, the ray hits the back wall from inside
+    let half = cylinder.with_sweep(180.0);
+    let record = hit(&half, &ray).unwrap();
+    assert!((record.t - 6.0).abs() < EPSILON);
+    assert!(!record.front_face);
+    assert_close(
+        record.normal,
+        Vec3::new(0.0, 0.0, 1.0),
+        "normal facing the ray",
+    );
+}
+
+#[test]
+fn quadrics_are_hit_on_their_side() {
+    let material = UberShader::default();
+    let up = Vec3::UP;
+    let sideways =
+        |height: Float| Ray::new(Point3::new(5.0, height, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+    // Cone with 45 degree sides, 0.5 wide at the middle
+    let cone = Quadric::cone(Point3::ORIGIN, up, 1.0, material);
+    let record = hit(&cone, &sideways(0.5)).unwrap();
+    assert!((record.t - 4.5).abs() < EPSILON);
+    assert_close(
+        record.normal,
+        Vec3::new(1.0, 1.0, 0.0).unit_vector(),
+        "cone normal",
+    );
+
+    let hyperboloid = Quadric::hyperboloid(Point3::ORIGIN, 2.0 * up, 1.0, 0.5, material);
+    let record = hit(&hyperboloid, &sideways(1.0)).unwrap();
+    assert!((record.t - 4.5).abs() < EPSILON, "narrowest at the middle");
+    assert_close(
+        record.normal,
+        Vec3::new(1.0, 0.0, 0.0),
+        "hyperboloid normal",
+    );
+    let record = hit(&hyperboloid, &sideways(2.0)).unwrap();
+    assert!(
+        (record.t - 4.0).abs() < EPSILON,
+        "as wide as the radius at the end"
+    );
+
+    // y = x² + z², the ray falls into the bowl and hits its inside
+    let paraboloid = Quadric::paraboloid(Point3::ORIGIN, up, 1.0, material);
+    let down = Ray::new(Point3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = hit(&paraboloid, &down).unwrap();
+    assert!((record.t - 4.75).abs() < EPSILON);
+    assert!(!record.front_face);
+    assert_close(
+        record.normal,
+        Vec3::new(-1.0, 1.0, 0.0).unit_vector(),
+        "bowl normal",
+    );
+}
+
+#[test]
+fn tori_are_hit_through_the_quartic() {
+    let torus = Torus::new(Point3::ORIGIN, Vec3::UP, 2.0, 0.5, UberShader::default());
+    let ray = Ray::new(Point3::new(5.0, 0.0, 0.0), Vec3::new(-2.0, 0.0, 0.0));
+    let record = hit(&torus, &ray).unwrap();
+    assert!(
+        (record.t - 1.25).abs() < EPSILON,
+        "t follows the unnormalized direction"
+    );
+    assert_close(record.normal, Vec3::new(1.0, 0.0, 0.0), "outer normal");
+    assert!(record.v.abs() < EPSILON, "v starts at the outer edge");
+    let record = torus.hit(&ray, 1.3, Float::INFINITY).unwrap();
+    assert!((record.t - 1.75).abs() < EPSILON, "inner side of the tube");
+
+    let through_hole = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(hit(&torus, &through_hole).is_none());
+    let onto_tube = Ray::new(Point3::new(0.0, 5.0, -2.0), Vec3::new(0.0, -1.0, 0.0));
+    let record = hit(&torus, &onto_tube).unwrap();
+    assert!((record.t - 4.5).abs() < EPSILON);
+    assert_close(record.normal, Vec3::UP, "top of the tube");
+    assert!((record.v - 0.25).abs() < EPSILON);
+
+    let half = torus.with_sweep(180.0);
+    assert!(hit(&half, &onto_tube).is_some());
+    let other_side = Ray::new(Point3::new(0.0, 5.0, 2.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(hit(&half, &other_side).is_none());
+}
+
+#[test]
+fn torus_hits_are_on_the_surface() {
+    seed_rng(21);
+    let axis = Vec3::new(1.0, 2.0, 0.5).unit_vector();
+    let center = Point3::new(0.5, -0.2, 0.1);
+    let torus = Torus::new(center, axis, 2.0, 0.7, UberShader::default());
+    let mut hits = 0;
+    for _ in 0..5000 {
+        // Far away, where the quartic is hardest to solve precisely
+        let origin = Point3::ORIGIN + 1000.0 * Vec3::random_point_in_unit_vector();
+        let target = Point3::ORIGIN + 2.0 * Vec3::random_point_in_unit_sphere();
+        let ray = Ray::new(origin, target - origin);
+        let Some(record) = hit(&torus, &ray) else {
+            continue;
+        };
+        hits += 1;
+        let offset = record.point - center;
+        let height = Vec3::dot_product(&offset, &axis);
+        let ring = offset - height * axis;
+        let distance = (ring.length() - 2.0).hypot(height);
+        // The rays start far away, which costs f32 builds most of their digits
+        let allowed = match cfg!(feature = "f32") {
+            true => 1e-3,
+            false => 1e-6,
+        };
+        assert!(
+            (distance - 0.7).abs() < allowed,
+            "hit {} from the tube center",
+            distance
+        );
+        let outward = (offset - 2.0 * ring.unit_vector()) / 0.7;
+        assert!(record.normal.dot(outward).abs() > 1.0 - allowed);
+    }
+    assert!(hits > 100);
+}
+
+#[test]
+fn curved_shapes_are_inside_their_bounds() {
+    seed_rng(23);
+    let material = UberShader::default();
+    let axis = Vec3::new(0.5, 2.0, -1.0);
+    let base = Point3::new(-0.5, -1.0, 0.5);
+    let objects: Vec<Primitive> = vec![
+        Quadric::cylinder(base, axis, 1.0, material)
+            .with_caps()
+            .into(),
+        Quadric::cone(base, axis, 1.2, material).with_caps().into(),
+        Quadric::paraboloid(base, axis, 1.5, material)
+            .with_sweep(200.0)
+            .into(),
+        Quadric::hyperboloid(base, axis, 1.5, 0.4, material).into(),
+        Torus::new(base, axis, 1.5, 0.5, material).into(),
+        Torus::new(base, axis, 1.0, 1.2, material)
+            .with_sweep(90.0)
+            .into(),
+    ];
+    assert_hits_inside_bounds(&objects);
+}
+
+#[test]
+fn degenerate_curved_shapes_are_rejected() {
+    let material = UberShader::default();
+    let up = Vec3::UP;
+    let scenes = [
+        Scene::builder().quadric(Quadric::cylinder(Point3::ORIGIN, Vec3::ZERO, 1.0, material)),
+        Scene::builder().quadric(Quadric::cone(Point3::ORIGIN, up, -1.0, material)),
+        Scene::builder().quadric(Quadric::hyperboloid(Point3::ORIGIN, up, 1.0, 1.5, material)),
+        Scene::builder()
+            .quadric(Quadric::paraboloid(Point3::ORIGIN, up, 1.0, material).with_sweep(0.0)),
+        Scene::builder().torus(Torus::new(Point3::ORIGIN, up, 1.0, 0.0, material)),
+        Scene::builder().torus(Torus::new(Point3::ORIGIN, Vec3::ZERO, 1.0, 0.5, material)),
+    ];
+    for builder in scenes {
+        let result = builder.build();
+        assert!(
+            matches!(&result, Err(Error::InvalidScene(message)) if message.starts_with("object 1 ")),
+            "{:?}",
+            result.err()
+        );
+    }
+}
+
+#[test]
+fn distance_fields_match_analytic_shapes() {
+    seed_rng(29);
+    let material = UberShader::default();
+    let center = Point3::new(0.3, -0.2, 0.1);
+    let sphere = Sphere::new(center, 1.5, material);
+    let marched_sphere = DistanceField::new(
+        Sdf::sphere(1.5).translate(center - Point3::ORIGIN),
+        material,
+    );
+    let half_extent = Vec3::new(1.0, 0.5, 1.5);
+    let sides = HittableList {
+        objects: cuboid(
+            Point3::ORIGIN - half_extent,
+            Point3::ORIGIN + half_extent,
+            material,
+        )
+        .to_vec(),
+    };
+    let marched_box = DistanceField::new(Sdf::cuboid(half_extent), material);
+
+    let pairs: [(&str, &dyn Hittable, &DistanceField); 2] = [
+        ("sphere", &sphere, &marched_sphere),
+        ("box", &sides, &marched_box),
+    ];
+    for (name, analytic, marched) in pairs {
+        // Rays that graze the silhouette may run out of steps just before reaching it
+        let mut disagreements = 0;
+        for _ in 0..1000 {
+            let ray = random_ray();
+            let (expected, actual) = match (
+                analytic.hit(&ray, EPSILON, Float::INFINITY),
+                hit(marched, &ray),
+            ) {
+                (Some(expected), Some(actual)) => (expected, actual),
+                (None, None) => continue,
+                _ => {
+                    disagreements += 1;
+                    continue;
+                }
+            };
+            if (expected.t - actual.t).abs() > 1e-3 {
+                disagreements += 1;
+                continue;
+            }
+            // The gradient is rounded off within the precision of the edges of the box
+            let on_edge = (0..3)
+                .filter(|axis| (actual.point[*axis].abs() - half_extent[*axis]).abs() < 1e-3)
+                .count()
+                > 1;
+            if name == "sphere" || !on_edge {
+                assert!(
+                    expected.normal.dot(Vec3::from(actual.normal)) > 0.999,
+                    "{} normal {:?} instead of {:?}",
+                    name,
+                    actual.normal,
+                    expected.normal
+                );
+            }
+        }
+        assert!(
+            disagreements < 10,
+            "{} differs for {} rays",
+            name,
+            disagreements
+        );
+    }
+}
+
+#[test]
+fn distance_fields_are_left_and_entered() {
+    let field = DistanceField::new(Sdf::sphere(1.0), UberShader::default());
+    let inside = Ray::new(Point3::ORIGIN, Vec3::new(0.0, 0.0, 2.0));
+    let record = hit(&field, &inside).unwrap();
+    assert!((record.t - 0.5).abs() < 1e-3);
+    assert!(!record.front_face, "rays from inside hit the back");
+
+    // Scattered from the surface, the ray doesn't hit it again on the way out but does on the
+    // way through
+    let surface = Point3::new(0.0, 0.0, 1.0);
+    let outwards = Ray::new(surface, Vec3::new(0.3, 0.0, 1.0));
+    assert!(field.hit(&outwards, 0.0001, Float::INFINITY).is_none());
+    let inwards = Ray::new(surface, Vec3::new(0.0, 0.0, -1.0));
+    let record = field.hit(&inwards, 0.0001, Float::INFINITY).unwrap();
+    assert!((record.t - 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn marching_never_skips_a_surface() {
+    seed_rng(31);
+    let blob = Sdf::torus(0.8, 0.3)
+        .union(Sdf::sphere(0.5).translate(Vec3::new(0.0, 0.4, 0.0)), 0.4)
+        .subtraction(Sdf::cuboid(Vec3::from_float(0.3)), 0.1);
+    let fields = [
+        ("blob", blob),
+        ("twist", Sdf::cuboid(Vec3::new(0.3, 1.5, 1.0)).twist(2.0)),
+        // Every copy fits inside its cell
+        (
+            "repetition",
+            Sdf::capsule(
+                Point3::new(0.0, -1.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                0.15,
+            )
+            .rotate(Vec3::new(1.0, 0.0, 1.0), 10.0)
+            .repeat(Vec3::new(0.8, 0.0, 0.8), [2, 0, 2]),
+        ),
+        (
+            "rounded box",
+            Sdf::rounded_cuboid(Vec3::new(1.0, 0.5, 0.8), 0.3).scale(1.5),
+        ),
+        ("menger sponge", Sdf::menger_sponge(3).scale(1.5)),
+        ("mandelbulb", Sdf::mandelbulb(8.0, 8).scale(1.5)),
+    ];
+    for (name, sdf) in fields {
+        let field = DistanceField::new(sdf.clone(), UberShader::default());
+        let mut hits = 0;
+        for _ in 0..300 {
+            let ray = random_ray();
+            let end = match hit(&field, &ray) {
+                Some(record) => {
+                    hits += 1;
+                    let distance = sdf.distance(record.point).abs();
+                    assert!(
+                        distance < 1e-3,
+                        "{} stops {} from the surface",
+                        name,
+                        distance
+                    );
+                    record.t
+                }
+                None => 2.0,
+            };
+            // Everything before the hit is outside the shape
+            for step in 1..200 {
+                let point = ray.at(end * step as Float / 200.0);
+                assert!(
+                    sdf.distance(point) > -1e-3,
+                    "{} skipped the surface at {:?}",
+                    name,
+                    point
+                );
+            }
+        }
+        assert!(hits > 20, "{} is hardly ever hit", name);
+    }
+}
+
+#[test]
+fn distance_fields_are_inside_their_bounds() {
+    seed_rng(37);
+    let material = UberShader::default();
+    let objects: Vec<Primitive> = vec![
+        DistanceField::new(
+            Sdf::torus(1.0, 0.4).rotate(Vec3::new(1.0, 1.0, 0.0), 40.0),
+            material,
+        )
+        .into(),
+        DistanceField::new(
+            Sdf::sphere(0.8)
+                .union(Sdf::sphere(0.8).translate(Vec3::new(1.2, 0.0, 0.0)), 0.6)
+                .translate(Vec3::new(-0.5, 0.3, 0.0)),
+            material,
+        )
+        .into(),
+        DistanceField::new(
+            Sdf::cuboid(Vec3::from_float(1.0)).intersection(Sdf::sphere(1.3), 0.0),
+            material,
+        )
+        .into(),
+        DistanceField::new(Sdf::cuboid(Vec3::new(0.2, 1.0, 0.8)).twist(1.0), material).into(),
+    ];
+    assert_hits_inside_bounds(&objects);
+}
+
+#[test]
+fn fractal_distances_are_signed() {
+    let sponge = Sdf::menger_sponge(3);
+    assert!(
+        sponge.distance(Point3::ORIGIN) > 0.0,
+        "the center is hollow"
+    );
+    assert!(
+        sponge.distance(Point3::new(0.95, 0.95, 0.95)) < 0.0,
+        "corners are solid"
+    );
+    assert!(sponge.distance(Point3::new(2.0, 0.0, 0.0)) > 0.9);
+
+    let bulb = Sdf::mandelbulb(8.0, 10);
+    assert!(
+        bulb.distance(Point3::new(0.0, 0.0, 0.3)) <= 0.0,
+        "the middle is solid"
+    );
+    let outside = bulb.distance(Point3::new(0.0, 3.0, 0.0));
+    assert!(outside > 0.0 && outside < 3.0, "{} is no bound", outside);
+}
+
+#[test]
+fn solids_report_every_span() {
+    let material = UberShader::default();
+    let mut spans = Vec::new();
+
+    // Behind the origin too
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 1.0));
+    unit_sphere().spans(&ray, &mut spans);
+    assert_eq!(spans.len(), 1);
+    assert!((spans[0].entry.t + 6.0).abs() < EPSILON && (spans[0].exit.t + 4.0).abs() < EPSILON);
+    assert_close(
+        spans[0].entry.normal,
+        Vec3::new(0.0, 0.0, -1.0),
+        "entry normal",
+    );
+
+    // Through both sides of the tube
+    spans.clear();
+    let torus = Torus::new(Point3::ORIGIN, Vec3::UP, 2.0, 0.5, material);
+    torus.spans(
+        &Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+        &mut spans,
+    );
+    let ends: Vec<(Float, Float)> = spans
+        .iter()
+        .map(|span| (span.entry.t, span.exit.t))
+        .collect();
+    assert_eq!(ends.len(), 2, "{:?}", ends);
+    for ((entry, exit), expected) in ends.iter().zip([(2.5, 3.5), (6.5, 7.5)]) {
+        assert!((entry - expected.0).abs() < 1e-6 && (exit - expected.1).abs() < 1e-6);
+    }
+
+    // Along the axis, in through one cap and out through the other
+    spans.clear();
+    let ray = Ray::new(Point3::new(0.0, -1.0, 0.0), Vec3::UP);
+    let cylinder = Quadric::cylinder(Point3::ORIGIN, Vec3::UP, 1.0, material);
+    cylinder.clone().with_caps().spans(&ray, &mut spans);
+    assert_eq!(spans.len(), 1);
+    assert!((spans[0].entry.t - 1.0).abs() < EPSILON && (spans[0].exit.t - 2.0).abs() < EPSILON);
+
+    // Open shapes have no inside
+    spans.clear();
+    cylinder.spans(&ray, &mut spans);
+    torus.with_sweep(180.0).spans(&ray, &mut spans);
+    assert!(spans.is_empty());
+
+    let block = Block::new(
+        Point3::new(1.0, 1.0, 1.0),
+        Point3::new(-1.0, -2.0, -1.0),
+        material,
+    );
+    block.spans(
+        &Ray::new(Point3::ORIGIN, Vec3::new(1.0, 0.0, 0.0)),
+        &mut spans,
+    );
+    assert_eq!(spans.len(), 1);
+    assert_close(
+        spans[0].exit.normal,
+        Vec3::new(1.0, 0.0, 0.0),
+        "exit normal",
+    );
+    assert!((spans[0].entry.t + 1.0).abs() < EPSILON && (spans[0].exit.t - 1.0).abs() < EPSILON);
+}
+
+/// Whether a point is inside the unit sphere combined with the block from (0, -0.5, -0.5) to
+/// (2, 0.5, 0.5).
+fn inside_csg(operation: CsgOperation, point: Point3) -> bool {
+    let sphere = Point3::distance(point, Point3::ORIGIN) < 1.0;
+    let block = (0.0..2.0).contains(&point.x) && point.y.abs() < 0.5 && point.z.abs() < 0.5;
+    match operation {
+        CsgOperation::Union => sphere || block,
+        CsgOperation::Intersection => sphere && block,
+        CsgOperation::Difference => sphere && !block,
+    }
+}
+
+#[test]
+fn csg_hits_are_on_the_boundary_of_the_solid() {
+    seed_rng(41);
+    let material = UberShader::default();
+    let block = Block::new(
+        Point3::new(0.0, -0.5, -0.5),
+        Point3::new(2.0, 0.5, 0.5),
+        material,
+    );
+    for operation in [
+        CsgOperation::Union,
+        CsgOperation::Intersection,
+        CsgOperation::Difference,
+    ] {
+        let csg = Csg::new(operation, unit_sphere(), block.clone());
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let ray = random_ray();
+            let length = ray.direction.length();
+            let step = tolerance(1e-6) / length;
+            let Some(record) = hit(&csg, &ray) else {
+                continue;
+            };
+            hits += 1;
+            let (before, after) = (ray.at(record.t - step), ray.at(record.t + step));
+            assert_ne!(
+                inside_csg(operation, before),
+                inside_csg(operation, after),
+                "{:?} hit at {:?} is not on the surface",
+                operation,
+                record.point
+            );
+            assert_eq!(record.front_face, !inside_csg(operation, before));
+            // Normals face the ray, so the point a little along them is on the side it came from
+            let outside = record.point + tolerance(1e-6) * record.normal;
+            assert_eq!(inside_csg(operation, outside), !record.front_face);
+            // Nothing was skipped before the hit
+            for sample in 1..50 {
+                let t = record.t * sample as Float / 50.0;
+                assert_eq!(
+                    inside_csg(operation, ray.at(t)),
+                    inside_csg(operation, ray.origin),
+                    "{:?} skipped a surface at {:?}",
+                    operation,
+                    ray.at(t)
+                );
+            }
+        }
+        assert!(hits > 200, "{:?} only hit {} times", operation, hits);
+    }
+}
+
+#[test]
+fn csg_surfaces_keep_their_materials() {
+    let outer = UberShader::new(Color::new(0.8, 0.2, 0.2), false, 0.0).with_id(1);
+    let cut = UberShader::new(Color::new(0.2, 0.2, 0.8), false, 0.0).with_id(2);
+    let hole = Quadric::cylinder(
+        Point3::new(0.0, -2.0, 0.0),
+        Vec3::new(0.0, 4.0, 0.0),
+        0.5,
+        cut,
+    )
+    .with_caps();
+    let csg = Csg::difference(Sphere::new(Point3::ORIGIN, 1.0, outer), hole);
+
+    // Down the side of the hole: in through the sphere, out through the wall of the hole
+    let ray = Ray::new(Point3::new(0.75, 2.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let entry = hit(&csg, &ray).expect("the ray enters the sphere");
+    assert_eq!(entry.material.id(), 1);
+    assert!(entry.front_face);
+
+    // Across the hole from its middle, entering the sphere through the wall of the hole
+    let ray = Ray::new(Point3::ORIGIN, Vec3::new(1.0, 0.0, 0.0));
+    let wall = hit(&csg, &ray).expect("the ray hits the wall of the hole");
+    assert_eq!(wall.material.id(), 2);
+    assert!(
+        wall.front_face,
+        "the inside of the hole is outside the solid"
+    );
+    assert!((wall.t - 0.5).abs() < EPSILON);
+    assert_close(wall.normal, Vec3::new(-1.0, 0.0, 0.0), "wall normal");
+    let exit = csg
+        .hit(&ray, wall.t + EPSILON, Float::INFINITY)
+        .expect("the ray leaves the sphere");
+    assert_eq!(exit.material.id(), 1);
+    assert!(!exit.front_face && (exit.t - 1.0).abs() < EPSILON);
+
+    // Nested solids keep the materials from the bottom of the tree
+    let nested = Csg::union(
+        csg,
+        Block::new(
+            Point3::new(2.0, -0.1, -0.1),
+            Point3::new(3.0, 0.1, 0.1),
+            outer,
+        ),
+    );
+    assert_eq!(
+        hit(&nested, &ray).map(|record| record.material.id()),
+        Some(2)
+    );
+}
+
+#[test]
+fn csg_needs_closed_operands() {
+    let material = UberShader::default();
+    let open = Quadric::cylinder(Point3::ORIGIN, Vec3::UP, 0.5, material);
+    let quad = Quad::new(Point3::ORIGIN, Vec3::new(1.0, 0.0, 0.0), Vec3::UP, material);
+    let scenes = [
+        Scene::builder().csg(Csg::difference(unit_sphere(), open)),
+        Scene::builder().csg(Csg::union(quad, unit_sphere())),
+        Scene::builder().csg(Csg::intersection(
+            unit_sphere(),
+            Csg::union(unit_sphere(), Sphere::new(Point3::ORIGIN, -1.0, material)),
+        )),
+        Scene::builder().block(Point3::ORIGIN, Point3::new(1.0, 0.0, 1.0), material),
+    ];
+    for builder in scenes {
+        let result = builder.build();
+        assert!(
+            matches!(&result, Err(Error::InvalidScene(message)) if message.starts_with("object 1 ")),
+            "{:?}",
+            result.err()
+        );
+    }
+
+    let closed = Scene::builder()
+        .csg(Csg::difference(
+            Block::new(Point3::ORIGIN, Point3::new(1.0, 1.0, 1.0), material),
+            Torus::new(Point3::ORIGIN, Vec3::UP, 1.0, 0.2, material),
+        ))
+        .build();
+    assert!(closed.is_ok());
+}
+
+#[test]
+fn volumes_need_closed_boundaries_and_valid_media() {
+    let material = UberShader::default();
+    let smoke = Medium::fog(1.0, Color::from_float(0.5));
+    let scenes = [
+        Scene::builder().volume(Volume::new(
+            Quadric::cylinder(Point3::ORIGIN, Vec3::UP, 1.0, material),
+            smoke,
+        )),
+        Scene::builder().volume(Volume::new(
+            Sphere::new(Point3::ORIGIN, 0.0, material),
+            smoke,
+        )),
+        Scene::builder().volume(Volume::new(
+            unit_sphere(),
+            Medium::new(Color::new(0.1, -0.1, 0.1), Color::ONE),
+        )),
+        Scene::builder().volume(Volume::new(unit_sphere(), smoke.with_asymmetry(1.0))),
+    ];
+    for builder in scenes {
+        let result = builder.build();
+        assert!(
+            matches!(&result, Err(Error::InvalidScene(message)) if message.starts_with("object 1 ")),
+            "{:?}",
+            result.err()
+        );
+    }
+    let result = Scene::builder().fog(Fog::new(smoke, -1.0)).build();
+    assert!(
+        matches!(&result, Err(Error::InvalidScene(message)) if message.starts_with("the fog ")),
+        "{:?}",
+        result.err()
+    );
+
+    // The boundary is hit like the primitive, but it is only an interface to the medium
+    let volume = Volume::new(
+        Block::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+            material,
+        ),
+        smoke,
+    );
+    let record = hit(
+        &volume,
+        &Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0)),
+    )
+    .expect("the boundary is hit");
+    assert!((record.t - 4.0).abs() < EPSILON && record.front_face);
+    assert!(record.material.is_interface());
+    assert_eq!(record.material.interior(), Some(&smoke));
+}
+
+#[test]
+fn density_grids_interpolate_between_voxels() {
+    let grid = DensityGrid::new([2, 1, 1], &[1.0, 3.0]).expect("the values fill the grid");
+    let at = |x: Float| grid.density(Point3::new(x, 0.5, 0.5));
+    // Values sit at the centers of the voxels and are held up to the faces of the cube
+    assert!((at(0.25) - 1.0).abs() < EPSILON && (at(0.75) - 3.0).abs() < EPSILON);
+    assert!((at(0.5) - 2.0).abs() < EPSILON && (at(0.375) - 1.5).abs() < EPSILON);
+    assert!((at(0.0) - 1.0).abs() < EPSILON && (at(1.0) - 3.0).abs() < EPSILON);
+    assert_eq!(at(1.1), 0.0);
+    assert_eq!(grid.max(), 3.0);
+
+    assert!(DensityGrid::new([2, 2, 1], &[1.0, 3.0]).is_none());
+    assert!(DensityGrid::new([2, 1, 1], &[1.0, -3.0]).is_none());
+    assert!(DensityGrid::new([2, 1, 1], &[1.0, f32::NAN]).is_none());
+
+    // Only bricks of 8x8x8 voxels with something in them are stored
+    let grid = DensityGrid::from_fn([40, 40, 40], |point| match point.x < 0.1 {
+        true => 1.0,
+        false => -1.0,
+    });
+    assert_eq!(grid.occupied_bricks(), 25);
+    assert_eq!(grid.voxel(3, 20, 39), 1.0);
+    assert_eq!(grid.voxel(4, 20, 39), 0.0);
+    assert_eq!(grid.voxel(3, 20, 40), 0.0);
+}
+
+#[test]
+fn density_grids_round_trip_through_files() {
+    let directory = std::env::temp_dir();
+    let path = directory.join(format!("density-grid-{}.grid", std::process::id()));
+
+    // Sides that aren't a multiple of the brick size
+    let grid = DensityGrid::from_fn([13, 9, 21], |point| point.x * point.y - point.z * 0.2);
+    write_grid(&path, &grid).expect("the grid is written");
+    let read = read_grid(&path).expect("the grid is read");
+    assert_eq!(read.resolution(), [13, 9, 21]);
+    assert_eq!(read.occupied_bricks(), grid.occupied_bricks());
+    for (x, y, z) in [(0, 0, 0), (12, 8, 0), (7, 5, 3), (12, 8, 20)] {
+        assert_eq!(read.voxel(x, y, z), grid.voxel(x, y, z));
+    }
+
+    // Dense encoding written by hand
+    let mut bytes = b"RTGRID01".to_vec();
+    for side in [2u64, 1, 1] {
+        bytes.extend(side.to_le_bytes());
+    }
+    bytes.push(0);
+    for value in [0.5f32, 2.0] {
+        bytes.extend(value.to_le_bytes());
+    }
+    std::fs::write(&path, &bytes).unwrap();
+    let read = read_grid(&path).expect("the dense grid is read");
+    assert_eq!((read.voxel(0, 0, 0), read.voxel(1, 0, 0)), (0.5, 2.0));
+
+    // Truncated and mislabeled files
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(matches!(read_grid(&path), Err(Error::AssetLoad { .. })));
+    bytes[7] = b'9';
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(read_grid(&path), Err(Error::AssetLoad { .. })));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn grid_volumes_carry_their_density_through_transforms() {
+    let grid = Arc::new(DensityGrid::from_fn([8, 8, 8], |point| point.x));
+    let volume = Volume::from_grid(
+        grid,
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        Medium::fog(1.0, Color::ONE),
+    );
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = hit(&volume, &ray).expect("the boundary is hit");
+    let density = record.density.expect("the hit has the density of the grid");
+    assert!((density.density(Point3::new(0.5, 0.0, 0.0)) - 0.75).abs() < 1e-6);
+    assert_eq!(density.density(Point3::new(1.5, 0.0, 0.0)), 0.0);
+
+    // Moved 10 along x and stretched twice as wide
+    let to_world = Matrix4::translation(Vec3::new(10.0, 0.0, 0.0))
+        * Matrix4::scaling(Vec3::new(2.0, 1.0, 1.0));
+    let moved = Transform::new(volume, to_world).unwrap();
+    let ray = Ray::new(Point3::new(10.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let record = hit(&moved, &ray).expect("the moved boundary is hit");
+    let density = record.density.expect("the hit has the density of the grid");
+    assert!((density.density(Point3::new(11.0, 0.0, 0.0)) - 0.75).abs() < 1e-6);
+}
@@ -0,0 +1,235 @@
+//! Turning films into images and comparing them: display transforms, denoising and image
+//! metrics.
+
+use raytracing_rust::compare::compare;
+use raytracing_rust::math::{random_scalar, seed_rng};
+use raytracing_rust::output::display::{srgb_eotf, srgb_oetf, DisplayTransform, ToneMapping};
+use raytracing_rust::rendering::{denoise, AovPixel, DenoiseSettings, FilmPixel};
+use raytracing_rust::{Color, Film, Float, Vec3};
+
+mod common;
+use common::tolerance;
+
+const TONE_MAPPINGS: [ToneMapping; 5] = [
+    ToneMapping::None,
+    ToneMapping::Reinhard,
+    ToneMapping::Aces,
+    ToneMapping::Agx,
+    ToneMapping::Hable,
+];
+
+#[test]
+fn srgb_transfer_functions_are_inverses() {
+    for step in 0..=1000 {
+        let linear = step as Float / 1000.0;
+        let encoded = srgb_oetf(linear);
+        assert!(
+            (0.0..=1.0).contains(&encoded),
+            "{} encodes to {}",
+            linear,
+            encoded
+        );
+        let decoded = srgb_eotf(encoded);
+        assert!(
+            (decoded - linear).abs() < tolerance(1e-12),
+            "{} round-trips to {}",
+            linear,
+            decoded
+        );
+    }
+}
+
+#[test]
+fn zero_exposure_only_encodes() {
+    let display = DisplayTransform::default();
+    assert_eq!(display.exposure, 0.0);
+    for step in 0..=100 {
+        let value = step as Float / 100.0;
+        let color = Color::new(value, 1.0 - value, 0.5 * value);
+        let expected = Color::new(srgb_oetf(color.x), srgb_oetf(color.y), srgb_oetf(color.z));
+        assert_eq!(display.apply(color), expected, "{:?}", color);
+    }
+}
+
+#[test]
+fn tone_mappings_keep_black_and_order() {
+    for tone_mapping in TONE_MAPPINGS {
+        let display = DisplayTransform {
+            exposure: 0.0,
+            tone_mapping,
+        };
+        let black = display.apply(Color::ZERO);
+        assert!(
+            black.max_component() < tolerance(1e-9),
+            "{:?} maps black to {:?}",
+            tone_mapping,
+            black
+        );
+
+        // Gray ramp from black to far above white. AgX mixes the channels, so one of them can dip
+        // by a hair where another saturates first; brightness has to keep rising.
+        let luminance = |color: Color| 0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z;
+        let mut previous = black;
+        for step in 1..=2000 {
+            let value = step as Float / 100.0;
+            let mapped = display.apply(Color::new(value, value, value));
+            assert!(
+                luminance(mapped) >= luminance(previous),
+                "{:?} darkens from {:?} to {:?} at {}",
+                tone_mapping,
+                previous,
+                mapped,
+                value
+            );
+            previous = mapped;
+        }
+    }
+}
+
+/// Film of a flat gray wall facing the camera, every pixel a single sample of `color(index)`,
+/// with the AOVs that guide the denoiser.
+fn wall(width: usize, height: usize, color: impl Fn(usize) -> Color) -> Film {
+    let pixels = (0..width * height)
+        .map(|index| FilmPixel {
+            sum: color(index),
+            weight: 1.0,
+            samples: 1,
+        })
+        .collect();
+    let aovs = (0..width * height)
+        .map(|index| AovPixel {
+            albedo: Color::new(0.5, 0.5, 0.5),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            depth: 2.0,
+            hits: 1,
+            sum_squares: color(index) * color(index),
+            ..AovPixel::default()
+        })
+        .collect();
+    Film::from_pixels(width, height, pixels, Some(aovs)).unwrap()
+}
+
+/// Variance of the mean of the channels over the pixels away from the borders.
+fn interior_variance(film: &Film) -> Float {
+    let values: Vec<Float> = (4..film.height() - 4)
+        .flat_map(|y| (4..film.width() - 4).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let color = film.pixel(x, y);
+            (color.x + color.y + color.z) / 3.0
+        })
+        .collect();
+    let mean = values.iter().sum::<Float>() / values.len() as Float;
+    values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<Float>()
+        / values.len() as Float
+}
+
+#[test]
+fn denoising_keeps_a_constant_image() {
+    let color = Color::new(0.3, 0.2, 0.1);
+    let film = wall(24, 16, |_| color);
+    let denoised = denoise(&film, &DenoiseSettings::default()).unwrap();
+    for y in 0..film.height() {
+        for x in 0..film.width() {
+            let difference = (denoised.pixel(x, y) - color)
+                .map(Float::abs)
+                .max_component();
+            assert!(
+                difference < tolerance(1e-12),
+                "pixel ({}, {}) is {:?}",
+                x,
+                y,
+                denoised.pixel(x, y)
+            );
+        }
+    }
+}
+
+#[test]
+fn denoising_reduces_noise_on_a_flat_wall() {
+    seed_rng(5);
+    let noise: Vec<Float> = (0..48 * 32).map(|_| random_scalar() - 0.5).collect();
+    let film = wall(48, 32, |index| {
+        let value = 0.3 + 0.2 * noise[index];
+        Color::new(value, value, value)
+    });
+    let denoised = denoise(&film, &DenoiseSettings::default()).unwrap();
+
+    let (before, after) = (interior_variance(&film), interior_variance(&denoised));
+    assert!(
+        after < 0.1 * before,
+        "the variance only drops from {} to {}",
+        before,
+        after
+    );
+}
+
+/// Film of one color in every pixel.
+fn uniform(width: usize, height: usize, color: Color) -> Film {
+    Film::from_colors(width, height, vec![color; width * height]).unwrap()
+}
+
+#[test]
+fn identical_images_have_no_error() {
+    let image = wall(16, 12, |index| {
+        Color::new(index as Float / 192.0, 0.5, 0.25)
+    });
+    let metrics = compare(&image, &image).unwrap();
+    assert_eq!(metrics.mse, 0.0);
+    assert_eq!(metrics.rel_mse, 0.0);
+    assert_eq!(metrics.psnr, Float::INFINITY);
+    assert!(
+        (metrics.ssim - 1.0).abs() < tolerance(1e-9),
+        "SSIM {}",
+        metrics.ssim
+    );
+    assert_eq!(metrics.flip, 0.0);
+}
+
+#[test]
+fn metrics_of_known_differences() {
+    let (width, height) = (16, 12);
+    let gray = uniform(width, height, Color::new(0.5, 0.5, 0.5));
+    let brighter = uniform(width, height, Color::new(0.6, 0.6, 0.6));
+    let metrics = compare(&gray, &brighter).unwrap();
+    // Every channel is 0.1 off
+    assert!(
+        (metrics.mse - 0.01).abs() < tolerance(1e-12),
+        "MSE {}",
+        metrics.mse
+    );
+    assert!(
+        (metrics.psnr - 20.0).abs() < tolerance(1e-9),
+        "PSNR {}",
+        metrics.psnr
+    );
+    let rel_mse = 0.01 / (0.25 + 0.01);
+    assert!(
+        (metrics.rel_mse - rel_mse).abs() < tolerance(1e-12),
+        "relMSE {}",
+        metrics.rel_mse
+    );
+
+    let small_flip = metrics.flip;
+    assert!(small_flip > 0.0, "FLIP {}", small_flip);
+
+    // Black against white is close to the largest color difference FLIP knows, between green
+    // and blue. Flat images have no structure for SSIM to compare, only the difference of their
+    // means, which leaves C1 / (1 + C1)
+    let black = uniform(width, height, Color::new(0.0, 0.0, 0.0));
+    let white = uniform(width, height, Color::ONE);
+    let metrics = compare(&black, &white).unwrap();
+    assert!(
+        metrics.flip > 0.9 && metrics.flip <= 1.0 && metrics.flip > small_flip,
+        "FLIP {}",
+        metrics.flip
+    );
+    let ssim = 1e-4 / (1.0 + 1e-4);
+    assert!(
+        (metrics.ssim - ssim).abs() < tolerance(1e-12),
+        "SSIM {}",
+        metrics.ssim
+    );
+}
@@ -0,0 +1,371 @@
+//! Image files and checkpoints: what is written reads back, and corrupt files give errors
+//! instead of crashing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use exr::prelude::read_all_flat_layers_from_file;
+
+use raytracing_rust::output::aov::Aov;
+use raytracing_rust::output::checkpoint::{read_checkpoint, write_checkpoint};
+use raytracing_rust::output::image::{
+    read_image, write_image, ImageFormat, OutputOptions, PpmEncoding,
+};
+use raytracing_rust::rendering::{render_progressive, Camera, UberShader};
+use raytracing_rust::scene::presets::Preset;
+use raytracing_rust::{Color, Error, Film, Float, Point3, RenderSettings, Scene, Vec3};
+
+/// Path of a file in the scratch directory of the integration tests.
+fn temporary(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// Small film with a different color in every pixel.
+fn gradient() -> Film {
+    let (width, height) = (5, 3);
+    let colors = (0..width * height)
+        .map(|index| Color::new(index as Float / 15.0, 0.5, 1.0 - index as Float / 30.0))
+        .collect();
+    Film::from_colors(width, height, colors).unwrap()
+}
+
+fn options(format: ImageFormat) -> OutputOptions {
+    OutputOptions {
+        format,
+        ..OutputOptions::default()
+    }
+}
+
+fn assert_unreadable(path: &Path, what: &str) {
+    match read_image(path) {
+        Err(Error::ReadImage { .. }) => {}
+        Err(error) => panic!("{}: unexpected error {}", what, error),
+        Ok(_) => panic!("{} was read", what),
+    }
+}
+
+/// Largest difference between any channel of two films of the same size.
+fn max_difference(a: &Film, b: &Film) -> Float {
+    assert_eq!((a.width(), a.height()), (b.width(), b.height()));
+    let mut difference: Float = 0.0;
+    for y in 0..a.height() {
+        for x in 0..a.width() {
+            let (a, b) = (a.pixel(x, y), b.pixel(x, y));
+            for axis in 0..3 {
+                difference = difference.max((a[axis] - b[axis]).abs());
+            }
+        }
+    }
+    difference
+}
+
+/// Every channel of the first layer of an EXR file, by name.
+fn read_exr_channels(path: &Path) -> Vec<(String, Vec<f32>)> {
+    let image = read_all_flat_layers_from_file(path).unwrap();
+    image.layer_data[0]
+        .channel_data
+        .list
+        .iter()
+        .map(|channel| {
+            let values = channel.sample_data.values_as_f32().collect();
+            (channel.name.to_string(), values)
+        })
+        .collect()
+}
+
+#[test]
+fn images_read_back_what_was_written() {
+    let film = gradient();
+    let formats = [
+        // 8-bit sRGB values, a step is up to 1% in linear light
+        (
+            "round-trip.ppm",
+            ImageFormat::Ppm,
+            PpmEncoding::Binary,
+            0.01,
+        ),
+        (
+            "round-trip-ascii.ppm",
+            ImageFormat::Ppm,
+            PpmEncoding::Ascii,
+            0.01,
+        ),
+        // 8-bit mantissas with a shared exponent
+        (
+            "round-trip.hdr",
+            ImageFormat::Hdr,
+            PpmEncoding::Binary,
+            1.0 / 256.0,
+        ),
+        // 32-bit floats
+        (
+            "round-trip.exr",
+            ImageFormat::Exr,
+            PpmEncoding::Binary,
+            1e-6,
+        ),
+    ];
+    for (name, format, ppm_encoding, tolerance) in formats {
+        let path = temporary(name);
+        let options = OutputOptions {
+            ppm_encoding,
+            ..options(format)
+        };
+        write_image(&path, &film, &options).unwrap();
+        let difference = max_difference(&read_image(&path).unwrap(), &film);
+        assert!(
+            difference <= tolerance,
+            "{} differs by {}",
+            name,
+            difference
+        );
+    }
+}
+
+#[test]
+fn exr_layers_are_named_after_the_render_passes() {
+    let settings = checkpoint_settings(1);
+    let scene = Preset::FourSpheres.build().unwrap();
+    let mut film = Film::with_aovs(settings.image_width, settings.image_height);
+    render_progressive(&settings, &scene, &mut film, |_| {}, |_| {}).unwrap();
+
+    let path = temporary("layers.exr");
+    let options = OutputOptions {
+        aovs: vec![Aov::Albedo, Aov::Depth, Aov::SampleCount],
+        ..options(ImageFormat::Exr)
+    };
+    write_image(&path, &film, &options).unwrap();
+    let mut names: Vec<String> = read_exr_channels(&path)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    let expected = [
+        "B",
+        "G",
+        "R",
+        "albedo.B",
+        "albedo.G",
+        "albedo.R",
+        "depth.Z",
+        "sample_count.count",
+    ];
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn aov_layers_hold_the_first_hits() {
+    // The camera looks down -z at a wall that fills the view
+    let albedo = Color::new(0.2, 0.4, 0.6);
+    let scene = Scene::builder()
+        .camera(Camera::new())
+        .plane(
+            Point3::new(0.0, 0.0, -2.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            UberShader::new(albedo, false, 0.0).with_id(5),
+        )
+        .build()
+        .unwrap();
+    let settings = checkpoint_settings(4);
+    let mut film = Film::with_aovs(settings.image_width, settings.image_height);
+    render_progressive(&settings, &scene, &mut film, |_| {}, |_| {}).unwrap();
+
+    let path = temporary("aovs.exr");
+    let options = OutputOptions {
+        aovs: vec![Aov::Albedo, Aov::Normal, Aov::Depth, Aov::MaterialId],
+        ..options(ImageFormat::Exr)
+    };
+    write_image(&path, &film, &options).unwrap();
+    let channels = read_exr_channels(&path);
+    let channel = |name: &str| {
+        let (_, values) = channels
+            .iter()
+            .find(|(channel, _)| channel == name)
+            .unwrap_or_else(|| panic!("no {} channel", name));
+        values
+    };
+
+    let expected = [
+        ("albedo.R", 0.2),
+        ("albedo.G", 0.4),
+        ("albedo.B", 0.6),
+        ("normal.X", 0.0),
+        ("normal.Y", 0.0),
+        ("normal.Z", 1.0),
+        ("material_id.id", 5.0),
+    ];
+    for (name, expected) in expected {
+        for (index, &value) in channel(name).iter().enumerate() {
+            assert!(
+                (value - expected).abs() < 1e-5,
+                "{} is {} in pixel {}, not {}",
+                name,
+                value,
+                index,
+                expected
+            );
+        }
+    }
+
+    // Distance from the camera: 2 straight ahead, further towards the corners of the view
+    let depth = channel("depth.Z");
+    let width = settings.image_width;
+    let center = depth[settings.image_height / 2 * width + width / 2];
+    assert!((center - 2.0).abs() < 0.1, "depth {} in the center", center);
+    assert!(depth[0] > center + 0.5, "depth {} in the corner", depth[0]);
+    assert!(depth.iter().all(|&depth| (2.0..4.6).contains(&depth)));
+}
+
+#[test]
+fn truncated_images_are_rejected() {
+    for (name, format) in [
+        ("truncated.ppm", ImageFormat::Ppm),
+        ("truncated.hdr", ImageFormat::Hdr),
+    ] {
+        let path = temporary(name);
+        write_image(&path, &gradient(), &options(format)).unwrap();
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..data.len() - 7]).unwrap();
+        assert_unreadable(&path, name);
+    }
+}
+
+#[test]
+fn oversized_headers_are_rejected() {
+    let headers: [(&str, &[u8]); 6] = [
+        // The pixel count overflows
+        (
+            "overflow-p6.ppm",
+            b"P6\n4294967296 4294967297\n255\n\x00\x00\x00",
+        ),
+        (
+            "overflow-p3.ppm",
+            b"P3\n18446744073709551615 3\n255\n0 0 0\n",
+        ),
+        (
+            "overflow.hdr",
+            b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 4294967296 +X 4294967297\n\x00\x00\x00\x00",
+        ),
+        // Too large to be backed by the data that follows
+        ("large-p6.ppm", b"P6\n10000 10000\n255\n\x00\x00\x00"),
+        ("large-p3.ppm", b"P3\n10000 10000\n255\n0 0 0\n"),
+        (
+            "large.hdr",
+            b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 10000 +X 10000\n\x00\x00\x00\x00",
+        ),
+    ];
+    for (name, data) in headers {
+        let path = temporary(name);
+        fs::write(&path, data).unwrap();
+        assert_unreadable(&path, name);
+    }
+}
+
+fn checkpoint_settings(samples_per_pixel: i32) -> RenderSettings {
+    RenderSettings {
+        image_width: 16,
+        image_height: 9,
+        samples_per_pixel,
+        max_depth: 8,
+        seed: 3,
+        ..RenderSettings::default()
+    }
+}
+
+/// Writes a checkpoint of a few samples of `four-spheres`.
+fn write_test_checkpoint(name: &str) -> PathBuf {
+    let settings = checkpoint_settings(2);
+    let scene = Preset::FourSpheres.build().unwrap();
+    let mut film = Film::with_aovs(settings.image_width, settings.image_height);
+    render_progressive(&settings, &scene, &mut film, |_| {}, |_| {}).unwrap();
+    let path = temporary(name);
+    write_checkpoint(
+        &path,
+        &film,
+        Preset::FourSpheres.name(),
+        settings.seed,
+        settings.max_depth,
+        false,
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn resumed_render_matches_an_uninterrupted_one() {
+    let path = write_test_checkpoint("resume.ckpt");
+    let checkpoint = read_checkpoint(&path).unwrap();
+    assert_eq!(checkpoint.scene, Preset::FourSpheres.name());
+    assert_eq!(checkpoint.film.min_samples(), 2);
+
+    let settings = checkpoint_settings(5);
+    let scene = Preset::FourSpheres.build().unwrap();
+    checkpoint
+        .check_resumable(
+            Preset::FourSpheres.name(),
+            settings.image_width,
+            settings.image_height,
+            false,
+        )
+        .unwrap();
+    let mut resumed = checkpoint.film;
+    render_progressive(&settings, &scene, &mut resumed, |_| {}, |_| {}).unwrap();
+
+    let mut uninterrupted = Film::with_aovs(settings.image_width, settings.image_height);
+    render_progressive(&settings, &scene, &mut uninterrupted, |_| {}, |_| {}).unwrap();
+    for y in 0..settings.image_height {
+        for x in 0..settings.image_width {
+            assert_eq!(
+                resumed.pixel(x, y),
+                uninterrupted.pixel(x, y),
+                "pixel ({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn checkpoints_of_other_renders_are_rejected() {
+    let path = write_test_checkpoint("mismatch.ckpt");
+    let checkpoint = read_checkpoint(&path).unwrap();
+    let settings = checkpoint_settings(4);
+    let (width, height) = (settings.image_width, settings.image_height);
+    for (scene, width, height, spectral) in [
+        (Preset::CornellBox.name(), width, height, false),
+        (Preset::FourSpheres.name(), width + 1, height, false),
+        (Preset::FourSpheres.name(), width, height, true),
+    ] {
+        assert!(
+            matches!(
+                checkpoint.check_resumable(scene, width, height, spectral),
+                Err(Error::InvalidSettings(_))
+            ),
+            "resumed {} at {}x{}, spectral {}",
+            scene,
+            width,
+            height,
+            spectral
+        );
+    }
+}
+
+#[test]
+fn corrupt_checkpoints_are_rejected() {
+    let path = write_test_checkpoint("corrupt.ckpt");
+    let data = fs::read(&path).unwrap();
+
+    let mut wrong_magic = data.clone();
+    wrong_magic[..8].copy_from_slice(b"RTCKPT00");
+    let truncated = data[..data.len() - 100].to_vec();
+    for (name, data) in [("magic.ckpt", wrong_magic), ("truncated.ckpt", truncated)] {
+        let path = temporary(name);
+        fs::write(&path, data).unwrap();
+        assert!(
+            matches!(read_checkpoint(&path), Err(Error::ReadCheckpoint { .. })),
+            "{} was read",
+            name
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Renders the built-in scenes at a low resolution with a fixed seed and compares them against the
+//! references in tests/references. Rendering is deterministic, so any change to the images shows
+//! up here. After an intended change, regenerate the references with
+//! `UPDATE_REFERENCES=1 cargo test --test regression` and commit them.
+//!
+//! The references are rendered in f64. An f32 build rounds differently, so some paths take
+//! other turns and its images only match up to noise; they are compared with a looser tolerance.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use raytracing_rust::compare::{compare, error_map, ErrorMap};
+use raytracing_rust::output::image::{read_image, write_image, ImageFormat, OutputOptions};
+use raytracing_rust::scene::presets::Preset;
+use raytracing_rust::{Film, Float, RenderSettings, Scene};
+
+const WIDTH: usize = 48;
+const SAMPLES: i32 = 16;
+const SEED: u64 = 7;
+/// Largest mean FLIP error accepted. A different noise pattern alone is well above this.
+const TOLERANCE: Float = 0.005;
+/// Largest mean FLIP error of an f32 render. Most scenes stay below 0.01, the media of
+/// `smoky-room` and the texture of `textured-plane` reach 0.045.
+const F32_TOLERANCE: Float = 0.06;
+/// The walls of `cornell-box` are spheres with a radius of 1e5, which f32 hits with visible
+/// artifacts (0.153 against the f64 reference).
+const F32_CORNELL_BOX_TOLERANCE: Float = 0.2;
+
+fn exr_options() -> OutputOptions {
+    OutputOptions {
+        format: ImageFormat::Exr,
+        ..OutputOptions::default()
+    }
+}
+
+fn render(preset: Preset) -> Film {
+    let scene = preset.build().unwrap();
+    scene.render(&settings(preset, &scene)).unwrap()
+}
+
+fn settings(preset: Preset, scene: &Scene) -> RenderSettings {
+    RenderSettings {
+        image_width: WIDTH,
+        image_height: (WIDTH as Float / scene.camera.aspect_ratio()).round() as usize,
+        samples_per_pixel: SAMPLES,
+        max_depth: 8,
+        seed: SEED,
+        // Dispersion only shows when tracing wavelengths
+        spectral: preset == Preset::Dispersion,
+        ..RenderSettings::default()
+    }
+}
+
+fn tolerance(preset: Preset) -> Float {
+    match (cfg!(feature = "f32"), preset) {
+        (false, _) => TOLERANCE,
+        (true, Preset::CornellBox) => F32_CORNELL_BOX_TOLERANCE,
+        (true, _) => F32_TOLERANCE,
+    }
+}
+
+fn reference_path(preset: Preset) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/references")
+        .join(format!("{}.exr", preset.name()))
+}
+
+fn check(preset: Preset) {
+    let film = render(preset);
+    let path = reference_path(preset);
+    if env::var_os("UPDATE_REFERENCES").is_some() {
+        write_image(&path, &film, &exr_options()).unwrap();
+        return;
+    }
+
+    let reference = read_image(&path).unwrap();
+    let metrics = compare(&reference, &film).unwrap();
+    if metrics.flip > tolerance(preset) {
+        // Keep the render and where it differs around for inspection
+        let output = Path::new(env!("CARGO_TARGET_TMPDIR"));
+        let render_path = output.join(format!("{}.exr", preset.name()));
+        let error_path = output.join(format!("{}-flip.exr", preset.name()));
+        write_image(&render_path, &film, &exr_options()).unwrap();
+        let errors = error_map(&reference, &film, ErrorMap::Flip).unwrap();
+        write_image(&error_path, &errors, &exr_options()).unwrap();
+        panic!(
+            "{} differs from its reference: {:?}, see {} and {}",
+            preset.name(),
+            metrics,
+            render_path.display(),
+            error_path.display()
+        );
+    }
+}
+
+#[test]
+fn four_spheres() {
+    check(Preset::FourSpheres);
+}
+
+#[test]
+fn cornell_box() {
+    check(Preset::CornellBox);
+}
+
+#[test]
+fn cornell_quads() {
+    check(Preset::CornellQuads);
+}
+
+#[test]
+fn smoky_room() {
+    check(Preset::SmokyRoom);
+}
+
+#[test]
+fn haze() {
+    check(Preset::Haze);
+}
+
+#[test]
+fn clouds() {
+    check(Preset::Clouds);
+}
+
+#[test]
+fn glass_spheres() {
+    check(Preset::GlassSpheres);
+}
+
+#[test]
+fn dispersion() {
+    check(Preset::Dispersion);
+}
+
+#[test]
+fn translucent() {
+    check(Preset::Translucent);
+}
+
+#[test]
+fn textured_plane() {
+    check(Preset::TexturedPlane);
+}
+
+#[test]
+fn quadrics() {
+    check(Preset::Quadrics);
+}
+
+#[test]
+fn csg() {
+    check(Preset::Csg);
+}
+
+#[test]
+fn distance_fields() {
+    check(Preset::DistanceFields);
+}
+
+#[test]
+fn instances() {
+    check(Preset::Instances);
+}
+
+#[test]
+fn packets_render_the_same_image() {
+    for preset in [Preset::Instances, Preset::Dispersion] {
+        let scene = preset.build().unwrap();
+        let film = scene.render(&settings(preset, &scene)).unwrap();
+        let packets = RenderSettings {
+            packets: true,
+            ..settings(preset, &scene)
+        };
+        let packet_film = scene.render(&packets).unwrap();
+        for (pixel, packet_pixel) in film.pixels().iter().zip(packet_film.pixels()) {
+            assert_eq!(pixel.color(), packet_pixel.color(), "{}", preset.name());
+        }
+    }
+}
@@ -0,0 +1,929 @@
+//! Statistical checks of the sampling routines and materials. Every test seeds the random number
+//! generator, so the outcome is deterministic.
+
+#![cfg_attr(feature = "f32", allow(clippy::excessive_precision))]
+
+use std::sync::Arc;
+
+use raytracing_rust::hittables::{Block, Disk, HitRecord, Primitive, Quad, Sphere, Volume};
+use raytracing_rust::math::{random_scalar, seed_rng, Onb, PI};
+use raytracing_rust::rendering::bsdf::{Bsdf, Ggx, Lambertian};
+use raytracing_rust::rendering::sampling::{trace_path, trace_spectral_path};
+use raytracing_rust::rendering::{
+    cie_xyz, Background, DensityGrid, Fog, HenyeyGreenstein, Light, Material, Medium,
+    SampledWavelengths, UberShader,
+};
+use raytracing_rust::{Color, Float, HittableList, Normal3, Point3, Ray, Vec3};
+
+mod common;
+use common::{tolerance, unit_sphere};
+
+const SAMPLES: usize = 200_000;
+/// Chi-square tests fail below this p-value.
+const SIGNIFICANCE: Float = 0.01;
+
+/// Largest difference between the channels of two colors.
+fn color_distance(a: Color, b: Color) -> Float {
+    (a - b).map(Float::abs).max_component()
+}
+
+/// Bins unit directions by cos(theta) and phi, compares the counts with the ones expected from
+/// integrating `pdf` over every bin, and asserts the chi-square test passes. `sample` returns
+/// `None` for absorbed samples, which `pdf` doesn't integrate to 1 to account for.
+fn chi_square_directions(
+    name: &str,
+    mut sample: impl FnMut() -> Option<Vec3>,
+    pdf: impl Fn(Vec3) -> Float,
+) {
+    const THETA_BINS: usize = 16;
+    const PHI_BINS: usize = 32;
+    const SUBDIVISIONS: usize = 16;
+    let bin_of = |direction: Vec3| {
+        let cos_theta = direction.z.clamp(-1.0, 1.0);
+        let phi = direction.y.atan2(direction.x).rem_euclid(2.0 * PI);
+        let theta_bin =
+            (((cos_theta + 1.0) / 2.0 * THETA_BINS as Float) as usize).min(THETA_BINS - 1);
+        let phi_bin = ((phi / (2.0 * PI) * PHI_BINS as Float) as usize).min(PHI_BINS - 1);
+        theta_bin * PHI_BINS + phi_bin
+    };
+
+    let mut observed = vec![0.0; THETA_BINS * PHI_BINS];
+    for _ in 0..SAMPLES {
+        if let Some(direction) = sample() {
+            observed[bin_of(direction.unit_vector())] += 1.0;
+        }
+    }
+
+    // Midpoint rule over the bin, with a solid angle element of d(cos theta) d(phi)
+    let cos_step = 2.0 / THETA_BINS as Float;
+    let phi_step = 2.0 * PI / PHI_BINS as Float;
+    let mut expected = vec![0.0; THETA_BINS * PHI_BINS];
+    for theta_bin in 0..THETA_BINS {
+        for phi_bin in 0..PHI_BINS {
+            let mut integral = 0.0;
+            for i in 0..SUBDIVISIONS {
+                for j in 0..SUBDIVISIONS {
+                    let cos_theta = -1.0
+                        + (theta_bin as Float + (i as Float + 0.5) / SUBDIVISIONS as Float)
+                            * cos_step;
+                    let phi =
+                        (phi_bin as Float + (j as Float + 0.5) / SUBDIVISIONS as Float) * phi_step;
+                    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                    let direction =
+                        Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+                    integral += pdf(direction);
+                }
+            }
+            expected[theta_bin * PHI_BINS + phi_bin] = integral * cos_step * phi_step
+                / (SUBDIVISIONS * SUBDIVISIONS) as Float
+                * SAMPLES as Float;
+        }
+    }
+
+    assert_chi_square(name, &observed, &expected);
+}
+
+/// Pools bins with too few expected samples, as the test is only valid for large counts, then
+/// checks the counts against the expectation.
+fn assert_chi_square(name: &str, observed: &[Float], expected: &[Float]) {
+    const MIN_EXPECTED: Float = 5.0;
+    let mut order: Vec<usize> = (0..expected.len()).collect();
+    order.sort_by(|a, b| expected[*a].total_cmp(&expected[*b]));
+
+    let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+    let mut statistic = 0.0;
+    let mut bins = 0;
+    for index in order {
+        if expected[index] == 0.0 {
+            assert_eq!(
+                observed[index], 0.0,
+                "{}: samples landed in bin {} where the density is 0",
+                name, index
+            );
+            continue;
+        }
+        if expected[index] < MIN_EXPECTED {
+            pooled_observed += observed[index];
+            pooled_expected += expected[index];
+            continue;
+        }
+        let difference = observed[index] - expected[index];
+        statistic += difference * difference / expected[index];
+        bins += 1;
+    }
+    if pooled_expected > 0.0 {
+        let difference = pooled_observed - pooled_expected;
+        statistic += difference * difference / pooled_expected;
+        bins += 1;
+    }
+
+    let p_value = chi_square_survival(statistic, (bins - 1) as Float);
+    assert!(
+        p_value > SIGNIFICANCE,
+        "{}: chi-square statistic {:.1} with {} degrees of freedom, p-value {:.2e}",
+        name,
+        statistic,
+        bins - 1,
+        p_value
+    );
+}
+
+/// Probability of a chi-square statistic at least this large, with the Wilson-Hilferty
+/// approximation, which is accurate for the degrees of freedom used here.
+fn chi_square_survival(statistic: Float, degrees_of_freedom: Float) -> Float {
+    let k = degrees_of_freedom;
+    let z = ((statistic / k).cbrt() - (1.0 - 2.0 / (9.0 * k))) / (2.0 / (9.0 * k)).sqrt();
+    0.5 * erfc(z / Float::sqrt(2.0))
+}
+
+/// Complementary error function, Numerical Recipes' erfcc with a relative error below 1.2e-7.
+fn erfc(x: Float) -> Float {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * polynomial.exp();
+    match x >= 0.0 {
+        true => result,
+        false => 2.0 - result,
+    }
+}
+
+fn tilted_frame() -> Onb {
+    Onb::from_w(Vec3::new(0.3, -0.5, 0.8))
+}
+
+/// Random direction in the upper hemisphere, uniform in solid angle.
+fn random_upper_direction() -> Vec3 {
+    let direction = Vec3::random_point_in_unit_vector();
+    Vec3::new(direction.x, direction.y, direction.z.abs())
+}
+
+#[test]
+fn points_in_unit_sphere_are_uniform() {
+    seed_rng(1);
+    const BINS: usize = 10;
+    let mut observed = vec![0.0; BINS * BINS * BINS];
+    for _ in 0..SAMPLES {
+        let point = Vec3::random_point_in_unit_sphere();
+        assert!(point.squared_length() < 1.0);
+        // Uniform in the ball means r^3, cos(theta) and phi are independent and uniform
+        let r3 = point.length().powi(3);
+        let cos_theta = point.z / point.length();
+        let phi = point.y.atan2(point.x).rem_euclid(2.0 * PI);
+        let index = |value: Float| ((value * BINS as Float) as usize).min(BINS - 1);
+        let bin = index(r3) * BINS * BINS
+            + index((cos_theta + 1.0) / 2.0) * BINS
+            + index(phi / (2.0 * PI));
+        observed[bin] += 1.0;
+    }
+    let expected = vec![SAMPLES as Float / (BINS * BINS * BINS) as Float; BINS * BINS * BINS];
+    assert_chi_square("unit ball", &observed, &expected);
+}
+
+#[test]
+fn unit_vectors_are_uniform() {
+    seed_rng(2);
+    chi_square_directions(
+        "unit sphere",
+        || Some(Vec3::random_point_in_unit_vector()),
+        |_| 1.0 / (4.0 * PI),
+    );
+}
+
+#[test]
+fn hemisphere_points_are_uniform() {
+    seed_rng(3);
+    let frame = tilted_frame();
+    chi_square_directions(
+        "hemisphere",
+        || Some(frame.to_local(Vec3::random_point_in_hemisphere(frame.w))),
+        |direction| match direction.z > 0.0 {
+            true => 1.0 / (2.0 * PI),
+            false => 0.0,
+        },
+    );
+}
+
+#[test]
+fn cosine_directions_match_pdf() {
+    seed_rng(4);
+    chi_square_directions(
+        "cosine direction",
+        || Some(Vec3::random_cosine_direction()),
+        |direction| direction.z.max(0.0) / PI,
+    );
+}
+
+#[test]
+fn lambertian_scattering_is_cosine_weighted() {
+    seed_rng(5);
+    let frame = tilted_frame();
+    let material = UberShader::new(Color::new(0.5, 0.5, 0.5), false, 0.0);
+    let record = HitRecord::new(Point3::ORIGIN, Normal3::from(frame.w), 1.0, true, material);
+    let ray_in = Ray::new(Point3::ORIGIN + frame.w, -frame.w);
+    chi_square_directions(
+        "lambertian scatter",
+        || {
+            let scattered = material.scatter(&ray_in, &record)?;
+            Some(frame.to_local(scattered.ray.direction))
+        },
+        |direction| direction.z.max(0.0) / PI,
+    );
+}
+
+#[test]
+fn ggx_normals_match_pdf() {
+    for (seed, roughness) in [(6, 0.5), (7, 0.7), (8, 1.0)] {
+        seed_rng(seed);
+        let ggx = Ggx::from_roughness(Color::ONE, roughness);
+        chi_square_directions(
+            &format!("GGX normals, roughness {}", roughness),
+            || Some(ggx.sample_normal()),
+            |half| ggx.normal_pdf(half),
+        );
+    }
+}
+
+#[test]
+fn ggx_directions_match_pdf() {
+    for (seed, roughness) in [(9, 0.5), (10, 0.8)] {
+        seed_rng(seed);
+        let ggx = Ggx::from_roughness(Color::ONE, roughness);
+        let wo = Vec3::new(0.4, 0.1, 0.9).unit_vector();
+        chi_square_directions(
+            &format!("GGX directions, roughness {}", roughness),
+            || ggx.sample(wo),
+            |wi| ggx.pdf(wo, wi),
+        );
+    }
+}
+
+#[test]
+fn bsdfs_are_reciprocal() {
+    seed_rng(11);
+    let bsdfs: Vec<(&str, Box<dyn Bsdf>)> = vec![
+        (
+            "lambertian",
+            Box::new(Lambertian {
+                albedo: Color::new(0.8, 0.5, 0.2),
+            }),
+        ),
+        (
+            "GGX",
+            Box::new(Ggx::from_roughness(Color::new(0.9, 0.6, 0.3), 0.4)),
+        ),
+    ];
+    for (name, bsdf) in &bsdfs {
+        for _ in 0..1000 {
+            let (wo, wi) = (random_upper_direction(), random_upper_direction());
+            let forward = bsdf.eval(wo, wi);
+            let backward = bsdf.eval(wi, wo);
+            assert!(
+                color_distance(forward, backward)
+                    <= tolerance(1e-9) * forward.max_component().max(1.0),
+                "{}: f({:?}, {:?}) = {:?} but the reverse is {:?}",
+                name,
+                wo,
+                wi,
+                forward,
+                backward
+            );
+        }
+    }
+}
+
+/// Fraction of the light arriving from above that `bsdf` reflects towards `wo`.
+fn directional_albedo(bsdf: &dyn Bsdf, wo: Vec3) -> Float {
+    let mut sum = 0.0;
+    for _ in 0..SAMPLES {
+        if let Some(wi) = bsdf.sample(wo) {
+            let pdf = bsdf.pdf(wo, wi);
+            if pdf > 0.0 {
+                sum += bsdf.eval(wo, wi).x * wi.z / pdf;
+            }
+        }
+    }
+    sum / SAMPLES as Float
+}
+
+#[test]
+fn bsdfs_do_not_create_energy() {
+    seed_rng(12);
+    let lambertian = Lambertian { albedo: Color::ONE };
+    for _ in 0..4 {
+        let wo = random_upper_direction();
+        let albedo = directional_albedo(&lambertian, wo);
+        assert!(
+            (albedo - 1.0).abs() < 1e-9,
+            "white lambertian reflects {}",
+            albedo
+        );
+    }
+
+    for roughness in [0.1, 0.5, 1.0] {
+        let ggx = Ggx::from_roughness(Color::ONE, roughness);
+        for cos_theta in [0.1 as Float, 0.5, 0.9] {
+            let wo = Vec3::new((1.0 - cos_theta * cos_theta).sqrt(), 0.0, cos_theta);
+            let albedo = directional_albedo(&ggx, wo);
+            // Single scattering GGX loses the light that bounces between microfacets
+            assert!(
+                albedo <= 1.0 + 1e-2 && albedo > 0.3,
+                "white GGX with roughness {} reflects {} at cos(theta) {}",
+                roughness,
+                albedo,
+                cos_theta
+            );
+        }
+    }
+}
+
+/// Average radiance of paths from random points around a sphere towards it, inside a furnace
+/// that is uniformly white.
+fn furnace(material: UberShader) -> Float {
+    furnace_with(Sphere::new(Point3::ORIGIN, 1.0, material).into(), None)
+}
+
+/// Furnace around any object, optionally filled with fog.
+fn furnace_with(object: Primitive, fog: Option<&Fog>) -> Float {
+    let world = HittableList {
+        objects: vec![object],
+    };
+    let background = Background::Uniform(Color::ONE);
+    const PATHS: usize = 20_000;
+    let mut sum = 0.0;
+    for _ in 0..PATHS {
+        let origin = Point3::ORIGIN + 3.0 * Vec3::random_point_in_unit_vector();
+        let target = Point3::ORIGIN + 0.9 * Vec3::random_point_in_unit_sphere();
+        let ray = Ray::new(origin, target - origin);
+        let sample = trace_path(ray, &world, &[], &background, fog, 1000);
+        sum += (sample.color.x + sample.color.y + sample.color.z) / 3.0;
+    }
+    sum / PATHS as Float
+}
+
+#[test]
+fn white_furnace() {
+    seed_rng(13);
+    let lossless = [
+        ("lambertian", UberShader::new(Color::ONE, false, 0.0)),
+        ("mirror", UberShader::new(Color::ONE, true, 0.0)),
+        ("glass", UberShader::dielectric(1.5)),
+    ];
+    for (name, material) in lossless {
+        let radiance = furnace(material);
+        assert!(
+            (radiance - 1.0).abs() < 1e-6,
+            "white {} returns {} in the furnace",
+            name,
+            radiance
+        );
+    }
+
+    // Rays scattered below the surface are absorbed, so these may lose energy but never gain it
+    let lossy = [
+        ("fuzzy metal", UberShader::new(Color::ONE, true, 0.5)),
+        ("glossy", UberShader::glossy(Color::ONE, 0.5)),
+    ];
+    for (name, material) in lossy {
+        let radiance = furnace(material);
+        assert!(
+            radiance <= 1.0 + 1e-2 && radiance > 0.5,
+            "white {} returns {} in the furnace",
+            name,
+            radiance
+        );
+    }
+}
+
+fn test_lights() -> [(&'static str, Light); 2] {
+    let light = UberShader::light(Color::ONE);
+    [
+        (
+            "quad",
+            Light::Quad(Quad::new(
+                Point3::new(-1.0, 1.0, -0.5),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.5, 0.5, 1.5),
+                light,
+            )),
+        ),
+        (
+            "disk",
+            Light::Disk(Disk::new(
+                Point3::new(0.5, 1.5, 0.0),
+                Vec3::new(1.0, -1.0, 0.5),
+                1.0,
+                light,
+            )),
+        ),
+    ]
+}
+
+#[test]
+fn light_samples_match_their_pdf() {
+    seed_rng(17);
+    let from = Point3::new(0.2, -0.3, 0.1);
+    for (name, light) in test_lights() {
+        // The mean of 1 / pdf is the solid angle the light covers, which uniform directions
+        // estimate independently
+        let inverse_pdf = (0..SAMPLES)
+            .map(|_| 1.0 / light.sample(from).pdf)
+            .sum::<Float>()
+            / SAMPLES as Float;
+        let hits = (0..SAMPLES)
+            .filter(|_| {
+                let ray = Ray::new(from, Vec3::random_point_in_unit_vector());
+                light.pdf(&ray, Float::INFINITY) > 0.0
+            })
+            .count();
+        let solid_angle = 4.0 * PI * hits as Float / SAMPLES as Float;
+        assert!(
+            (inverse_pdf - solid_angle).abs() < 0.02 * solid_angle,
+            "{} light covers {} sr, its samples average {}",
+            name,
+            solid_angle,
+            inverse_pdf
+        );
+
+        for _ in 0..1000 {
+            let sample = light.sample(from);
+            let ray = Ray::new(from, sample.direction);
+            let pdf = light.pdf(&ray, Float::INFINITY);
+            assert!(
+                (pdf - sample.pdf).abs() < 1e-6 * sample.pdf,
+                "{} light samples with density {}, but reports {}",
+                name,
+                sample.pdf,
+                pdf
+            );
+        }
+    }
+}
+
+#[test]
+fn light_sampling_is_unbiased() {
+    seed_rng(19);
+    for (name, light) in test_lights() {
+        let emitter: Primitive = match &light {
+            Light::Quad(quad) => quad.clone().into(),
+            Light::Disk(disk) => disk.clone().into(),
+        };
+        let floor = Quad::new(
+            Point3::new(-3.0, -1.0, 3.0),
+            Vec3::new(6.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -6.0),
+            UberShader::new(Color::new(0.8, 0.8, 0.8), false, 0.0),
+        );
+        let world = HittableList {
+            objects: vec![emitter, floor.into()],
+        };
+        let background = Background::Uniform(Color::ZERO);
+        let lights = [light];
+        let mean = |lights: &[Light]| {
+            const PATHS: usize = 400_000;
+            let sum: Float = (0..PATHS)
+                .map(|_| {
+                    let target = Point3::new(random_scalar() - 0.5, -1.0, random_scalar() - 0.5);
+                    let origin = Point3::new(0.0, 0.0, 4.0);
+                    let ray = Ray::new(origin, target - origin);
+                    trace_path(ray, &world, lights, &background, None, 4)
+                        .color
+                        .x
+                })
+                .sum();
+            sum / PATHS as Float
+        };
+        let sampled = mean(&lights);
+        let unsampled = mean(&[]);
+        assert!(
+            (sampled - unsampled).abs() < 0.03 * unsampled,
+            "{} light gives {} with light sampling and {} without",
+            name,
+            sampled,
+            unsampled
+        );
+    }
+}
+
+#[test]
+fn phase_function_samples_match_its_density() {
+    seed_rng(29);
+    let frame = tilted_frame();
+    for asymmetry in [0.0, 0.6, -0.3] {
+        let phase = HenyeyGreenstein { asymmetry };
+        chi_square_directions(
+            &format!("Henyey-Greenstein, asymmetry {}", asymmetry),
+            || Some(frame.to_local(phase.sample(frame.w))),
+            |direction| phase.eval(frame.w, frame.to_world(direction)),
+        );
+    }
+    // Forward scattering keeps light going the way it went, arriving from -wo
+    let forward = HenyeyGreenstein { asymmetry: 0.8 };
+    let wo = Vec3::new(0.0, 0.0, 1.0);
+    assert!(forward.eval(wo, -wo) > 10.0 * forward.eval(wo, wo));
+}
+
+#[test]
+fn media_without_absorption_keep_all_light() {
+    seed_rng(31);
+    let white = Medium::fog(2.0, Color::ONE).with_asymmetry(0.5);
+    // Denser in blue than in red, so channels are sampled with different distances
+    let tinted = Medium::new(Color::ZERO, Color::new(0.5, 1.0, 3.0));
+    let sphere = |material| Sphere::new(Point3::ORIGIN, 1.0, material);
+    // Grey media scatter every channel alike and return what they get, but for the rare path
+    // that scatters too close to a surface to see it. The weights of distances sampled for one
+    // channel of a tinted medium only average out.
+    let cases: [(&str, Primitive, Option<Fog>, Float); 4] = [
+        (
+            "white volume",
+            Volume::new(unit_sphere(), white).into(),
+            None,
+            1e-3,
+        ),
+        (
+            "glass with a medium",
+            sphere(UberShader::dielectric(1.5).with_interior(white)).into(),
+            None,
+            1e-3,
+        ),
+        (
+            "fog",
+            sphere(UberShader::new(Color::ONE, false, 0.0)).into(),
+            Some(Fog::new(white, 5.0)),
+            1e-3,
+        ),
+        (
+            "tinted volume",
+            Volume::new(unit_sphere(), tinted).into(),
+            None,
+            0.03,
+        ),
+    ];
+    for (name, object, fog, tolerance) in cases {
+        let radiance = furnace_with(object, fog.as_ref());
+        assert!(
+            (radiance - 1.0).abs() < tolerance,
+            "{} returns {} in the furnace",
+            name,
+            radiance
+        );
+    }
+}
+
+#[test]
+fn media_attenuate_light_exponentially() {
+    seed_rng(37);
+    let world: HittableList<Primitive> = HittableList { objects: vec![] };
+    let background = Background::Uniform(Color::ONE);
+    // Only absorbs, 2 units of fog at 0.5 per unit leave e^-1 of the light in every channel
+    let fog = Fog::new(Medium::new(Color::from_float(0.5), Color::ZERO), 2.0);
+    const PATHS: usize = 20_000;
+    let sum = (0..PATHS)
+        .map(|_| {
+            let ray = Ray::new(Point3::ORIGIN, Vec3::random_point_in_unit_vector());
+            trace_path(ray, &world, &[], &background, Some(&fog), 10).color
+        })
+        .fold(Color::ZERO, |sum, color| sum + color);
+    let mean = sum / PATHS as Float;
+    let expected = Float::exp(-1.0);
+    for channel in 0..3 {
+        assert!(
+            (mean[channel] - expected).abs() < 0.02,
+            "{:?} gets through the fog, expected {}",
+            mean,
+            expected
+        );
+    }
+}
+
+#[test]
+fn light_sampling_through_media_is_unbiased() {
+    seed_rng(41);
+    let smoke = Medium::new(Color::new(0.1, 0.2, 0.3), Color::from_float(1.5)).with_asymmetry(0.4);
+    assert_light_sampling_is_unbiased("smoke", Volume::new(unit_sphere(), smoke).into());
+}
+
+/// Compares the light reaching the camera through `object` and thin fog with and without
+/// sampling the light, which adds up the same light in expectation.
+fn assert_light_sampling_is_unbiased(name: &str, object: Primitive) {
+    let light = Quad::new(
+        Point3::new(-1.0, 2.0, -1.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 2.0),
+        UberShader::light(Color::ONE),
+    );
+    let world: HittableList<Primitive> = HittableList {
+        objects: vec![
+            light.clone().into(),
+            object,
+            Quad::new(
+                Point3::new(-3.0, -1.0, 3.0),
+                Vec3::new(6.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, -6.0),
+                UberShader::new(Color::new(0.8, 0.8, 0.8), false, 0.0),
+            )
+            .into(),
+        ],
+    };
+    let fog = Fog::new(Medium::fog(0.1, Color::from_float(0.9)), 10.0);
+    let background = Background::Uniform(Color::ZERO);
+    let lights = [Light::Quad(light)];
+    let mean = |lights: &[Light]| {
+        const PATHS: usize = 200_000;
+        let sum: Float = (0..PATHS)
+            .map(|_| {
+                let target = Point3::new(random_scalar() - 0.5, random_scalar() - 0.5, 0.0);
+                let origin = Point3::new(0.0, 0.0, 4.0);
+                let ray = Ray::new(origin, target - origin);
+                trace_path(ray, &world, lights, &background, Some(&fog), 4)
+                    .color
+                    .x
+            })
+            .sum();
+        sum / PATHS as Float
+    };
+    let sampled = mean(&lights);
+    let unsampled = mean(&[]);
+    assert!(
+        (sampled - unsampled).abs() < 0.03 * unsampled,
+        "light through {} gives {} with light sampling and {} without",
+        name,
+        sampled,
+        unsampled
+    );
+}
+
+/// Cloud-like grid of density between 0 and 1.
+fn puffy_grid() -> Arc<DensityGrid> {
+    Arc::new(DensityGrid::from_fn([24, 24, 24], |point| {
+        let offset = point - Point3::new(0.5, 0.5, 0.5);
+        1.0 - 4.0 * offset.squared_length() + 0.3 * (point.x * 20.0).sin() * (point.y * 15.0).sin()
+    }))
+}
+
+#[test]
+fn grid_volumes_without_absorption_keep_all_light() {
+    seed_rng(43);
+    let white = Volume::from_grid(
+        puffy_grid(),
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        Medium::fog(4.0, Color::ONE).with_asymmetry(0.3),
+    );
+    let radiance = furnace_with(white.into(), None);
+    // A path now and then leaks out near the boundary like in a homogeneous medium
+    assert!(
+        (radiance - 1.0).abs() < 1e-3,
+        "a white grid volume returns {} in the furnace",
+        radiance
+    );
+}
+
+#[test]
+fn grid_volumes_attenuate_by_their_optical_depth() {
+    seed_rng(47);
+    // Absorbs along a ramp in x, through the middle of the box from one side to the other
+    let grid = Arc::new(DensityGrid::from_fn([16, 4, 4], |point| 2.0 * point.x));
+    let absorption = Color::new(0.25, 0.5, 1.0);
+    let volume = Volume::from_grid(
+        grid,
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        Medium::new(absorption, Color::ZERO),
+    );
+    let density = volume.density().unwrap().clone();
+    const STEPS: usize = 10_000;
+    let depth: Float = (0..STEPS)
+        .map(|step| {
+            let x = -1.0 + 2.0 * (step as Float + 0.5) / STEPS as Float;
+            2.0 / STEPS as Float * density.density(Point3::new(x, 0.1, 0.2))
+        })
+        .sum();
+
+    let world: HittableList<Primitive> = HittableList {
+        objects: vec![volume.into()],
+    };
+    let background = Background::Uniform(Color::ONE);
+    const PATHS: usize = 20_000;
+    let sum = (0..PATHS)
+        .map(|_| {
+            let ray = Ray::new(Point3::new(-3.0, 0.1, 0.2), Vec3::new(1.0, 0.0, 0.0));
+            trace_path(ray, &world, &[], &background, None, 10).color
+        })
+        .fold(Color::ZERO, |sum, color| sum + color);
+    let mean = sum / PATHS as Float;
+    for channel in 0..3 {
+        let expected = (-absorption[channel] * depth).exp();
+        assert!(
+            (mean[channel] - expected).abs() < 0.02,
+            "{:?} gets through the grid, expected {} in channel {}",
+            mean,
+            expected,
+            channel
+        );
+    }
+}
+
+#[test]
+fn light_sampling_through_grid_volumes_is_unbiased() {
+    seed_rng(53);
+    let cloud = Volume::from_grid(
+        puffy_grid(),
+        Point3::new(-1.0, -1.0, -1.0),
+        Point3::new(1.0, 1.0, 1.0),
+        Medium::new(Color::new(0.2, 0.4, 0.6), Color::from_float(2.0)).with_asymmetry(0.4),
+    );
+    assert_light_sampling_is_unbiased("a grid volume", cloud.into());
+}
+
+#[test]
+fn translucent_materials_without_absorption_keep_all_light() {
+    seed_rng(59);
+    let radiance = furnace(UberShader::subsurface(Color::ONE, Color::from_float(0.25)));
+    assert!(
+        (radiance - 1.0).abs() < 1e-3,
+        "a white translucent sphere returns {} in the furnace",
+        radiance
+    );
+}
+
+#[test]
+fn translucent_slabs_reflect_their_albedo() {
+    seed_rng(61);
+    // Thick compared to the mean free path and wide enough that light only comes in at the top
+    let albedo = Color::new(0.2, 0.5, 0.8);
+    let slab = Block::new(
+        Point3::new(-100.0, -100.0, -100.0),
+        Point3::new(100.0, 0.0, 100.0),
+        UberShader::subsurface(albedo, Color::from_float(0.05)),
+    );
+    let world: HittableList<Primitive> = HittableList {
+        objects: vec![slab.into()],
+    };
+    let background = Background::Uniform(Color::ONE);
+    const PATHS: usize = 20_000;
+    let sum = (0..PATHS)
+        .map(|_| {
+            let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.3, -1.0, 0.1));
+            trace_path(ray, &world, &[], &background, None, 20).color
+        })
+        .fold(Color::ZERO, |sum, color| sum + color);
+    let mean = sum / PATHS as Float;
+    for channel in 0..3 {
+        assert!(
+            (mean[channel] - albedo[channel]).abs() < 0.02,
+            "a slab of albedo {:?} reflects {:?}",
+            albedo,
+            mean
+        );
+    }
+}
+
+#[test]
+fn light_sampling_under_translucent_surfaces_is_unbiased() {
+    seed_rng(67);
+    let wax = UberShader::subsurface(Color::new(0.9, 0.7, 0.5), Color::new(0.3, 0.2, 0.1));
+    assert_light_sampling_is_unbiased("wax", Sphere::new(Point3::ORIGIN, 1.0, wax).into());
+}
+
+#[test]
+fn random_numbers_are_uniform() {
+    seed_rng(14);
+    const BINS: usize = 100;
+    let mut observed = vec![0.0; BINS];
+    for _ in 0..SAMPLES {
+        observed[(random_scalar() * BINS as Float) as usize] += 1.0;
+    }
+    assert_chi_square(
+        "random_scalar",
+        &observed,
+        &vec![SAMPLES as Float / BINS as Float; BINS],
+    );
+}
+
+/// Average of `to_rgb` of the values of `spectrum` over evenly spread hero wavelengths, which
+/// integrates the spectrum over the visible range.
+fn spectrum_to_rgb(spectrum: impl Fn(&SampledWavelengths) -> Color) -> Color {
+    const STEPS: usize = 3000;
+    let mut sum = Color::ZERO;
+    for step in 0..STEPS {
+        let wavelengths = SampledWavelengths::new((step as Float + 0.5) / STEPS as Float);
+        sum += wavelengths.to_rgb(spectrum(&wavelengths));
+    }
+    sum / STEPS as Float
+}
+
+#[test]
+fn spectra_of_rgb_colors_round_trip() {
+    let white = spectrum_to_rgb(|_| Color::ONE);
+    assert!(
+        color_distance(white, Color::ONE) < 1e-3,
+        "a flat spectrum is {:?}",
+        white
+    );
+
+    let colors = [
+        Color::new(0.8, 0.3, 0.2),
+        Color::new(0.1, 0.6, 0.3),
+        Color::new(0.2, 0.3, 0.9),
+        Color::new(0.5, 0.5, 0.5),
+    ];
+    for color in colors {
+        let reflectance = spectrum_to_rgb(|wavelengths| wavelengths.reflectance(color));
+        assert!(
+            color_distance(reflectance, color) < 1e-3,
+            "reflectance {:?} comes back as {:?}",
+            color,
+            reflectance
+        );
+        let light = spectrum_to_rgb(|wavelengths| wavelengths.unbounded(5.0 * color));
+        assert!(
+            color_distance(light, 5.0 * color) < 5e-3,
+            "light {:?} comes back as {:?}",
+            5.0 * color,
+            light
+        );
+    }
+
+    // The luminance matching function peaks in the green and vanishes at the ends of the range
+    assert!(cie_xyz(555.0).y > 0.95 && cie_xyz(555.0).y < 1.05);
+    assert!(cie_xyz(380.0).y < 1e-3 && cie_xyz(780.0).y < 1e-3);
+}
+
+#[test]
+fn abbe_numbers_set_the_dispersion_of_glass() {
+    let flint = UberShader::dielectric(1.62).with_abbe_number(36.0);
+    let index = |wavelength| flint.refraction_index(wavelength).unwrap();
+    assert!((index(587.56) - 1.62).abs() < tolerance(1e-12));
+    let abbe_number = (index(587.56) - 1.0) / (index(486.13) - index(656.27));
+    // The difference of two close indices keeps few digits of an f32
+    let allowed = match cfg!(feature = "f32") {
+        true => 1e-3,
+        false => 1e-9,
+    };
+    assert!(
+        (abbe_number - 36.0).abs() < allowed,
+        "abbe number {}",
+        abbe_number
+    );
+    assert!(flint.is_dispersive());
+    assert!(!UberShader::dielectric(1.5).is_dispersive());
+    assert!(UberShader::new(Color::ONE, false, 0.0)
+        .with_abbe_number(36.0)
+        .refraction_index(500.0)
+        .is_none());
+}
+
+#[test]
+fn spectral_paths_agree_with_rgb_paths() {
+    seed_rng(29);
+    let world: HittableList<Primitive> = HittableList {
+        objects: vec![
+            Sphere::new(
+                Point3::new(-1.5, 0.0, 0.0),
+                1.0,
+                UberShader::dielectric(1.5),
+            )
+            .into(),
+            Sphere::new(
+                Point3::new(1.5, 0.0, 0.0),
+                1.0,
+                UberShader::dielectric(1.7).with_abbe_number(20.0),
+            )
+            .into(),
+            Sphere::new(
+                Point3::new(0.0, 0.0, -1.5),
+                1.0,
+                UberShader::new(Color::new(0.8, 0.3, 0.2), false, 0.0),
+            )
+            .into(),
+        ],
+    };
+    let background = Background::Uniform(Color::new(0.9, 0.7, 0.5));
+    const PATHS: usize = 100_000;
+    let mut rgb = Color::ZERO;
+    let mut spectral = Color::ZERO;
+    for _ in 0..PATHS {
+        let origin = Point3::ORIGIN + 4.0 * Vec3::random_point_in_unit_vector();
+        let target = Point3::ORIGIN + 1.5 * Vec3::random_point_in_unit_sphere();
+        let ray = Ray::new(origin, target - origin);
+        rgb += trace_path(ray, &world, &[], &background, None, 50).color;
+        let wavelengths = SampledWavelengths::sample();
+        spectral +=
+            trace_spectral_path(ray, &world, &[], &background, None, &wavelengths, 50).color;
+    }
+    let (rgb, spectral) = (rgb / PATHS as Float, spectral / PATHS as Float);
+    assert!(
+        color_distance(rgb, spectral) < 0.02,
+        "RGB paths average {:?} and spectral paths {:?}",
+        rgb,
+        spectral
+    );
+}